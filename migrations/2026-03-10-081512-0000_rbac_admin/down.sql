@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+-- до up.sql у пользователя была одна роль: оставляем самую раннюю, иначе уникальный индекс не создать
+DELETE FROM user_roles ur
+USING user_roles older
+WHERE older.user_id = ur.user_id
+  AND (older.assigned_at, older.role_id) < (ur.assigned_at, ur.role_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS ux_user_roles_user_id ON user_roles(user_id);
+
+DELETE FROM permissions WHERE key = 'roles.manage';
@@ -0,0 +1,14 @@
+-- Управление ролями через admin API
+INSERT INTO permissions (key) VALUES
+  ('roles.manage')
+ON CONFLICT (key) DO NOTHING;
+
+-- admin получает новое право (seed_rbac выдал только существовавшие на тот момент)
+INSERT INTO role_permissions(role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r, permissions p
+WHERE r.key='admin' AND p.key IN ('roles.manage')
+ON CONFLICT DO NOTHING;
+
+-- пользователь может иметь несколько ролей (assign/revoke через API)
+DROP INDEX IF EXISTS ux_user_roles_user_id;
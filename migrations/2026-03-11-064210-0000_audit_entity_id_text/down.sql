@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_audit_action;
+DROP INDEX IF EXISTS idx_audit_actor;
+
+DELETE FROM audit_log WHERE entity_id !~ '^[0-9a-fA-F-]{36}$';
+
+ALTER TABLE audit_log
+  ALTER COLUMN entity_id TYPE UUID USING entity_id::uuid;
@@ -0,0 +1,6 @@
+-- social_post_jobs / social_accounts имеют BIGINT id, поэтому entity_id храним как текст
+ALTER TABLE audit_log
+  ALTER COLUMN entity_id TYPE TEXT USING entity_id::text;
+
+CREATE INDEX IF NOT EXISTS idx_audit_actor ON audit_log(actor_id);
+CREATE INDEX IF NOT EXISTS idx_audit_action ON audit_log(action);
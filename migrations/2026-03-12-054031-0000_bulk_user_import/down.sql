@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM role_permissions
+WHERE permission_id IN (SELECT id FROM permissions WHERE key = 'users.import');
+DELETE FROM permissions WHERE key = 'users.import';
+
+DROP TABLE IF EXISTS class_members;
+DROP TABLE IF EXISTS school_classes;
+
+ALTER TABLE local_credentials DROP COLUMN IF EXISTS must_change_password;
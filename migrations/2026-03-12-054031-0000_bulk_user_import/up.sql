@@ -0,0 +1,29 @@
+-- Массовый импорт пользователей: одноразовые пароли и классы
+
+-- пароль выдан администратором и должен быть сменён при первом входе
+ALTER TABLE local_credentials
+  ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE school_classes (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name TEXT NOT NULL UNIQUE,
+  created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE TABLE class_members (
+  class_id UUID NOT NULL REFERENCES school_classes(id) ON DELETE CASCADE,
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  added_at TIMESTAMP NOT NULL DEFAULT now(),
+  PRIMARY KEY (class_id, user_id)
+);
+
+CREATE INDEX idx_class_members_user ON class_members(user_id);
+
+INSERT INTO permissions (key) VALUES ('users.import')
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r, permissions p
+WHERE r.key = 'admin' AND p.key = 'users.import'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_users_active;
+
+ALTER TABLE users
+  DROP COLUMN IF EXISTS deleted_at,
+  DROP COLUMN IF EXISTS is_active;
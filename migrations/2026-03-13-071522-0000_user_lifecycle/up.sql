@@ -0,0 +1,8 @@
+-- Жизненный цикл пользователя вместо hard delete:
+-- is_active = false  -> заблокирован (вход и сессии запрещены)
+-- deleted_at         -> удалён (soft delete); строка остаётся ради FK из content_items/subjects/audit_log
+ALTER TABLE users
+  ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE,
+  ADD COLUMN deleted_at TIMESTAMP NULL;
+
+CREATE INDEX idx_users_active ON users(is_active) WHERE deleted_at IS NULL;
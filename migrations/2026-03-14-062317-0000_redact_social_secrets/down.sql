@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+-- Маскирование необратимо.
+SELECT 1;
@@ -0,0 +1,19 @@
+-- Токены, попавшие в тексты ошибок и ответы провайдеров до маскирования.
+-- access_token=... / refresh_token=... в URL и "access_token":"..." в JSON, /bot<id>:<token>/ у Telegram.
+UPDATE social_post_jobs
+SET error_message = regexp_replace(
+        regexp_replace(error_message, '((access|refresh)_token=)[^&"\s)]+', '\1****', 'g'),
+        '/bot[0-9]+:[A-Za-z0-9_-]+', '/bot****', 'g')
+WHERE error_message ~ '(access|refresh)_token=|/bot[0-9]+:';
+
+UPDATE social_post_attempts
+SET error_message = regexp_replace(
+        regexp_replace(error_message, '((access|refresh)_token=)[^&"\s)]+', '\1****', 'g'),
+        '/bot[0-9]+:[A-Za-z0-9_-]+', '/bot****', 'g')
+WHERE error_message ~ '(access|refresh)_token=|/bot[0-9]+:';
+
+UPDATE social_post_attempts
+SET response_body = regexp_replace(
+        regexp_replace(response_body, '("(access|refresh)_token"\s*:\s*")[^"]*', '\1****', 'g'),
+        '((access|refresh)_token=)[^&"\s)]+', '\1****', 'g')
+WHERE response_body ~ '(access|refresh)_token';
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS admin_notifications;
+
+ALTER TABLE social_accounts
+    DROP COLUMN IF EXISTS auth_error_at,
+    DROP COLUMN IF EXISTS auth_error,
+    DROP COLUMN IF EXISTS token_refreshed_at;
@@ -0,0 +1,20 @@
+-- Автообновление долгоживущих токенов Instagram/Threads и флаг проблем с авторизацией
+ALTER TABLE social_accounts
+    ADD COLUMN token_refreshed_at TIMESTAMPTZ,
+    ADD COLUMN auth_error TEXT,
+    ADD COLUMN auth_error_at TIMESTAMPTZ;
+
+-- Уведомления для админов (сбои фоновых задач и т.п.)
+CREATE TABLE admin_notifications (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT,
+    entity_type TEXT,
+    entity_id TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    read_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_admin_notifications_unread
+    ON admin_notifications(created_at DESC) WHERE read_at IS NULL;
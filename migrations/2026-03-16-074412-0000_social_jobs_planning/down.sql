@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_social_post_jobs_scheduled_for;
+
+DELETE FROM role_permissions
+WHERE permission_id IN (SELECT id FROM permissions WHERE key = 'social.publish');
+DELETE FROM permissions WHERE key = 'social.publish';
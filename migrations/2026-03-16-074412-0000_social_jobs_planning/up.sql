@@ -0,0 +1,12 @@
+-- Планирование публикаций новостей в соцсети: редакторы и админы
+INSERT INTO permissions (key) VALUES ('social.publish')
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id
+FROM roles r, permissions p
+WHERE r.key IN ('admin', 'editor') AND p.key = 'social.publish'
+ON CONFLICT DO NOTHING;
+
+CREATE INDEX IF NOT EXISTS idx_social_post_jobs_scheduled_for
+    ON social_post_jobs(scheduled_for DESC);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+UPDATE social_post_jobs SET status = 'failed', updated_at = now() WHERE status = 'dead';
+
+ALTER TABLE social_post_attempts DROP COLUMN IF EXISTS error_kind;
@@ -0,0 +1,7 @@
+-- Класс ошибки попытки: rate_limited / auth / permanent / transient
+ALTER TABLE social_post_attempts ADD COLUMN error_kind TEXT;
+
+-- Раньше failed без next_retry_at подхватывались worker'ом бесконечно; теперь это dead
+UPDATE social_post_jobs
+SET status = 'dead', updated_at = now()
+WHERE status = 'failed' AND next_retry_at IS NULL;
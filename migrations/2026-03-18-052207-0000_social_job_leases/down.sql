@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_social_post_jobs_locked_until;
+
+ALTER TABLE social_post_jobs
+    DROP CONSTRAINT IF EXISTS social_post_jobs_idempotency_key_key;
+
+ALTER TABLE social_post_jobs
+    DROP COLUMN IF EXISTS publish_state,
+    DROP COLUMN IF EXISTS publish_started_at,
+    DROP COLUMN IF EXISTS idempotency_key,
+    DROP COLUMN IF EXISTS locked_until,
+    DROP COLUMN IF EXISTS locked_by;
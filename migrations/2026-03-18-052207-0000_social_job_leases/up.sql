@@ -0,0 +1,25 @@
+-- Аренда job'а воркером: пока идёт публикация, воркер продлевает locked_until.
+-- Истёкшую аренду (воркер упал) reaper возвращает в очередь.
+ALTER TABLE social_post_jobs
+    ADD COLUMN locked_by TEXT,
+    ADD COLUMN locked_until TIMESTAMPTZ,
+    -- стабильный ключ для всех попыток job'а (VK guid и т.п.)
+    ADD COLUMN idempotency_key UUID NOT NULL DEFAULT gen_random_uuid(),
+    -- запрос к провайдеру мог уйти: следующая попытка сначала выясняет, вышел ли пост
+    ADD COLUMN publish_started_at TIMESTAMPTZ,
+    -- промежуточное состояние адаптера (id контейнера Meta)
+    ADD COLUMN publish_state JSONB;
+
+ALTER TABLE social_post_jobs
+    ADD CONSTRAINT social_post_jobs_idempotency_key_key UNIQUE (idempotency_key);
+
+CREATE INDEX idx_social_post_jobs_locked_until
+    ON social_post_jobs (locked_until)
+    WHERE status = 'processing';
+
+-- зависшие до миграции job'ы reaper подберёт на первом проходе;
+-- был ли отправлен запрос, неизвестно, поэтому считаем, что мог
+UPDATE social_post_jobs
+SET locked_until = now(),
+    publish_started_at = updated_at
+WHERE status = 'processing';
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS idx_social_post_jobs_campaign_id;
+
+ALTER TABLE social_post_jobs
+    DROP COLUMN IF EXISTS campaign_id;
+
+DROP TABLE IF EXISTS social_campaign_accounts;
+DROP TABLE IF EXISTS social_campaigns;
@@ -0,0 +1,32 @@
+-- Регулярные кампании вместо захардкоженных *_hourly: по cron ставят job'ы в social_post_jobs.
+CREATE TABLE social_campaigns (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- 5 полей (мин час день месяц день_недели) или 6 с секундами
+    cron_expr TEXT NOT NULL,
+    -- IANA, в ней считается cron
+    timezone TEXT NOT NULL DEFAULT 'UTC',
+    -- {"type": "next_unposted", "kind": "idiom", "level": "B2"} | {"type": "random_hot", ...}
+    content_source JSONB NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_social_campaigns_next_run_at
+    ON social_campaigns (next_run_at)
+    WHERE is_active;
+
+CREATE TABLE social_campaign_accounts (
+    campaign_id BIGINT NOT NULL REFERENCES social_campaigns(id) ON DELETE CASCADE,
+    social_account_id BIGINT NOT NULL REFERENCES social_accounts(id) ON DELETE CASCADE,
+    PRIMARY KEY (campaign_id, social_account_id)
+);
+
+ALTER TABLE social_post_jobs
+    ADD COLUMN campaign_id BIGINT REFERENCES social_campaigns(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_social_post_jobs_campaign_id ON social_post_jobs (campaign_id);
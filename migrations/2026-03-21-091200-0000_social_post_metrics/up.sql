@@ -0,0 +1,15 @@
+-- Последний снимок вовлечённости по опубликованному job'у; обновляет сборщик метрик.
+CREATE TABLE social_post_metrics (
+    social_post_job_id BIGINT PRIMARY KEY REFERENCES social_post_jobs(id) ON DELETE CASCADE,
+    -- NULL — провайдер эту метрику не отдаёт
+    views BIGINT,
+    likes BIGINT,
+    reactions BIGINT,
+    comments BIGINT,
+    shares BIGINT,
+    raw JSONB,
+    last_error TEXT,
+    collected_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_social_post_metrics_collected_at ON social_post_metrics (collected_at);
@@ -0,0 +1,42 @@
+-- Интервальное повторение карточек: общее для веба и Telegram-бота.
+CREATE TABLE quizlet_card_progress (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    card_id UUID NOT NULL REFERENCES quizlet_cards(id) ON DELETE CASCADE,
+    -- коробка Лейтнера: 0 — не знает, чем больше, тем реже повторять
+    box_no INT NOT NULL DEFAULT 0,
+    correct_count INT NOT NULL DEFAULT 0,
+    wrong_count INT NOT NULL DEFAULT 0,
+    due_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_reviewed_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, card_id)
+);
+
+CREATE INDEX idx_quizlet_card_progress_due ON quizlet_card_progress (user_id, due_at);
+
+-- Школьный аккаунт <-> пользователь Telegram
+CREATE TABLE telegram_links (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    telegram_user_id BIGINT NOT NULL UNIQUE,
+    chat_id BIGINT NOT NULL,
+    telegram_username TEXT,
+    linked_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Одноразовые коды привязки: выдаются на сайте, вводятся боту
+CREATE TABLE telegram_link_codes (
+    code TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_telegram_link_codes_user ON telegram_link_codes (user_id);
+
+-- Отправленные /quiz: по poll_answer находим карточку и правильный вариант
+CREATE TABLE telegram_quiz_polls (
+    poll_id TEXT PRIMARY KEY,
+    chat_id BIGINT NOT NULL,
+    card_id UUID NOT NULL REFERENCES quizlet_cards(id) ON DELETE CASCADE,
+    correct_option INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
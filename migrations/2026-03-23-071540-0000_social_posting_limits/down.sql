@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_social_post_jobs_account_published;
+
+ALTER TABLE social_post_jobs
+    DROP COLUMN IF EXISTS priority;
@@ -0,0 +1,8 @@
+-- Приоритет job'а: из наступивших первыми уходят job'ы с большим приоритетом.
+ALTER TABLE social_post_jobs
+    ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+-- лимиты аккаунта (settings_json.posting) считаются по публикациям за последние сутки
+CREATE INDEX idx_social_post_jobs_account_published
+    ON social_post_jobs (social_account_id, published_at DESC)
+    WHERE published_at IS NOT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE social_post_jobs
+    DROP COLUMN IF EXISTS published_state;
@@ -0,0 +1,4 @@
+-- Что адаптер запомнил о вышедшем посте для правки/удаления: id всех сообщений
+-- альбома Telegram, вид медиа и т.п. У постов, вышедших раньше, NULL.
+ALTER TABLE social_post_jobs
+    ADD COLUMN published_state JSONB;
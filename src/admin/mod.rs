@@ -1,4 +1,2 @@
 pub mod routes;
-pub mod middleware;
 pub mod views;
-pub mod api;
\ No newline at end of file
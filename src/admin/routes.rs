@@ -1,229 +1,237 @@
 use axum::{
-    extract::{Form, State, Extension, Path},
-    response::{Html, IntoResponse, Redirect,  Json},
+    extract::{State, Query, Extension},
+    response::Html,
     http::StatusCode,
 };
 use serde::Deserialize;
-use tower_cookies::{Cookies, Cookie};
-use uuid::Uuid;
 
 use diesel::prelude::*;
-use crate::{AppState, models::User};
-use crate::schema::users::dsl::*;
-use crate::{models::{NewUser,UpdateUser}};
+use crate::AppState;
+use crate::schema::school_classes;
 
-use crate::schema::{roles, user_roles};
-use crate:: auth::context::AuthContext;
-use crate::models::audit::write_audit;
-use crate::admin::views::{UserRow, AdminUsersTemplate};
+use crate::admin::views::{
+    UserRow, AdminUsersTemplate, AdminRolesTemplate, AuditRow, AdminAuditTemplate, SocialJobRow,
+    AdminSocialJobsTemplate,
+};
+use crate::api::admin::roles::{load_roles, load_permission_keys};
+use crate::api::admin::audit::{AuditQuery, load_audit_page};
+use crate::api::admin::social_jobs::{JobsQuery, SOCIAL_PUBLISH_PERMISSION, load_jobs_page};
+use crate::api::admin::users::{UsersQuery, load_users_page};
+use crate::auth::context::AuthContext;
 
 use askama::Template;
 
-#[derive(Deserialize)]
-pub struct AssignRoleReq {
-    pub user_id: Uuid,
-    pub role_key: String,
+fn users_query_string(q: &UsersQuery, page: i64) -> String {
+    let mut ser = url::form_urlencoded::Serializer::new(String::new());
+    let fields = [
+        ("q", q.q.as_deref()),
+        ("role", q.role.as_deref()),
+        ("class", q.class.as_deref()),
+        ("active", q.active.as_deref()),
+        ("include_deleted", q.include_deleted.as_deref()),
+        ("sort", q.sort.as_deref()),
+        ("order", q.order.as_deref()),
+    ];
+    for (k, v) in fields {
+        if let Some(v) = v.filter(|v| !v.trim().is_empty()) {
+            ser.append_pair(k, v);
+        }
+    }
+    ser.append_pair("page", &page.to_string());
+    if let Some(pp) = q.per_page {
+        ser.append_pair("per_page", &pp.to_string());
+    }
+    ser.finish()
 }
 
-pub async fn assign_role(
+pub async fn admin_users_page(
     State(state): State<AppState>,
-    Extension(ctx): Extension<AuthContext>,
-    Json(req): Json<AssignRoleReq>,
-) -> Result<StatusCode, StatusCode> {
-    if !ctx.has_perm("roles.assign") {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
+    Query(q): Query<UsersQuery>,
+) -> Result<Html<String>, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
-    // найти роль
-    let role_id: Uuid = roles::table
-        .filter(roles::key.eq(&req.role_key))
-        .select(roles::id)
-        .first(&mut conn)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
-
-    // вставить связь (idempotent)
-    diesel::insert_into(user_roles::table)
-        .values((
-            user_roles::user_id.eq(req.user_id),
-            user_roles::role_id.eq(role_id),
-            user_roles::assigned_by.eq(Some(ctx.user_id)),
-        ))
-        .on_conflict_do_nothing()
-        .execute(&mut conn)
+    let page = load_users_page(&mut conn, &q)?;
+
+    let role_keys = load_roles(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|r| r.key)
+        .collect();
+    let class_names = school_classes::table
+        .select(school_classes::name)
+        .order(school_classes::name.asc())
+        .load::<String>(&mut conn)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        write_audit(
-            &mut conn,
-            Some(ctx.user_id),
-            "role.assign",
-            "user",
-            req.user_id,
-            Some(serde_json::json!({ "role_key": req.role_key })),
-        ).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let rows = page.users.into_iter().map(|u| UserRow {
+        id: u.id,
+        username: u.username.unwrap_or_default(),
+        full_name: u.full_name.unwrap_or_default(),
+        email: u.email.unwrap_or_default(),
+        roles: u.roles.join(", "),
+        classes: u.classes.join(", "),
+        status: if u.deleted_at.is_some() {
+            "deleted"
+        } else if u.is_active {
+            "active"
+        } else {
+            "inactive"
+        }
+        .to_string(),
+        created_at: u.created_at.map(|d| d.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default(),
+    }).collect();
 
-    Ok(StatusCode::NO_CONTENT)
-}
+    let last_page = ((page.total + page.per_page - 1) / page.per_page).max(1);
+    let tpl = AdminUsersTemplate {
+        users: rows,
+        total: page.total,
+        page: page.page,
+        last_page,
+        prev_url: if page.page > 1 {
+            format!("/admin/users?{}", users_query_string(&q, page.page - 1))
+        } else {
+            String::new()
+        },
+        next_url: if page.page < last_page {
+            format!("/admin/users?{}", users_query_string(&q, page.page + 1))
+        } else {
+            String::new()
+        },
+        role_keys,
+        class_names,
+        q,
+    };
 
-#[derive(Deserialize)]
-pub struct AdminLoginForm {
-    pub username: String,
-    pub password: String,
+    Ok(Html(tpl.render().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
 }
 
-pub async fn admin_index(cookies: Cookies) -> impl IntoResponse {
-    let ok = cookies.get("admin_session").map(|c| c.value() == "ok").unwrap_or(false);
-    if ok {
-        Redirect::to("/admin/users")
-    } else {
-        Redirect::to("/admin/login")
-    }
-}
+pub async fn admin_roles_page(
+    State(state): State<AppState>,
+) -> Result<Html<String>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
-pub async fn admin_login_page() -> Html<&'static str> {
-    Html(r#"<!doctype html>
-<html>
-<head><meta charset="utf-8"><title>Admin Login</title></head>
-<body style="font-family: sans-serif; max-width: 420px; margin: 40px auto;">
-  <h2>Admin Login</h2>
-  <form method="post" action="/admin/login">
-    <div style="margin: 8px 0;">
-      <label>Username</label><br/>
-      <input name="username" style="width: 100%; padding: 8px;" />
-    </div>
-    <div style="margin: 8px 0;">
-      <label>Password</label><br/>
-      <input type="password" name="password" style="width: 100%; padding: 8px;" />
-    </div>
-    <button type="submit" style="padding: 10px 14px;">Login</button>
-  </form>
-</body>
-</html>"#)
-}
+    let roles = load_roles(&mut conn).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let permissions = load_permission_keys(&mut conn).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-pub async fn admin_login_submit( // обработка локальной формы логина
-    cookies: Cookies,
-    Form(form): Form<AdminLoginForm>,
-) -> Result<impl IntoResponse, StatusCode> {
-    // Минимально: креды из env
-    let admin_user = std::env::var("ADMIN_USERNAME").unwrap_or_else(|_| "admin".to_string());
-    let admin_pass = std::env::var("ADMIN_PASSWORD").unwrap_or_else(|_| "admin".to_string());
-
-    if form.username == admin_user && form.password == admin_pass {
-        let mut c = Cookie::new("admin_session", "ok");
-        c.set_http_only(true);
-        c.set_path("/");
-        cookies.add(c);
-        Ok(Redirect::to("/admin/users").into_response())
-    } else {
-        Ok((StatusCode::UNAUTHORIZED, Html("Invalid credentials")).into_response())
-    }
-}
+    let tpl = AdminRolesTemplate { roles, permissions };
 
-pub async fn admin_logout(cookies: Cookies) -> impl IntoResponse {// обработка локальной формы логина
-    let mut c = Cookie::new("admin_session", "");
-    c.set_path("/");
-    cookies.remove(c);
-    Redirect::to("/admin/login")
+    Ok(Html(tpl.render().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
 }
 
-pub async fn get_user(
-    Path(user_id): Path<Uuid>,
-    State(state): State<AppState>,
-) -> Json<User> {
-    let mut conn = state.pool.get().unwrap();
-
-    let user = users
-        .find(user_id)
-        .first::<User>(&mut conn)
-        .expect("User not found");
-
-    Json(user)
+fn audit_query_string(q: &AuditQuery, cursor: Option<&str>) -> String {
+    let mut ser = url::form_urlencoded::Serializer::new(String::new());
+    let fields = [
+        ("actor_id", q.actor_id.as_deref()),
+        ("action", q.action.as_deref()),
+        ("entity_type", q.entity_type.as_deref()),
+        ("entity_id", q.entity_id.as_deref()),
+        ("from", q.from.as_deref()),
+        ("to", q.to.as_deref()),
+        ("cursor", cursor),
+    ];
+    for (k, v) in fields {
+        if let Some(v) = v.filter(|v| !v.trim().is_empty()) {
+            ser.append_pair(k, v);
+        }
+    }
+    ser.finish()
 }
 
-pub async fn create_user(
+pub async fn admin_audit_page(
     State(state): State<AppState>,
-    Json(new_user): Json<NewUser>,
-) -> Json<User> {
-    let mut conn = state.pool.get().unwrap();
-
-    let user = diesel::insert_into(users)
-        .values(&new_user)
-        .get_result::<User>(&mut conn)
-        .expect("Failed to insert user");
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<AuditQuery>,
+) -> Result<Html<String>, StatusCode> {
+    if !ctx.has_perm("content.audit.read") {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    Json(user)
-}
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
-pub async fn update_user(
-    Path(user_id): Path<Uuid>,
-    State(state): State<AppState>,
-    Json(update): Json<UpdateUserDb>,
-) -> Json<User> {
-    let mut conn = state.pool.get().unwrap();
+    let page = load_audit_page(&mut conn, &q)?;
+
+    let rows = page.items.into_iter().map(|e| AuditRow {
+        created_at: e.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        actor: e.actor_username
+            .or_else(|| e.actor_id.map(|a| a.to_string()))
+            .unwrap_or_else(|| "system".to_string()),
+        action: e.action,
+        entity_type: e.entity_type,
+        entity_id: e.entity_id,
+        metadata: e.metadata.map(|m| m.to_string()).unwrap_or_default(),
+    }).collect();
 
-    let updated_user = diesel::update(users.find(user_id))
-        .set(&update)
-        .get_result::<User>(&mut conn)
-        .expect("Failed to update user");
+    let tpl = AdminAuditTemplate {
+        rows,
+        next_url: page.next_cursor
+            .map(|c| format!("/admin/audit?{}", audit_query_string(&q, Some(&c))))
+            .unwrap_or_default(),
+        export_url: format!("/api/admin/audit/export?{}", audit_query_string(&q, None)),
+        q,
+    };
 
-    Json(updated_user)
+    Ok(Html(tpl.render().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
 }
 
-// DELETE /users/:id
-pub async fn delete_user(
-    Path(user_id): Path<Uuid>,
-    State(state): State<AppState>,
-) -> Json<&'static str> {
-    let mut conn = state.pool.get().unwrap();
-
-    diesel::delete(users.find(user_id))
-        .execute(&mut conn)
-        .expect("Failed to delete user");
+#[derive(Deserialize)]
+pub struct SocialJobsPageQuery {
+    pub status: Option<String>,
+    pub provider: Option<String>,
+    pub page: Option<i64>,
+}
 
-    Json("User deleted")
+fn social_jobs_url(status: &str, provider: &str, page: i64) -> String {
+    let mut ser = url::form_urlencoded::Serializer::new(String::new());
+    for (k, v) in [("status", status), ("provider", provider)] {
+        if !v.is_empty() {
+            ser.append_pair(k, v);
+        }
+    }
+    ser.append_pair("page", &page.to_string());
+    format!("/admin/social/jobs?{}", ser.finish())
 }
 
-pub async fn admin_users_page(
+// Запланированные публикации; действия (планирование, отмена, перенос) — через /api/admin/social/jobs
+pub async fn admin_social_jobs_page(
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<SocialJobsPageQuery>,
 ) -> Result<Html<String>, StatusCode> {
-    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    if !ctx.has_perm(SOCIAL_PUBLISH_PERMISSION) {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    let list = users
-        .select(User::as_select())
-        .load::<User>(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
-    // Простая HTML-таблица
-    let rows = list.into_iter().map(|u| UserRow {
-        id: u.id,
-        auth0_id: u.auth0_id,
-        email: u.email.unwrap_or_default(),
-        created_at: u.created_at.map(|d| d.to_string()).unwrap_or_default(),
+    let status = q.status.unwrap_or_default().trim().to_string();
+    let provider = q.provider.unwrap_or_default().trim().to_string();
+    let params = JobsQuery {
+        status: Some(status.clone()),
+        provider: Some(provider.clone()),
+        page: q.page,
+        ..Default::default()
+    };
+    let page = load_jobs_page(&mut conn, &params)?;
+
+    let rows = page.jobs.into_iter().map(|j| SocialJobRow {
+        id: j.id,
+        scheduled_for: j.scheduled_for.format("%Y-%m-%d %H:%M").to_string(),
+        news_title: j.news_title,
+        account: format!("{} · {}", j.provider, j.account_name),
+        status: j.status,
+        retry_count: j.retry_count,
+        error: j.error_message.unwrap_or_default(),
     }).collect();
 
-    let tpl = AdminUsersTemplate {
-        users: rows,
-        q: "".to_string(), // позже сюда подставим search query
+    let has_next = page.page * page.per_page < page.total;
+    let tpl = AdminSocialJobsTemplate {
+        rows,
+        total: page.total,
+        prev_url: if page.page > 1 { social_jobs_url(&status, &provider, page.page - 1) } else { String::new() },
+        next_url: if has_next { social_jobs_url(&status, &provider, page.page + 1) } else { String::new() },
+        status,
+        provider,
     };
 
     Ok(Html(tpl.render().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?))
 }
-
-// минимальный экранировщик
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-     .replace('<', "&lt;")
-     .replace('>', "&gt;")
-     .replace('"', "&quot;")
-     .replace('\'', "&#39;")
-}
-
-pub async fn admin_roles_page() -> Html<&'static str> {
-    Html("Roles page")
-}
-
-pub async fn admin_audit_page() -> Html<&'static str> {
-    Html("Audit page")
-}
\ No newline at end of file
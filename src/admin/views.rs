@@ -1,12 +1,19 @@
 use askama::Template;
-use crate::models::User;
+use crate::api::admin::roles::RoleDto;
+use crate::api::admin::audit::AuditQuery;
+use crate::api::admin::users::UsersQuery;
+use crate::api::admin::users_import::ImportedCredential;
 use uuid::Uuid;
 
 #[derive(Clone,Debug)]
 pub struct UserRow {
     pub id: Uuid,
-    pub auth0_id: String,
+    pub username: String,
+    pub full_name: String,
     pub email: String,
+    pub roles: String,
+    pub classes: String,
+    pub status: String,
     pub created_at: String,
 }
 
@@ -20,5 +27,67 @@ pub struct AdminLoginTemplate {
 #[template(path = "admin_users.html")]
 pub struct AdminUsersTemplate {
     pub users: Vec<UserRow>,
-    pub q: String,
-}
\ No newline at end of file
+    pub q: UsersQuery,
+    pub total: i64,
+    pub page: i64,
+    pub last_page: i64,
+    pub prev_url: String,
+    pub next_url: String,
+    pub role_keys: Vec<String>,
+    pub class_names: Vec<String>,
+}
+#[derive(Template)]
+#[template(path = "admin_roles.html")]
+pub struct AdminRolesTemplate {
+    pub roles: Vec<RoleDto>,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditRow {
+    pub created_at: String,
+    pub actor: String,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub metadata: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin_audit.html")]
+pub struct AdminAuditTemplate {
+    pub rows: Vec<AuditRow>,
+    pub q: AuditQuery,
+    pub next_url: String,
+    pub export_url: String,
+}
+
+/// Печатный лист с логинами и одноразовыми паролями после массового импорта.
+#[derive(Template)]
+#[template(path = "credentials_sheet.html")]
+pub struct CredentialsSheetTemplate {
+    pub generated_at: String,
+    pub rows: Vec<ImportedCredential>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SocialJobRow {
+    pub id: i64,
+    pub scheduled_for: String,
+    pub news_title: String,
+    pub account: String,
+    pub status: String,
+    pub retry_count: i32,
+    pub error: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin_social_jobs.html")]
+pub struct AdminSocialJobsTemplate {
+    pub rows: Vec<SocialJobRow>,
+    pub status: String,
+    pub provider: String,
+    pub total: i64,
+    pub prev_url: String,
+    pub next_url: String,
+}
@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Extension, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json},
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use diesel::PgConnection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::context::AuthContext,
+    models::audit::{query_audit, AuditCursor, AuditEvent, AuditFilter},
+};
+
+const AUDIT_READ_PERMISSION: &str = "content.audit.read";
+const CSV_EXPORT_MAX_ROWS: i64 = 50_000;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<String>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    /// RFC3339, `YYYY-MM-DDTHH:MM` (UTC) или `YYYY-MM-DD`
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditPage {
+    pub items: Vec<AuditEvent>,
+    pub next_cursor: Option<String>,
+}
+
+fn non_empty(v: &Option<String>) -> Option<String> {
+    v.as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+// пустые поля из HTML-формы считаем "фильтр не задан"
+fn parse_uuid(v: &Option<String>) -> Result<Option<Uuid>, StatusCode> {
+    non_empty(v)
+        .map(|s| s.parse().map_err(|_| StatusCode::BAD_REQUEST))
+        .transpose()
+}
+
+fn parse_time(v: &Option<String>) -> Result<Option<NaiveDateTime>, StatusCode> {
+    let Some(s) = non_empty(v) else {
+        return Ok(None);
+    };
+
+    if let Ok(d) = DateTime::parse_from_rfc3339(&s) {
+        return Ok(Some(d.with_timezone(&Utc).naive_utc()));
+    }
+    if let Ok(d) = NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M") {
+        return Ok(Some(d));
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        return Ok(d.and_hms_opt(0, 0, 0));
+    }
+
+    Err(StatusCode::BAD_REQUEST)
+}
+
+impl AuditQuery {
+    pub fn filter(&self) -> Result<AuditFilter, StatusCode> {
+        Ok(AuditFilter {
+            actor_id: parse_uuid(&self.actor_id)?,
+            action: non_empty(&self.action),
+            entity_type: non_empty(&self.entity_type),
+            entity_id: non_empty(&self.entity_id),
+            from: parse_time(&self.from)?,
+            to: parse_time(&self.to)?,
+        })
+    }
+}
+
+/// Одна страница audit_log (используется JSON API и askama-страницей).
+pub fn load_audit_page(conn: &mut PgConnection, q: &AuditQuery) -> Result<AuditPage, StatusCode> {
+    let after = match non_empty(&q.cursor) {
+        Some(c) => Some(AuditCursor::decode(&c).ok_or(StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let limit = q.limit.unwrap_or(50).clamp(1, 500);
+
+    // +1 запись, чтобы понять, есть ли следующая страница
+    let mut items = query_audit(conn, &q.filter()?, after, limit + 1).map_err(|e| {
+        eprintln!("audit query error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|e| {
+            AuditCursor {
+                created_at: e.created_at,
+                id: e.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(AuditPage { items, next_cursor })
+}
+
+/// В audit_log лежит пользовательский текст (логины, названия, диффы): Excel исполнил бы
+/// ячейку, начинающуюся с `=`, `+`, `-` или `@`, как формулу. Апостроф делает её текстом.
+fn csv_cell(s: String) -> String {
+    if s.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{s}")
+    } else {
+        s
+    }
+}
+
+fn audit_csv(items: &[AuditEvent]) -> Result<String, csv::Error> {
+    let mut out = csv::Writer::from_writer(Vec::new());
+    out.write_record([
+        "id",
+        "created_at",
+        "actor_id",
+        "actor_username",
+        "action",
+        "entity_type",
+        "entity_id",
+        "metadata",
+    ])?;
+    for e in items {
+        let row = [
+            e.id.to_string(),
+            e.created_at.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string(),
+            e.actor_id.map(|v| v.to_string()).unwrap_or_default(),
+            e.actor_username.clone().unwrap_or_default(),
+            e.action.clone(),
+            e.entity_type.clone(),
+            e.entity_id.clone(),
+            e.metadata.as_ref().map(|m| m.to_string()).unwrap_or_default(),
+        ];
+        out.write_record(row.map(csv_cell))?;
+    }
+    let bytes = out.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// GET /api/admin/audit
+pub async fn list_audit(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, StatusCode> {
+    if !ctx.has_perm(AUDIT_READ_PERMISSION) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    Ok(Json(load_audit_page(&mut conn, &q)?))
+}
+
+// GET /api/admin/audit/export — те же фильтры, без пагинации, text/csv
+pub async fn export_audit_csv(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<AuditQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if !ctx.has_perm(AUDIT_READ_PERMISSION) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let items = query_audit(&mut conn, &q.filter()?, None, CSV_EXPORT_MAX_ROWS).map_err(|e| {
+        eprintln!("audit export error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let body = audit_csv(&items).map_err(|e| {
+        eprintln!("audit export csv error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let filename = format!("audit-{}.csv", Utc::now().format("%Y%m%d-%H%M%S"));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_export_quotes_fields_and_defuses_formulas() {
+        let event = AuditEvent {
+            id: Uuid::nil(),
+            actor_id: None,
+            actor_username: Some("=HYPERLINK(\"http://evil\")".to_string()),
+            action: "quizlet.set.update".to_string(),
+            entity_type: "set".to_string(),
+            entity_id: "@SUM(1,2)".to_string(),
+            metadata: Some(serde_json::json!({ "title": "a, \"b\"" })),
+            created_at: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+        };
+
+        let csv = audit_csv(&[event]).unwrap();
+        let rows: Vec<csv::StringRecord> =
+            csv::Reader::from_reader(csv.as_bytes()).records().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(&rows[0][3], "'=HYPERLINK(\"http://evil\")");
+        assert_eq!(&rows[0][4], "quizlet.set.update");
+        assert_eq!(&rows[0][6], "'@SUM(1,2)");
+        assert_eq!(&rows[0][7], r#"{"title":"a, \"b\""}"#);
+    }
+}
@@ -1,2 +1,12 @@
 pub mod users;
-pub mod social_test;
\ No newline at end of file
+pub mod social_test;
+pub mod roles;
+pub mod audit;
+pub mod users_import;
+pub mod users_export;
+pub mod social_accounts;
+pub mod notifications;
+pub mod social_jobs;
+pub mod social_campaigns;
+pub mod social_metrics;
+pub mod news;
@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use diesel::prelude::*;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::audit::{snapshot, Audit},
+    auth::context::AuthContext,
+    models::news::{NewsPost, NewsPostChanges},
+    schema::{news_posts, social_accounts, social_post_jobs},
+    social::service::SocialPublishers,
+    social::types::SocialProvider,
+};
+
+const NEWS_UPDATE_PERMISSION: &str = "content.update_any";
+
+/// Уже опубликованная копия новости в соцсети.
+#[derive(Debug, Serialize)]
+pub struct PublishedCopy {
+    pub job_id: i64,
+    pub account_id: i64,
+    pub account_name: String,
+    pub provider: String,
+    pub external_post_id: String,
+    pub can_edit: bool,
+    pub can_delete: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateNewsResponse {
+    pub news: NewsPost,
+    /// Не пусто — UI предлагает перенести правку через `.../social-jobs/propagate`
+    pub published_copies: Vec<PublishedCopy>,
+}
+
+// PATCH /api/admin/news/{id}
+pub async fn update_news(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<Uuid>,
+    audit: Audit,
+    Json(changes): Json<NewsPostChanges>,
+) -> Result<Json<UpdateNewsResponse>, StatusCode> {
+    if !ctx.has_perm(NEWS_UPDATE_PERMISSION) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if changes.title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let news = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let before = news_posts::table.find(id).first::<NewsPost>(conn)?;
+            // пустая правка — не ошибка, просто нечего менять
+            let after = match diesel::update(news_posts::table.find(id)).set(&changes).get_result::<NewsPost>(conn) {
+                Err(diesel::result::Error::QueryBuilderError(_)) => before.clone(),
+                other => other?,
+            };
+
+            audit.record(conn, "news.update", "news_post", id, snapshot(&before), snapshot(&after))?;
+            Ok(after)
+        })
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+            e => {
+                eprintln!("update_news error: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    let rows = social_post_jobs::table
+        .inner_join(social_accounts::table)
+        .filter(social_post_jobs::news_post_id.eq(id))
+        .filter(social_post_jobs::status.eq("posted"))
+        .filter(social_post_jobs::external_post_id.is_not_null())
+        .order(social_post_jobs::id.asc())
+        .select((
+            social_post_jobs::id,
+            social_accounts::id,
+            social_accounts::account_name,
+            social_accounts::provider,
+            social_post_jobs::external_post_id.assume_not_null(),
+        ))
+        .load::<(i64, i64, String, String, String)>(&mut conn)
+        .map_err(|e| {
+            eprintln!("update_news error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let publishers = SocialPublishers::new();
+    let published_copies = rows
+        .into_iter()
+        .map(|(job_id, account_id, account_name, provider, external_post_id)| {
+            let (can_edit, can_delete) = SocialProvider::from_db(&provider)
+                .and_then(|p| publishers.get(p).ok())
+                .map(|p| (p.capabilities().edit, p.capabilities().delete))
+                .unwrap_or_default();
+            PublishedCopy {
+                job_id,
+                account_id,
+                account_name,
+                provider,
+                external_post_id,
+                can_edit,
+                can_delete,
+            }
+        })
+        .collect();
+
+    Ok(Json(UpdateNewsResponse { news, published_copies }))
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    models::notifications::{AdminNotification, NewAdminNotification},
+    schema::admin_notifications,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationsQuery {
+    /// `true` — только непрочитанные
+    pub unread: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+/// Уведомление для админов (видно в `GET /api/admin/notifications`).
+pub fn notify_admins(conn: &mut PgConnection, n: NewAdminNotification) -> QueryResult<i64> {
+    diesel::insert_into(admin_notifications::table)
+        .values(&n)
+        .returning(admin_notifications::id)
+        .get_result(conn)
+}
+
+// GET /api/admin/notifications?unread=true&limit=50
+pub async fn list_notifications(
+    State(state): State<AppState>,
+    Query(q): Query<NotificationsQuery>,
+) -> Result<Json<Vec<AdminNotification>>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let mut query = admin_notifications::table.into_boxed();
+    if q.unread.unwrap_or(false) {
+        query = query.filter(admin_notifications::read_at.is_null());
+    }
+
+    let rows = query
+        .order(admin_notifications::created_at.desc())
+        .limit(q.limit.unwrap_or(50).clamp(1, 200))
+        .select(AdminNotification::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            eprintln!("list_notifications error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(rows))
+}
+
+// POST /api/admin/notifications/{id}/read
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let updated = diesel::update(
+        admin_notifications::table
+            .find(id)
+            .filter(admin_notifications::read_at.is_null()),
+    )
+    .set(admin_notifications::read_at.eq(Utc::now()))
+    .execute(&mut conn)
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if updated == 0 {
+        let exists = admin_notifications::table
+            .find(id)
+            .count()
+            .get_result::<i64>(&mut conn)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if exists == 0 {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
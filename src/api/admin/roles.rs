@@ -0,0 +1,558 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::context::AuthContext,
+    models::audit::write_audit,
+};
+use crate::schema::{permissions, role_permissions, roles, user_roles, users};
+
+#[derive(Debug, Serialize)]
+pub struct RoleDto {
+    pub id: Uuid,
+    pub key: String,
+    pub name: String,
+    pub permissions: Vec<String>,
+    pub users_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoleReq {
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachPermissionReq {
+    pub permission_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignRoleReq {
+    pub role_key: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserRoleDto {
+    pub key: String,
+    pub name: String,
+    pub assigned_by: Option<Uuid>,
+    pub assigned_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EffectivePermission {
+    pub key: String,
+    pub via_roles: Vec<String>, // какие роли дают это право
+}
+
+#[derive(Debug, Serialize)]
+pub struct EffectivePermissionsResponse {
+    pub user_id: Uuid,
+    pub roles: Vec<String>,
+    pub permissions: Vec<EffectivePermission>,
+}
+
+fn require_perm(ctx: &AuthContext, perm: &str) -> Result<(), StatusCode> {
+    if ctx.has_perm(perm) {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+fn find_role_id(conn: &mut PgConnection, role_key: &str) -> Result<Uuid, StatusCode> {
+    roles::table
+        .filter(roles::key.eq(role_key))
+        .select(roles::id)
+        .first(conn)
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })
+}
+
+fn find_permission_id(conn: &mut PgConnection, permission_key: &str) -> Result<Uuid, StatusCode> {
+    permissions::table
+        .filter(permissions::key.eq(permission_key))
+        .select(permissions::id)
+        .first(conn)
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })
+}
+
+/// Все роли с их permissions и количеством пользователей.
+/// Используется и JSON API, и askama-страницей `/admin/roles`.
+pub fn load_roles(conn: &mut PgConnection) -> Result<Vec<RoleDto>, diesel::result::Error> {
+    let role_rows: Vec<(Uuid, String, String)> = roles::table
+        .select((roles::id, roles::key, roles::name))
+        .order(roles::key.asc())
+        .load(conn)?;
+
+    let perm_rows: Vec<(Uuid, String)> = role_permissions::table
+        .inner_join(permissions::table.on(permissions::id.eq(role_permissions::permission_id)))
+        .select((role_permissions::role_id, permissions::key))
+        .order(permissions::key.asc())
+        .load(conn)?;
+
+    let count_rows: Vec<(Uuid, i64)> = user_roles::table
+        .group_by(user_roles::role_id)
+        .select((user_roles::role_id, diesel::dsl::count(user_roles::user_id)))
+        .load(conn)?;
+
+    let mut perms_by_role: BTreeMap<Uuid, Vec<String>> = BTreeMap::new();
+    for (role_id, key) in perm_rows {
+        perms_by_role.entry(role_id).or_default().push(key);
+    }
+    let counts: BTreeMap<Uuid, i64> = count_rows.into_iter().collect();
+
+    Ok(role_rows
+        .into_iter()
+        .map(|(id, key, name)| RoleDto {
+            permissions: perms_by_role.remove(&id).unwrap_or_default(),
+            users_count: counts.get(&id).copied().unwrap_or(0),
+            id,
+            key,
+            name,
+        })
+        .collect())
+}
+
+pub fn load_permission_keys(conn: &mut PgConnection) -> Result<Vec<String>, diesel::result::Error> {
+    permissions::table
+        .select(permissions::key)
+        .order(permissions::key.asc())
+        .load(conn)
+}
+
+// GET /api/admin/roles
+pub async fn list_roles(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<RoleDto>>, StatusCode> {
+    require_perm(&ctx, "roles.read")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let items = load_roles(&mut conn).map_err(|e| {
+        eprintln!("list_roles error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(items))
+}
+
+// POST /api/admin/roles
+pub async fn create_role(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(req): Json<CreateRoleReq>,
+) -> Result<(StatusCode, Json<RoleDto>), StatusCode> {
+    require_perm(&ctx, "roles.manage")?;
+
+    let role_key = req.key.trim().to_lowercase();
+    let role_name = req.name.trim().to_string();
+    if role_key.is_empty() || role_name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let mut permission_ids = Vec::with_capacity(req.permissions.len());
+    for perm_key in &req.permissions {
+        permission_ids.push(find_permission_id(&mut conn, perm_key.trim())?);
+    }
+
+    let role_id = conn
+        .transaction::<Uuid, diesel::result::Error, _>(|conn| {
+            let role_id: Uuid = diesel::insert_into(roles::table)
+                .values((roles::key.eq(&role_key), roles::name.eq(&role_name)))
+                .returning(roles::id)
+                .get_result(conn)?;
+
+            for permission_id in &permission_ids {
+                diesel::insert_into(role_permissions::table)
+                    .values((
+                        role_permissions::role_id.eq(role_id),
+                        role_permissions::permission_id.eq(permission_id),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+
+            write_audit(
+                conn,
+                Some(ctx.user_id),
+                "role.create",
+                "role",
+                role_id,
+                Some(serde_json::json!({
+                    "key": role_key,
+                    "name": role_name,
+                    "permissions": req.permissions,
+                })),
+            )?;
+
+            Ok(role_id)
+        })
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => StatusCode::CONFLICT,
+            e => {
+                eprintln!("create_role error: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    let created = load_roles(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .find(|r| r.id == role_id)
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+// DELETE /api/admin/roles/{role_key}
+pub async fn delete_role(
+    Path(role_key): Path<String>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, StatusCode> {
+    require_perm(&ctx, "roles.manage")?;
+
+    // без admin-роли в систему больше никто не попадёт
+    if role_key == "admin" {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let role_id = find_role_id(&mut conn, &role_key)?;
+
+    // роль, которая ещё назначена пользователям, не удаляем молча (CASCADE снял бы её у всех)
+    let assigned: i64 = user_roles::table
+        .filter(user_roles::role_id.eq(role_id))
+        .count()
+        .get_result(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if assigned > 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(roles::table.find(role_id)).execute(conn)?;
+
+        write_audit(
+            conn,
+            Some(ctx.user_id),
+            "role.delete",
+            "role",
+            role_id,
+            Some(serde_json::json!({ "key": role_key })),
+        )
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/admin/permissions
+pub async fn list_permissions(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    require_perm(&ctx, "roles.read")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let keys = load_permission_keys(&mut conn).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(keys))
+}
+
+// POST /api/admin/roles/{role_key}/permissions
+pub async fn attach_permission(
+    Path(role_key): Path<String>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(req): Json<AttachPermissionReq>,
+) -> Result<StatusCode, StatusCode> {
+    require_perm(&ctx, "roles.manage")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let permission_key = req.permission_key.trim().to_string();
+
+    let role_id = find_role_id(&mut conn, &role_key)?;
+    let permission_id = find_permission_id(&mut conn, &permission_key)?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let inserted = diesel::insert_into(role_permissions::table)
+            .values((
+                role_permissions::role_id.eq(role_id),
+                role_permissions::permission_id.eq(permission_id),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        if inserted > 0 {
+            write_audit(
+                conn,
+                Some(ctx.user_id),
+                "role.permission.attach",
+                "role",
+                role_id,
+                Some(serde_json::json!({ "role_key": role_key, "permission_key": permission_key })),
+            )?;
+        }
+
+        Ok(())
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DELETE /api/admin/roles/{role_key}/permissions/{permission_key}
+pub async fn detach_permission(
+    Path((role_key, permission_key)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, StatusCode> {
+    require_perm(&ctx, "roles.manage")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let role_id = find_role_id(&mut conn, &role_key)?;
+    let permission_id = find_permission_id(&mut conn, &permission_key)?;
+
+    let deleted = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let deleted = diesel::delete(
+                role_permissions::table
+                    .filter(role_permissions::role_id.eq(role_id))
+                    .filter(role_permissions::permission_id.eq(permission_id)),
+            )
+            .execute(conn)?;
+
+            if deleted > 0 {
+                write_audit(
+                    conn,
+                    Some(ctx.user_id),
+                    "role.permission.detach",
+                    "role",
+                    role_id,
+                    Some(serde_json::json!({ "role_key": role_key, "permission_key": permission_key })),
+                )?;
+            }
+
+            Ok(deleted)
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if deleted == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/admin/users/{id}/roles
+pub async fn list_user_roles(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<UserRoleDto>>, StatusCode> {
+    require_perm(&ctx, "roles.read")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let rows: Vec<(String, String, Option<Uuid>, chrono::NaiveDateTime)> = user_roles::table
+        .inner_join(roles::table.on(roles::id.eq(user_roles::role_id)))
+        .filter(user_roles::user_id.eq(user_id))
+        .select((roles::key, roles::name, user_roles::assigned_by, user_roles::assigned_at))
+        .order(roles::key.asc())
+        .load(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|(key, name, assigned_by, assigned_at)| UserRoleDto {
+                key,
+                name,
+                assigned_by,
+                assigned_at,
+            })
+            .collect(),
+    ))
+}
+
+// POST /api/admin/users/{id}/roles
+pub async fn assign_role(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(req): Json<AssignRoleReq>,
+) -> Result<StatusCode, StatusCode> {
+    require_perm(&ctx, "roles.assign")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let role_key = req.role_key.trim().to_lowercase();
+
+    users::table
+        .find(user_id)
+        .select(users::id)
+        .first::<Uuid>(&mut conn)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let role_id = find_role_id(&mut conn, &role_key).map_err(|e| match e {
+        StatusCode::NOT_FOUND => StatusCode::BAD_REQUEST,
+        other => other,
+    })?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        // вставить связь (idempotent)
+        let inserted = diesel::insert_into(user_roles::table)
+            .values((
+                user_roles::user_id.eq(user_id),
+                user_roles::role_id.eq(role_id),
+                user_roles::assigned_by.eq(Some(ctx.user_id)),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        if inserted > 0 {
+            write_audit(
+                conn,
+                Some(ctx.user_id),
+                "role.assign",
+                "user",
+                user_id,
+                Some(serde_json::json!({ "role_key": role_key, "assigned_by": ctx.user_id })),
+            )?;
+        }
+
+        Ok(())
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DELETE /api/admin/users/{id}/roles/{role_key}
+pub async fn revoke_role(
+    Path((user_id, role_key)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, StatusCode> {
+    require_perm(&ctx, "roles.revoke")?;
+
+    // не даём админу случайно лишить себя доступа к админке
+    if user_id == ctx.user_id && role_key == "admin" {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let role_id = find_role_id(&mut conn, &role_key)?;
+
+    let deleted = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let deleted = diesel::delete(
+                user_roles::table
+                    .filter(user_roles::user_id.eq(user_id))
+                    .filter(user_roles::role_id.eq(role_id)),
+            )
+            .execute(conn)?;
+
+            if deleted > 0 {
+                write_audit(
+                    conn,
+                    Some(ctx.user_id),
+                    "role.revoke",
+                    "user",
+                    user_id,
+                    Some(serde_json::json!({ "role_key": role_key })),
+                )?;
+            }
+
+            Ok(deleted)
+        })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if deleted == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/admin/users/{id}/permissions
+pub async fn user_effective_permissions(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<EffectivePermissionsResponse>, StatusCode> {
+    require_perm(&ctx, "roles.read")?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    users::table
+        .find(user_id)
+        .select(users::id)
+        .first::<Uuid>(&mut conn)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let role_keys: Vec<String> = user_roles::table
+        .inner_join(roles::table.on(roles::id.eq(user_roles::role_id)))
+        .filter(user_roles::user_id.eq(user_id))
+        .select(roles::key)
+        .order(roles::key.asc())
+        .load(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let rows: Vec<(String, String)> = user_roles::table
+        .inner_join(roles::table.on(roles::id.eq(user_roles::role_id)))
+        .inner_join(role_permissions::table.on(role_permissions::role_id.eq(user_roles::role_id)))
+        .inner_join(permissions::table.on(permissions::id.eq(role_permissions::permission_id)))
+        .filter(user_roles::user_id.eq(user_id))
+        .select((permissions::key, roles::key))
+        .load(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut by_perm: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (perm_key, role_key) in rows {
+        by_perm.entry(perm_key).or_default().push(role_key);
+    }
+
+    let permissions = by_perm
+        .into_iter()
+        .map(|(key, mut via_roles)| {
+            via_roles.sort();
+            EffectivePermission { key, via_roles }
+        })
+        .collect();
+
+    Ok(Json(EffectivePermissionsResponse {
+        user_id,
+        roles: role_keys,
+        permissions,
+    }))
+}
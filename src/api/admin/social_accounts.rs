@@ -0,0 +1,394 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    AppState,
+    auth::audit::{snapshot, Audit},
+    models::social::{NewSocialAccount, SocialAccount},
+    schema::{social_accounts, social_post_jobs},
+    social::accounts::{
+        decrypt_account, load_account_for_admin, load_all_accounts, load_stored_account, mask_token,
+        redact_account_secrets,
+    },
+    social::crypto::{open_token, seal_token},
+    social::limits::validate_posting_rules,
+    social::adapters::{is_dry_run, validate_api_base_url, validate_dry_run},
+    social::render::validate_template,
+    social::service::SocialPublishers,
+    social::types::SocialProvider,
+};
+
+/// Аккаунт для API: токены только в маскированном виде.
+#[derive(Debug, Serialize)]
+pub struct SocialAccountDto {
+    pub id: i64,
+    pub provider: String,
+    pub account_name: String,
+    pub external_account_id: Option<String>,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub settings_json: Value,
+    pub is_active: bool,
+    /// Посты не уходят провайдеру, а пишутся в попытки (`settings_json.dry_run` или песочница)
+    pub dry_run: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub token_refreshed_at: Option<DateTime<Utc>>,
+    /// Последняя ошибка продления токена (сбрасывается при успехе или новом токене)
+    pub auth_error: Option<String>,
+    pub auth_error_at: Option<DateTime<Utc>>,
+}
+
+impl From<SocialAccount> for SocialAccountDto {
+    fn from(a: SocialAccount) -> Self {
+        let dry_run = is_dry_run(&a);
+        Self {
+            id: a.id,
+            provider: a.provider,
+            account_name: a.account_name,
+            external_account_id: a.external_account_id,
+            access_token: a.access_token.as_deref().map(mask_token),
+            refresh_token: a.refresh_token.as_deref().map(mask_token),
+            token_expires_at: a.token_expires_at,
+            settings_json: a.settings_json,
+            is_active: a.is_active,
+            dry_run,
+            created_at: a.created_at,
+            updated_at: a.updated_at,
+            token_refreshed_at: a.token_refreshed_at,
+            auth_error: a.auth_error,
+            auth_error_at: a.auth_error_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSocialAccountReq {
+    pub provider: String,
+    pub account_name: String,
+    pub external_account_id: Option<String>,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub settings_json: Option<Value>,
+    pub is_active: Option<bool>,
+}
+
+/// Частичное обновление: отсутствующее поле не меняется,
+/// пустая строка в токене/external_account_id очищает значение.
+#[derive(Debug, Deserialize)]
+pub struct UpdateSocialAccountReq {
+    pub account_name: Option<String>,
+    pub external_account_id: Option<String>,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub settings_json: Option<Value>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestConnectionResponse {
+    pub ok: bool,
+    pub detail: String,
+}
+
+fn dto_snapshot(a: &SocialAccount) -> Option<Value> {
+    snapshot(&SocialAccountDto::from(a.clone()))
+}
+
+/// Копия с открытыми токенами — только для маски в ответе и аудите.
+/// Нерасшифровываемый токен показывается как отсутствующий.
+fn opened(a: &SocialAccount) -> SocialAccount {
+    SocialAccount {
+        access_token: open_token(a.access_token.clone()).ok().flatten(),
+        refresh_token: open_token(a.refresh_token.clone()).ok().flatten(),
+        ..a.clone()
+    }
+}
+
+fn blank_to_none(v: Option<String>) -> Option<String> {
+    v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn validate_settings(v: &Option<Value>) -> Result<(), StatusCode> {
+    match v {
+        Some(s) if !s.is_object() => Err(StatusCode::BAD_REQUEST),
+        Some(s) => validate_template(s)
+            .and_then(|_| validate_api_base_url(s))
+            .and_then(|_| validate_dry_run(s))
+            .and_then(|_| validate_posting_rules(s))
+            .map_err(|e| {
+                eprintln!("social account settings rejected: {e}");
+                StatusCode::BAD_REQUEST
+            }),
+        None => Ok(()),
+    }
+}
+
+// GET /api/admin/social/accounts
+pub async fn list_social_accounts(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SocialAccountDto>>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let rows = load_all_accounts(&mut conn).map_err(|e| {
+        eprintln!("list_social_accounts error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows.into_iter().map(SocialAccountDto::from).collect()))
+}
+
+// GET /api/admin/social/accounts/{id}
+pub async fn get_social_account(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<SocialAccountDto>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let account = load_account_for_admin(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(account.into()))
+}
+
+// POST /api/admin/social/accounts
+pub async fn create_social_account(
+    State(state): State<AppState>,
+    audit: Audit,
+    Json(req): Json<CreateSocialAccountReq>,
+) -> Result<(StatusCode, Json<SocialAccountDto>), StatusCode> {
+    let provider = SocialProvider::from_db(req.provider.trim()).ok_or(StatusCode::BAD_REQUEST)?;
+    if req.account_name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    validate_settings(&req.settings_json)?;
+
+    let seal = |t: Option<String>| {
+        seal_token(t).map_err(|e| {
+            eprintln!("create_social_account seal error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    };
+    let plain_access = blank_to_none(req.access_token);
+    let plain_refresh = blank_to_none(req.refresh_token);
+    let access_token = seal(plain_access.clone())?;
+    let refresh_token = seal(plain_refresh.clone())?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let created = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let created = diesel::insert_into(social_accounts::table)
+                .values(&NewSocialAccount {
+                    provider: provider.as_str().to_string(),
+                    account_name: req.account_name.trim().to_string(),
+                    external_account_id: blank_to_none(req.external_account_id),
+                    access_token,
+                    refresh_token,
+                    token_expires_at: req.token_expires_at,
+                    settings_json: req.settings_json.unwrap_or_else(|| serde_json::json!({})),
+                    is_active: req.is_active.unwrap_or(true),
+                })
+                .returning(SocialAccount::as_returning())
+                .get_result::<SocialAccount>(conn)?;
+
+            // в ответ и аудит — маска открытого токена, а не шифртекста
+            let created = SocialAccount {
+                access_token: plain_access,
+                refresh_token: plain_refresh,
+                ..created
+            };
+            audit.record(conn, "social_account.create", "social_account", created.id, None, dto_snapshot(&created))?;
+            Ok(created)
+        })
+        .map_err(|e| {
+            eprintln!("create_social_account error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok((StatusCode::CREATED, Json(created.into())))
+}
+
+// PUT /api/admin/social/accounts/{id}
+pub async fn update_social_account(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    audit: Audit,
+    Json(req): Json<UpdateSocialAccountReq>,
+) -> Result<Json<SocialAccountDto>, StatusCode> {
+    if req.account_name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    validate_settings(&req.settings_json)?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    // токены не расшифровываем: аккаунт с битым ключом чинят, присылая новые
+    let stored = load_stored_account(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let before = opened(&stored);
+
+    let (new_access, new_refresh) = (req.access_token.is_some(), req.refresh_token.is_some());
+    let mut next = before.clone();
+    if let Some(name) = req.account_name {
+        next.account_name = name.trim().to_string();
+    }
+    if req.external_account_id.is_some() {
+        next.external_account_id = blank_to_none(req.external_account_id);
+    }
+    if req.access_token.is_some() {
+        next.access_token = blank_to_none(req.access_token);
+        // новый токен вручную — старая ошибка продления больше не актуальна
+        next.auth_error = None;
+        next.auth_error_at = None;
+        next.token_refreshed_at = None;
+    }
+    if req.refresh_token.is_some() {
+        next.refresh_token = blank_to_none(req.refresh_token);
+    }
+    if req.token_expires_at.is_some() {
+        next.token_expires_at = req.token_expires_at;
+    }
+    if let Some(settings) = req.settings_json {
+        next.settings_json = settings;
+    }
+    if let Some(active) = req.is_active {
+        next.is_active = active;
+    }
+
+    let seal = |t: &Option<String>| {
+        seal_token(t.clone()).map_err(|e| {
+            eprintln!("update_social_account seal error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    };
+    // непереданный токен остаётся в БД как был, без перешифровки
+    let access_token = if new_access { seal(&next.access_token)? } else { stored.access_token.clone() };
+    let refresh_token = if new_refresh { seal(&next.refresh_token)? } else { stored.refresh_token.clone() };
+
+    let updated = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated = diesel::update(social_accounts::table.find(id))
+                .set((
+                    social_accounts::account_name.eq(&next.account_name),
+                    social_accounts::external_account_id.eq(&next.external_account_id),
+                    social_accounts::access_token.eq(&access_token),
+                    social_accounts::refresh_token.eq(&refresh_token),
+                    social_accounts::token_expires_at.eq(next.token_expires_at),
+                    social_accounts::settings_json.eq(&next.settings_json),
+                    social_accounts::is_active.eq(next.is_active),
+                    social_accounts::token_refreshed_at.eq(next.token_refreshed_at),
+                    social_accounts::auth_error.eq(&next.auth_error),
+                    social_accounts::auth_error_at.eq(next.auth_error_at),
+                    social_accounts::updated_at.eq(Utc::now()),
+                ))
+                .returning(SocialAccount::as_returning())
+                .get_result::<SocialAccount>(conn)?;
+            let updated = SocialAccount {
+                access_token: next.access_token.clone(),
+                refresh_token: next.refresh_token.clone(),
+                ..updated
+            };
+
+            audit.record(
+                conn,
+                "social_account.update",
+                "social_account",
+                id,
+                dto_snapshot(&before),
+                dto_snapshot(&updated),
+            )?;
+            Ok(updated)
+        })
+        .map_err(|e| {
+            eprintln!("update_social_account error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(updated.into()))
+}
+
+// DELETE /api/admin/social/accounts/{id}
+// Jobs удаляются каскадом вместе с историей, поэтому аккаунт с jobs только деактивируют.
+pub async fn delete_social_account(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    audit: Audit,
+) -> Result<StatusCode, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let before = load_stored_account(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|stored| opened(&stored))
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let jobs: i64 = social_post_jobs::table
+        .filter(social_post_jobs::social_account_id.eq(id))
+        .count()
+        .get_result(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if jobs > 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(social_accounts::table.find(id)).execute(conn)?;
+        audit.record(conn, "social_account.delete", "social_account", id, dto_snapshot(&before), None)
+    })
+    .map_err(|e| {
+        eprintln!("delete_social_account error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/admin/social/accounts/{id}/test — проверка кредов без публикации
+pub async fn test_social_account(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<TestConnectionResponse>, StatusCode> {
+    let stored = {
+        let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+        load_stored_account(&mut conn, id)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?
+    };
+    // битый ключ — такой же провал проверки, как отказ провайдера
+    let account = match decrypt_account(stored) {
+        Ok(account) => account,
+        Err(err) => {
+            return Ok(Json(TestConnectionResponse {
+                ok: false,
+                detail: format!("token decryption failed: {err:#}"),
+            }));
+        }
+    };
+
+    let provider = SocialProvider::from_db(&account.provider).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let publisher = SocialPublishers::new()
+        .get(provider)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let resp = match publisher.test_connection(&account).await {
+        Ok(detail) => TestConnectionResponse { ok: true, detail },
+        Err(err) => TestConnectionResponse {
+            ok: false,
+            detail: redact_account_secrets(&format!("{err:#}"), &account),
+        },
+    };
+
+    Ok(Json(resp))
+}
@@ -0,0 +1,358 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    AppState,
+    api::admin::social_jobs::require_publish,
+    auth::audit::{snapshot, Audit},
+    auth::context::AuthContext,
+    models::social::{NewSocialCampaign, SocialCampaign},
+    schema::{social_accounts, social_campaign_accounts, social_campaigns},
+    social::campaigns::{CampaignSchedule, ContentSource},
+};
+
+#[derive(Debug, Serialize)]
+pub struct SocialCampaignDto {
+    pub id: i64,
+    pub name: String,
+    pub cron_expr: String,
+    pub timezone: String,
+    pub content_source: Value,
+    pub account_ids: Vec<i64>,
+    pub is_active: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Почему последний запуск ничего не поставил (нет контента, битый источник)
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCampaignReq {
+    pub name: String,
+    /// `0 9 * * MON-FRI` — 5 полей или 6 с секундами
+    pub cron_expr: String,
+    /// IANA, по умолчанию UTC
+    pub timezone: Option<String>,
+    /// `{ "type": "next_unposted", "kind": "idiom", "level": "B2" }` или `{ "type": "random_hot" }`
+    pub content_source: ContentSource,
+    pub account_ids: Vec<i64>,
+    pub is_active: Option<bool>,
+}
+
+/// Частичное обновление: отсутствующее поле не меняется.
+#[derive(Debug, Deserialize)]
+pub struct UpdateCampaignReq {
+    pub name: Option<String>,
+    pub cron_expr: Option<String>,
+    pub timezone: Option<String>,
+    pub content_source: Option<ContentSource>,
+    pub account_ids: Option<Vec<i64>>,
+    pub is_active: Option<bool>,
+}
+
+fn to_dto(c: SocialCampaign, account_ids: Vec<i64>) -> SocialCampaignDto {
+    SocialCampaignDto {
+        id: c.id,
+        name: c.name,
+        cron_expr: c.cron_expr,
+        timezone: c.timezone,
+        content_source: c.content_source,
+        account_ids,
+        is_active: c.is_active,
+        next_run_at: c.next_run_at,
+        last_run_at: c.last_run_at,
+        last_error: c.last_error,
+        created_at: c.created_at,
+        updated_at: c.updated_at,
+    }
+}
+
+fn load_campaign(conn: &mut PgConnection, id: i64) -> QueryResult<Option<SocialCampaignDto>> {
+    let Some(campaign) = social_campaigns::table
+        .find(id)
+        .select(SocialCampaign::as_select())
+        .first(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let account_ids = campaign_account_ids(conn, id)?;
+    Ok(Some(to_dto(campaign, account_ids)))
+}
+
+fn campaign_account_ids(conn: &mut PgConnection, id: i64) -> QueryResult<Vec<i64>> {
+    social_campaign_accounts::table
+        .filter(social_campaign_accounts::campaign_id.eq(id))
+        .order(social_campaign_accounts::social_account_id.asc())
+        .select(social_campaign_accounts::social_account_id)
+        .load(conn)
+}
+
+/// Следующий запуск по cron; невалидный cron/таймзона — 422.
+fn next_run(cron_expr: &str, timezone: &str) -> Result<Option<DateTime<Utc>>, StatusCode> {
+    let schedule = CampaignSchedule::parse(cron_expr, timezone).map_err(|e| {
+        eprintln!("social campaign schedule rejected: {e}");
+        StatusCode::UNPROCESSABLE_ENTITY
+    })?;
+    Ok(schedule.next_after(Utc::now()))
+}
+
+fn validate_source(source: &ContentSource) -> Result<(), StatusCode> {
+    source.validate().map_err(|e| {
+        eprintln!("social campaign content_source rejected: {e}");
+        StatusCode::UNPROCESSABLE_ENTITY
+    })
+}
+
+/// Все аккаунты должны существовать (выключенные можно — кампания их пропустит).
+fn validate_accounts(conn: &mut PgConnection, ids: &mut Vec<i64>) -> Result<(), StatusCode> {
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let found: i64 = social_accounts::table
+        .filter(social_accounts::id.eq_any(&*ids))
+        .count()
+        .get_result(conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if found != ids.len() as i64 {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    Ok(())
+}
+
+fn replace_accounts(conn: &mut PgConnection, id: i64, ids: &[i64]) -> QueryResult<()> {
+    diesel::delete(social_campaign_accounts::table.filter(social_campaign_accounts::campaign_id.eq(id)))
+        .execute(conn)?;
+    for account_id in ids {
+        diesel::insert_into(social_campaign_accounts::table)
+            .values((
+                social_campaign_accounts::campaign_id.eq(id),
+                social_campaign_accounts::social_account_id.eq(account_id),
+            ))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+// GET /api/admin/social/campaigns
+pub async fn list_social_campaigns(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<SocialCampaignDto>>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let campaigns = social_campaigns::table
+        .order(social_campaigns::id.asc())
+        .select(SocialCampaign::as_select())
+        .load::<SocialCampaign>(&mut conn)
+        .map_err(|e| {
+            eprintln!("list_social_campaigns error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let links: Vec<(i64, i64)> = social_campaign_accounts::table
+        .order(social_campaign_accounts::social_account_id.asc())
+        .select((social_campaign_accounts::campaign_id, social_campaign_accounts::social_account_id))
+        .load(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let dtos = campaigns
+        .into_iter()
+        .map(|c| {
+            let ids = links.iter().filter(|(cid, _)| *cid == c.id).map(|(_, aid)| *aid).collect();
+            to_dto(c, ids)
+        })
+        .collect();
+
+    Ok(Json(dtos))
+}
+
+// POST /api/admin/social/campaigns
+pub async fn create_social_campaign(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    audit: Audit,
+    Json(req): Json<CreateCampaignReq>,
+) -> Result<(StatusCode, Json<SocialCampaignDto>), StatusCode> {
+    require_publish(&ctx)?;
+    if req.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let timezone = req.timezone.as_deref().map(str::trim).filter(|s| !s.is_empty()).unwrap_or("UTC");
+    let next_run_at = next_run(&req.cron_expr, timezone)?;
+    validate_source(&req.content_source)?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    let mut account_ids = req.account_ids.clone();
+    validate_accounts(&mut conn, &mut account_ids)?;
+
+    let id = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let created = diesel::insert_into(social_campaigns::table)
+                .values(&NewSocialCampaign {
+                    name: req.name.trim().to_string(),
+                    cron_expr: req.cron_expr.trim().to_string(),
+                    timezone: timezone.to_string(),
+                    content_source: serde_json::to_value(&req.content_source).unwrap_or_default(),
+                    is_active: req.is_active.unwrap_or(true),
+                    next_run_at,
+                })
+                .returning(SocialCampaign::as_returning())
+                .get_result::<SocialCampaign>(conn)?;
+            replace_accounts(conn, created.id, &account_ids)?;
+
+            let dto = to_dto(created, account_ids.clone());
+            audit.record(conn, "social_campaign.create", "social_campaign", dto.id, None, snapshot(&dto))?;
+            Ok(dto.id)
+        })
+        .map_err(|e| {
+            eprintln!("create_social_campaign error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let dto = load_campaign(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok((StatusCode::CREATED, Json(dto)))
+}
+
+// PUT /api/admin/social/campaigns/{id}
+pub async fn update_social_campaign(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+    audit: Audit,
+    Json(req): Json<UpdateCampaignReq>,
+) -> Result<Json<SocialCampaignDto>, StatusCode> {
+    require_publish(&ctx)?;
+    if req.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(source) = &req.content_source {
+        validate_source(source)?;
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let before = load_campaign(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let name = req.name.map(|n| n.trim().to_string()).unwrap_or_else(|| before.name.clone());
+    let cron_expr = req.cron_expr.map(|c| c.trim().to_string()).unwrap_or_else(|| before.cron_expr.clone());
+    let timezone = req
+        .timezone
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| before.timezone.clone());
+    let content_source = match &req.content_source {
+        Some(source) => serde_json::to_value(source).unwrap_or_default(),
+        None => before.content_source.clone(),
+    };
+    let is_active = req.is_active.unwrap_or(before.is_active);
+
+    // расписание пересчитываем всегда: могли поменять cron, таймзону или включить кампанию
+    let next_run_at = next_run(&cron_expr, &timezone)?;
+
+    let mut account_ids = req.account_ids.clone().unwrap_or_else(|| before.account_ids.clone());
+    if req.account_ids.is_some() {
+        validate_accounts(&mut conn, &mut account_ids)?;
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let updated = diesel::update(social_campaigns::table.find(id))
+            .set((
+                social_campaigns::name.eq(&name),
+                social_campaigns::cron_expr.eq(&cron_expr),
+                social_campaigns::timezone.eq(&timezone),
+                social_campaigns::content_source.eq(&content_source),
+                social_campaigns::is_active.eq(is_active),
+                social_campaigns::next_run_at.eq(next_run_at),
+                social_campaigns::updated_at.eq(Utc::now()),
+            ))
+            .returning(SocialCampaign::as_returning())
+            .get_result::<SocialCampaign>(conn)?;
+        if req.account_ids.is_some() {
+            replace_accounts(conn, id, &account_ids)?;
+        }
+
+        let after = to_dto(updated, account_ids.clone());
+        audit.record(conn, "social_campaign.update", "social_campaign", id, snapshot(&before), snapshot(&after))
+    })
+    .map_err(|e| {
+        eprintln!("update_social_campaign error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    load_campaign(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// DELETE /api/admin/social/campaigns/{id}
+// Поставленные кампанией jobs остаются (campaign_id обнуляется).
+pub async fn delete_social_campaign(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+    audit: Audit,
+) -> Result<StatusCode, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let before = load_campaign(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(social_campaigns::table.find(id)).execute(conn)?;
+        audit.record(conn, "social_campaign.delete", "social_campaign", id, snapshot(&before), None)
+    })
+    .map_err(|e| {
+        eprintln!("delete_social_campaign error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/admin/social/campaigns/{id}/run — внеочередной запуск на ближайшем тике планировщика
+pub async fn run_social_campaign_now(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+) -> Result<Json<SocialCampaignDto>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let updated = diesel::update(
+        social_campaigns::table
+            .find(id)
+            .filter(social_campaigns::is_active.eq(true)),
+    )
+    .set(social_campaigns::next_run_at.eq(Utc::now()))
+    .execute(&mut conn)
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let dto = load_campaign(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if updated == 0 {
+        // выключенную кампанию сначала включают
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(Json(dto))
+}
@@ -0,0 +1,960 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    auth::audit::{snapshot, Audit},
+    auth::context::AuthContext,
+    models::news::NewsPost,
+    models::social::{NewSocialPostJob, SocialAccount, SocialPostAttempt, SocialPostJob},
+    schema::{news_posts, social_accounts, social_post_attempts, social_post_jobs},
+    social::accounts::{load_account, redact_account_secrets},
+    social::adapters::{is_dry_run, MediaItem, PublishCheckpoint, PublishedPost},
+    social::render::{account_template, build_payload, render_post, validate_template, PostContext},
+    social::service::SocialPublishers,
+    social::types::SocialProvider,
+    social::worker_pool::WorkerReport,
+    social_jobs::{create_social_post_job, insert_attempt, JobPayload, OPEN_JOB_STATUSES},
+};
+
+/// Планирование публикаций — редакторы и админы.
+pub const SOCIAL_PUBLISH_PERMISSION: &str = "social.publish";
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleNewsReq {
+    pub account_ids: Vec<i64>,
+    /// По умолчанию — сейчас (worker подхватит в течение ~15 секунд)
+    pub scheduled_for: Option<DateTime<Utc>>,
+    /// Переопределения по сети: `{ "telegram": { "text": "..." }, "instagram": { "media": [{ "type": "image", "url": "...", "alt_text": "..." }] } }`
+    #[serde(default)]
+    pub overrides: HashMap<String, JobPayload>,
+    /// Из наступивших job'ов аккаунта первыми уходят с большим приоритетом; по умолчанию 0
+    pub priority: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewReq {
+    pub news_id: Uuid,
+    /// Несохранённый шаблон для примерки; по умолчанию — из `settings_json` аккаунта
+    pub template: Option<String>,
+    /// Как в `overrides`: с картинкой у Telegram лимит подписи короче
+    pub image_url: Option<String>,
+}
+
+/// Пост так, как его соберёт worker: шаблон, медиа после подстановки ссылок, проверка возможностей.
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse {
+    pub account_id: i64,
+    pub account_name: String,
+    pub provider: String,
+    pub template: String,
+    pub text: String,
+    pub image_url: Option<String>,
+    pub media: Vec<MediaItem>,
+    pub link: Option<String>,
+    pub hashtags: Vec<String>,
+    pub length: usize,
+    pub limit: usize,
+    pub truncated: bool,
+    /// Аккаунт в dry-run: worker запишет payload в попытку, а не отправит
+    pub dry_run: bool,
+    /// Почему провайдер такой пост не примет (например, Instagram без картинки)
+    pub problem: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewsPreviewQuery {
+    pub account_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RescheduleReq {
+    pub scheduled_for: DateTime<Utc>,
+    /// По умолчанию не меняется
+    pub priority: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct EditPostReq {
+    /// Новый текст как есть; без него текст заново рендерится из новости
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct PropagateReq {
+    /// Какие публикации новости править; по умолчанию — все опубликованные
+    pub job_ids: Option<Vec<i64>>,
+}
+
+/// Итог правки одной публикации: edited | unsupported | failed.
+#[derive(Debug, Serialize)]
+pub struct PropagateResult {
+    pub job_id: i64,
+    pub provider: String,
+    pub account_name: String,
+    pub status: &'static str,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct JobsQuery {
+    pub status: Option<String>,
+    pub account_id: Option<i64>,
+    pub news_id: Option<Uuid>,
+    pub provider: Option<String>,
+    pub campaign_id: Option<i64>,
+    /// Окно по scheduled_for
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl JobsQuery {
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SocialJobDto {
+    pub id: i64,
+    pub news_post_id: Uuid,
+    pub news_title: String,
+    pub social_account_id: i64,
+    pub account_name: String,
+    pub provider: String,
+    pub status: String,
+    pub scheduled_for: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub retry_count: i32,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub external_post_id: Option<String>,
+    pub error_message: Option<String>,
+    pub payload_json: Value,
+    /// Кампания, поставившая job; `None` — запланирован вручную.
+    pub campaign_id: Option<i64>,
+    pub priority: i32,
+    /// Какой воркер держит job в `processing` и до какого времени.
+    pub locked_by: Option<String>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SocialJobsResponse {
+    pub jobs: Vec<SocialJobDto>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// Worker этого процесса и все job'ы в `processing` — в том числе у worker'ов других инстансов.
+#[derive(Debug, Serialize)]
+pub struct WorkerStatusResponse {
+    pub worker: WorkerReport,
+    pub processing: Vec<SocialJobDto>,
+}
+
+type JobRow = (SocialPostJob, String, String, String);
+
+fn to_dto((job, account_name, provider, news_title): JobRow) -> SocialJobDto {
+    SocialJobDto {
+        id: job.id,
+        news_post_id: job.news_post_id,
+        news_title,
+        social_account_id: job.social_account_id,
+        account_name,
+        provider,
+        status: job.status,
+        scheduled_for: job.scheduled_for,
+        published_at: job.published_at,
+        retry_count: job.retry_count,
+        next_retry_at: job.next_retry_at,
+        external_post_id: job.external_post_id,
+        error_message: job.error_message,
+        payload_json: job.payload_json,
+        campaign_id: job.campaign_id,
+        priority: job.priority,
+        locked_by: job.locked_by,
+        locked_until: job.locked_until,
+        created_at: job.created_at,
+        updated_at: job.updated_at,
+    }
+}
+
+pub(crate) fn require_publish(ctx: &AuthContext) -> Result<(), StatusCode> {
+    if ctx.has_perm(SOCIAL_PUBLISH_PERMISSION) {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+fn load_job(conn: &mut PgConnection, id: i64) -> QueryResult<Option<SocialPostJob>> {
+    social_post_jobs::table
+        .find(id)
+        .select(SocialPostJob::as_select())
+        .first(conn)
+        .optional()
+}
+
+fn load_jobs_by_ids(conn: &mut PgConnection, ids: &[i64]) -> QueryResult<Vec<SocialJobDto>> {
+    let rows = social_post_jobs::table
+        .inner_join(social_accounts::table)
+        .inner_join(news_posts::table)
+        .filter(social_post_jobs::id.eq_any(ids))
+        .order(social_post_jobs::id.asc())
+        .select((
+            SocialPostJob::as_select(),
+            social_accounts::account_name,
+            social_accounts::provider,
+            news_posts::title,
+        ))
+        .load::<JobRow>(conn)?;
+    Ok(rows.into_iter().map(to_dto).collect())
+}
+
+/// Страница jobs с фильтрами, ближайшие по scheduled_for сверху.
+pub fn load_jobs_page(conn: &mut PgConnection, q: &JobsQuery) -> Result<SocialJobsResponse, StatusCode> {
+    let (page, per_page) = (q.page(), q.per_page());
+
+    let filtered = || {
+        let mut query = social_post_jobs::table
+            .inner_join(social_accounts::table)
+            .inner_join(news_posts::table)
+            .into_boxed();
+        if let Some(status) = q.status.as_deref().filter(|s| !s.is_empty()) {
+            query = query.filter(social_post_jobs::status.eq(status.to_string()));
+        }
+        if let Some(account_id) = q.account_id {
+            query = query.filter(social_post_jobs::social_account_id.eq(account_id));
+        }
+        if let Some(news_id) = q.news_id {
+            query = query.filter(social_post_jobs::news_post_id.eq(news_id));
+        }
+        if let Some(provider) = q.provider.as_deref().filter(|s| !s.is_empty()) {
+            query = query.filter(social_accounts::provider.eq(provider.to_string()));
+        }
+        if let Some(campaign_id) = q.campaign_id {
+            query = query.filter(social_post_jobs::campaign_id.eq(campaign_id));
+        }
+        if let Some(from) = q.from {
+            query = query.filter(social_post_jobs::scheduled_for.ge(from));
+        }
+        if let Some(to) = q.to {
+            query = query.filter(social_post_jobs::scheduled_for.lt(to));
+        }
+        query
+    };
+
+    let total: i64 = filtered().count().get_result(conn).map_err(|e| {
+        eprintln!("list_social_jobs count error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let rows = filtered()
+        .order((social_post_jobs::scheduled_for.desc(), social_post_jobs::id.desc()))
+        .limit(per_page)
+        .offset((page - 1) * per_page)
+        .select((
+            SocialPostJob::as_select(),
+            social_accounts::account_name,
+            social_accounts::provider,
+            news_posts::title,
+        ))
+        .load::<JobRow>(conn)
+        .map_err(|e| {
+            eprintln!("list_social_jobs error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(SocialJobsResponse {
+        jobs: rows.into_iter().map(to_dto).collect(),
+        total,
+        page,
+        per_page,
+    })
+}
+
+// POST /api/admin/news/{id}/social-jobs
+// Одна job на аккаунт; повторно запланировать новость в аккаунт с незавершённой job нельзя (409).
+pub async fn schedule_news(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(news_id): Path<Uuid>,
+    audit: Audit,
+    Json(req): Json<ScheduleNewsReq>,
+) -> Result<(StatusCode, Json<Vec<SocialJobDto>>), StatusCode> {
+    require_publish(&ctx)?;
+
+    let mut account_ids = req.account_ids.clone();
+    account_ids.sort_unstable();
+    account_ids.dedup();
+    if account_ids.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if req.overrides.keys().any(|k| SocialProvider::from_db(k).is_none()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let scheduled_for = req.scheduled_for.unwrap_or_else(Utc::now);
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let news_exists: i64 = news_posts::table
+        .find(news_id)
+        .count()
+        .get_result(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if news_exists == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let accounts: Vec<(i64, String)> = social_accounts::table
+        .filter(social_accounts::id.eq_any(&account_ids))
+        .filter(social_accounts::is_active.eq(true))
+        .select((social_accounts::id, social_accounts::provider))
+        .load(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if accounts.len() != account_ids.len() {
+        // неизвестный или выключенный аккаунт
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let created_ids = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            // блокировка аккаунтов сериализует параллельное планирование в них: второй запрос
+            // дождётся коммита первого и увидит его job'ы
+            social_accounts::table
+                .filter(social_accounts::id.eq_any(&account_ids))
+                .select(social_accounts::id)
+                .for_update()
+                .load::<i64>(conn)?;
+            let open_jobs: i64 = social_post_jobs::table
+                .filter(social_post_jobs::news_post_id.eq(news_id))
+                .filter(social_post_jobs::social_account_id.eq_any(&account_ids))
+                .filter(social_post_jobs::status.eq_any(["pending", "scheduled", "failed", "processing"]))
+                .count()
+                .get_result(conn)?;
+            if open_jobs > 0 {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+
+            let mut ids = Vec::with_capacity(accounts.len());
+            for (account_id, provider) in &accounts {
+                let payload = req.overrides.get(provider).cloned().unwrap_or_default();
+                let job = create_social_post_job(
+                    conn,
+                    &NewSocialPostJob {
+                        news_post_id: news_id,
+                        social_account_id: *account_id,
+                        status: "scheduled".to_string(),
+                        scheduled_for,
+                        published_at: None,
+                        retry_count: 0,
+                        next_retry_at: None,
+                        external_post_id: None,
+                        error_message: None,
+                        payload_json: serde_json::to_value(payload).unwrap_or_else(|_| serde_json::json!({})),
+                        campaign_id: None,
+                        priority: req.priority.unwrap_or(0),
+                    },
+                )?;
+                audit.record(conn, "social_job.create", "social_job", job.id, None, snapshot(&job))?;
+                ids.push(job.id);
+            }
+            Ok(ids)
+        })
+        .map_err(|e| match e {
+            diesel::result::Error::RollbackTransaction => StatusCode::CONFLICT,
+            e => {
+                eprintln!("schedule_news error: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    let jobs = load_jobs_by_ids(&mut conn, &created_ids).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok((StatusCode::CREATED, Json(jobs)))
+}
+
+// GET /api/admin/social/jobs?status=&account_id=&news_id=&provider=&from=&to=&page=&per_page=
+pub async fn list_social_jobs(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<JobsQuery>,
+) -> Result<Json<SocialJobsResponse>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    Ok(Json(load_jobs_page(&mut conn, &q)?))
+}
+
+/// Меняет статус job'а (при `reschedule` — ещё время и приоритет), если она в одном из `allowed` статусов.
+/// 404 — job нет, 409 — job уже в работе или опубликована.
+fn transition_job(
+    conn: &mut PgConnection,
+    audit: &Audit,
+    id: i64,
+    allowed: &[&str],
+    action: &str,
+    status: &str,
+    reschedule: Option<(DateTime<Utc>, Option<i32>)>,
+) -> Result<(), StatusCode> {
+    let before = load_job(conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !allowed.contains(&before.status.as_str()) {
+        return Err(StatusCode::CONFLICT);
+    }
+    let (scheduled_for, priority) = reschedule.unwrap_or((before.scheduled_for, None));
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let after = diesel::update(
+            social_post_jobs::table
+                .find(id)
+                .filter(social_post_jobs::status.eq(&before.status)),
+        )
+        .set((
+            social_post_jobs::status.eq(status),
+            social_post_jobs::scheduled_for.eq(scheduled_for),
+            social_post_jobs::priority.eq(priority.unwrap_or(before.priority)),
+            social_post_jobs::next_retry_at.eq(None::<DateTime<Utc>>),
+            // админ решил судьбу прерванной публикации сам — без проверки у провайдера
+            social_post_jobs::publish_started_at.eq(None::<DateTime<Utc>>),
+            social_post_jobs::updated_at.eq(Utc::now()),
+        ))
+        .returning(SocialPostJob::as_returning())
+        .get_result::<SocialPostJob>(conn)?;
+
+        audit.record(conn, action, "social_job", id, snapshot(&before), snapshot(&after))
+    })
+    .map_err(|e| match e {
+        // worker успел забрать job между проверкой и update
+        diesel::result::Error::NotFound => StatusCode::CONFLICT,
+        e => {
+            eprintln!("{action} error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })
+}
+
+// POST /api/admin/social/jobs/{id}/cancel
+pub async fn cancel_social_job(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+    audit: Audit,
+) -> Result<Json<SocialJobDto>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    transition_job(&mut conn, &audit, id, &OPEN_JOB_STATUSES, "social_job.cancel", "cancelled", None)?;
+
+    load_jobs_by_ids(&mut conn, &[id])
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// POST /api/admin/social/jobs/{id}/reschedule — также возвращает в план отменённую, упавшую, dead
+// или прогнанную в dry-run job
+pub async fn reschedule_social_job(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+    audit: Audit,
+    Json(req): Json<RescheduleReq>,
+) -> Result<Json<SocialJobDto>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let allowed = ["pending", "scheduled", "failed", "cancelled", "dead", "dry_run"];
+    transition_job(
+        &mut conn,
+        &audit,
+        id,
+        &allowed,
+        "social_job.reschedule",
+        "scheduled",
+        Some((req.scheduled_for, req.priority)),
+    )?;
+
+    load_jobs_by_ids(&mut conn, &[id])
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Что сделать с уже опубликованным постом.
+enum PostChange {
+    Edit { text: Option<String> },
+    Delete,
+}
+
+enum ChangeError {
+    Status(StatusCode),
+    /// Провайдер отказал; текст уже без токенов
+    Provider(String),
+}
+
+impl From<StatusCode> for ChangeError {
+    fn from(code: StatusCode) -> Self {
+        Self::Status(code)
+    }
+}
+
+impl ChangeError {
+    fn into_status(self, action: &str) -> StatusCode {
+        match self {
+            Self::Status(code) => code,
+            Self::Provider(msg) => {
+                eprintln!("{action} error: {msg}");
+                StatusCode::BAD_GATEWAY
+            }
+        }
+    }
+}
+
+/// Сколько правка/удаление держит опубликованную job. Истёкшую блокировку (процесс упал)
+/// следующая правка просто перехватывает.
+const CHANGE_LEASE_MINUTES: i64 = 5;
+
+/// Правит или удаляет пост у провайдера по `external_post_id` job'а.
+/// 409 — job ещё не опубликована или её уже правят, 422 — провайдер так не умеет,
+/// `Provider` — отказ сети. Каждое обращение к провайдеру пишется в attempts.
+async fn change_published_post(
+    conn: &mut PgConnection,
+    audit: &Audit,
+    id: i64,
+    change: PostChange,
+) -> Result<(), ChangeError> {
+    let internal = |e: anyhow::Error| {
+        eprintln!("change_published_post #{id} error: {e:#}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let job = load_job(conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if job.status != "posted" || job.external_post_id.is_none() {
+        return Err(StatusCode::CONFLICT.into());
+    }
+
+    let account = load_account(conn, job.social_account_id)
+        .map_err(internal)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let provider = SocialProvider::from_db(&account.provider).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let publisher = SocialPublishers::new().get(provider).map_err(internal)?;
+    let caps = publisher.capabilities();
+
+    let post = match &change {
+        PostChange::Edit { .. } if !caps.edit => return Err(StatusCode::UNPROCESSABLE_ENTITY.into()),
+        PostChange::Delete if !caps.delete => return Err(StatusCode::UNPROCESSABLE_ENTITY.into()),
+        PostChange::Edit { .. } => Some(
+            news_posts::table
+                .find(job.news_post_id)
+                .first::<NewsPost>(conn)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        ),
+        PostChange::Delete => None,
+    };
+
+    // блокировка строки на время запроса к провайдеру: параллельные правка/удаление/propagate
+    // получают 409, а не отправляют провайдеру второй запрос и не перетирают результат
+    let lock = format!("admin-{}", Uuid::new_v4().simple());
+    let before = diesel::update(
+        social_post_jobs::table
+            .find(id)
+            .filter(social_post_jobs::status.eq("posted"))
+            .filter(
+                social_post_jobs::locked_until
+                    .is_null()
+                    .or(social_post_jobs::locked_until.lt(Utc::now())),
+            ),
+    )
+    .set((
+        social_post_jobs::locked_by.eq(&lock),
+        social_post_jobs::locked_until.eq(Utc::now() + chrono::Duration::minutes(CHANGE_LEASE_MINUTES)),
+    ))
+    .returning(SocialPostJob::as_returning())
+    .get_result::<SocialPostJob>(conn)
+    .optional()
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::CONFLICT)?;
+    let external_post_id = before.external_post_id.clone().ok_or(StatusCode::CONFLICT)?;
+    let published = PublishedPost {
+        external_post_id: &external_post_id,
+        state: before.published_state.as_ref(),
+    };
+
+    let (action, result, payload_json) = match (change, post) {
+        (PostChange::Edit { text }, Some(post)) => {
+            let mut overrides: JobPayload = serde_json::from_value(before.payload_json.clone()).unwrap_or_default();
+            if text.is_some() {
+                overrides.text = text;
+            }
+            let payload_json = serde_json::to_value(&overrides).unwrap_or_else(|_| before.payload_json.clone());
+            let payload = build_payload(
+                &account,
+                provider,
+                caps,
+                &PostContext::from(&post),
+                post.image_url.clone(),
+                overrides,
+                PublishCheckpoint::default(),
+            );
+            let result = publisher.edit(&account, published, &payload).await;
+            ("social_job.edit", result, payload_json)
+        }
+        _ => {
+            let result = publisher.delete(&account, published).await;
+            ("social_job.delete", result, before.payload_json.clone())
+        }
+    };
+
+    let failure = result.err().map(|err| redact_account_secrets(&format!("{err:#}"), &account));
+    let (status, attempt_status) = match (action, &failure) {
+        ("social_job.edit", Some(_)) => ("posted", "edit_failed"),
+        ("social_job.edit", None) => ("posted", "edited"),
+        (_, Some(_)) => ("posted", "delete_failed"),
+        (_, None) => ("deleted", "deleted"),
+    };
+    let payload_json = if failure.is_some() { before.payload_json.clone() } else { payload_json };
+
+    // попытка, новый статус и аудит — одной транзакцией; заодно снимается блокировка
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let attempt_no = social_post_attempts::table
+            .filter(social_post_attempts::social_post_job_id.eq(id))
+            .select(diesel::dsl::max(social_post_attempts::attempt_no))
+            .first::<Option<i32>>(conn)?
+            .unwrap_or(0)
+            + 1;
+        insert_attempt(conn, id, attempt_no, attempt_status, None, failure.clone(), None)
+            .map_err(|e| diesel::result::Error::QueryBuilderError(e.into()))?;
+
+        let after = diesel::update(
+            social_post_jobs::table
+                .find(id)
+                .filter(social_post_jobs::locked_by.eq(&lock)),
+        )
+        .set((
+            social_post_jobs::status.eq(status),
+            social_post_jobs::payload_json.eq(&payload_json),
+            social_post_jobs::locked_by.eq(None::<String>),
+            social_post_jobs::locked_until.eq(None::<DateTime<Utc>>),
+            social_post_jobs::updated_at.eq(Utc::now()),
+        ))
+        .returning(SocialPostJob::as_returning())
+        .get_result::<SocialPostJob>(conn)?;
+
+        if failure.is_none() {
+            audit.record(conn, action, "social_job", id, snapshot(&before), snapshot(&after))?;
+        }
+        Ok(())
+    })
+    .map_err(|e| {
+        eprintln!("{action} error: {:?}", e);
+        ChangeError::Status(match e {
+            // блокировку успели перехватить после истечения
+            diesel::result::Error::NotFound => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })
+    })?;
+
+    match failure {
+        Some(msg) => Err(ChangeError::Provider(msg)),
+        None => Ok(()),
+    }
+}
+
+// POST /api/admin/social/jobs/{id}/edit — текст опубликованного поста у провайдера
+pub async fn edit_social_job(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+    audit: Audit,
+    Json(req): Json<EditPostReq>,
+) -> Result<Json<SocialJobDto>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    change_published_post(&mut conn, &audit, id, PostChange::Edit { text: req.text })
+        .await
+        .map_err(|e| e.into_status("edit_social_job"))?;
+
+    load_jobs_by_ids(&mut conn, &[id])
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// POST /api/admin/social/jobs/{id}/delete — удалить опубликованный пост у провайдера
+pub async fn delete_social_post(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+    audit: Audit,
+) -> Result<Json<SocialJobDto>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    change_published_post(&mut conn, &audit, id, PostChange::Delete)
+        .await
+        .map_err(|e| e.into_status("delete_social_post"))?;
+
+    load_jobs_by_ids(&mut conn, &[id])
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// POST /api/admin/news/{id}/social-jobs/propagate — перенести правку новости в опубликованные посты.
+// Текст рендерится заново по шаблону аккаунта; явный text из overrides остаётся как был.
+pub async fn propagate_news_update(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(news_id): Path<Uuid>,
+    audit: Audit,
+    Json(req): Json<PropagateReq>,
+) -> Result<Json<Vec<PropagateResult>>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let mut query = social_post_jobs::table
+        .inner_join(social_accounts::table)
+        .filter(social_post_jobs::news_post_id.eq(news_id))
+        .filter(social_post_jobs::status.eq("posted"))
+        .filter(social_post_jobs::external_post_id.is_not_null())
+        .select((social_post_jobs::id, social_accounts::provider, social_accounts::account_name))
+        .order(social_post_jobs::id.asc())
+        .into_boxed();
+    if let Some(ids) = &req.job_ids {
+        query = query.filter(social_post_jobs::id.eq_any(ids));
+    }
+    let jobs = query.load::<(i64, String, String)>(&mut conn).map_err(|e| {
+        eprintln!("propagate_news_update error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut results = Vec::with_capacity(jobs.len());
+    for (job_id, provider, account_name) in jobs {
+        let (status, error) =
+            match change_published_post(&mut conn, &audit, job_id, PostChange::Edit { text: None }).await {
+                Ok(()) => ("edited", None),
+                Err(ChangeError::Status(StatusCode::UNPROCESSABLE_ENTITY)) => ("unsupported", None),
+                Err(ChangeError::Status(code)) => ("failed", Some(code.to_string())),
+                Err(ChangeError::Provider(msg)) => ("failed", Some(msg)),
+            };
+        results.push(PropagateResult {
+            job_id,
+            provider,
+            account_name,
+            status,
+            error,
+        });
+    }
+
+    Ok(Json(results))
+}
+
+// GET /api/admin/social/jobs/{id}/attempts
+pub async fn list_job_attempts(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<SocialPostAttempt>>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    if load_job(&mut conn, id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .is_none()
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let attempts = social_post_attempts::table
+        .filter(social_post_attempts::social_post_job_id.eq(id))
+        .order((social_post_attempts::created_at.asc(), social_post_attempts::id.asc()))
+        .select(SocialPostAttempt::as_select())
+        .load(&mut conn)
+        .map_err(|e| {
+            eprintln!("list_job_attempts error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(attempts))
+}
+
+// GET /api/admin/social/worker
+pub async fn social_worker_status(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<WorkerStatusResponse>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let processing = JobsQuery {
+        status: Some("processing".to_string()),
+        per_page: Some(MAX_PER_PAGE),
+        ..Default::default()
+    };
+
+    Ok(Json(WorkerStatusResponse {
+        worker: state.social_worker.report(),
+        processing: load_jobs_page(&mut conn, &processing)?.jobs,
+    }))
+}
+
+/// Превью без сохранения: тот же `build_payload`, что у worker'а, с пустыми переопределениями job'а.
+fn render_preview(
+    account: &SocialAccount,
+    post: &NewsPost,
+    template: Option<String>,
+    image_url: Option<String>,
+) -> Option<PreviewResponse> {
+    let provider = SocialProvider::from_db(&account.provider)?;
+    let caps = SocialPublishers::new().get(provider).ok()?.capabilities();
+
+    let template = template
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| account_template(account, provider));
+    // примеряемый шаблон подставляем как сохранённый
+    let mut account = account.clone();
+    if let Some(settings) = account.settings_json.as_object_mut() {
+        settings.insert("template".to_string(), Value::String(template.clone()));
+    }
+
+    let image_url = image_url.or_else(|| post.image_url.clone());
+    let ctx = PostContext::from(post);
+    let payload = build_payload(
+        &account,
+        provider,
+        caps,
+        &ctx,
+        image_url.clone(),
+        JobPayload::default(),
+        PublishCheckpoint::default(),
+    );
+    let rendered = render_post(&template, &ctx, provider, !payload.media.is_empty());
+
+    Some(PreviewResponse {
+        account_id: account.id,
+        provider: provider.as_str().to_string(),
+        template,
+        problem: caps.check(provider, &payload).err().map(|e| e.to_string()),
+        dry_run: is_dry_run(&account),
+        account_name: account.account_name,
+        text: payload.text,
+        image_url,
+        media: payload.media,
+        link: payload.link,
+        hashtags: payload.hashtags,
+        length: rendered.length,
+        limit: rendered.limit,
+        truncated: rendered.truncated,
+    })
+}
+
+// POST /api/admin/social/accounts/{id}/preview — пост так, как его отправит worker
+pub async fn preview_social_post(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(account_id): Path<i64>,
+    Json(req): Json<PreviewReq>,
+) -> Result<Json<PreviewResponse>, StatusCode> {
+    require_publish(&ctx)?;
+    if let Some(template) = &req.template {
+        validate_template(&serde_json::json!({ "template": template })).map_err(|e| {
+            eprintln!("preview_social_post template rejected: {e}");
+            StatusCode::UNPROCESSABLE_ENTITY
+        })?;
+    }
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    // токены здесь не нужны, поэтому без расшифровки
+    let account = social_accounts::table
+        .find(account_id)
+        .select(SocialAccount::as_select())
+        .first::<SocialAccount>(&mut conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let post = news_posts::table
+        .find(req.news_id)
+        .first::<NewsPost>(&mut conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    render_preview(&account, &post, req.template, req.image_url)
+        .map(Json)
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)
+}
+
+// GET /api/admin/news/{id}/social-preview?account_id= — новость во всех активных аккаунтах (или в одном)
+pub async fn preview_news_social_posts(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(news_id): Path<Uuid>,
+    Query(q): Query<NewsPreviewQuery>,
+) -> Result<Json<Vec<PreviewResponse>>, StatusCode> {
+    require_publish(&ctx)?;
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let post = news_posts::table
+        .find(news_id)
+        .first::<NewsPost>(&mut conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // конкретный аккаунт показываем и выключенным — его могут готовить к запуску
+    let mut query = social_accounts::table.into_boxed();
+    query = match q.account_id {
+        Some(id) => query.filter(social_accounts::id.eq(id)),
+        None => query.filter(social_accounts::is_active.eq(true)),
+    };
+    let accounts = query
+        .order((social_accounts::provider.asc(), social_accounts::id.asc()))
+        .select(SocialAccount::as_select())
+        .load::<SocialAccount>(&mut conn)
+        .map_err(|e| {
+            eprintln!("preview_news_social_posts error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if q.account_id.is_some() && accounts.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(
+        accounts
+            .iter()
+            .filter_map(|account| render_preview(account, &post, None, None))
+            .collect(),
+    ))
+}
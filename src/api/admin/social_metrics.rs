@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Integer, Nullable, Text, Timestamptz, Uuid as SqlUuid};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    api::admin::social_jobs::require_publish,
+    auth::context::AuthContext,
+    social::campaigns::NEWS_KINDS,
+    social::types::SocialProvider,
+};
+
+const DEFAULT_DAYS: i32 = 30;
+const DEFAULT_LIMIT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsReportQuery {
+    /// Только посты этого типа: idiom, news, ...
+    pub kind: Option<String>,
+    /// Только публикации в этой сети
+    pub provider: Option<String>,
+    /// За сколько последних дней брать публикации (1..365, по умолчанию 30)
+    pub days: Option<i32>,
+    pub limit: Option<i64>,
+    /// engagement (по умолчанию) | views | engagement_rate
+    pub sort: Option<String>,
+}
+
+/// Новость с суммой метрик по всем её публикациям за период.
+#[derive(Debug, Serialize, QueryableByName)]
+pub struct NewsEngagementRow {
+    #[diesel(sql_type = SqlUuid)]
+    pub news_id: Uuid,
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+    #[diesel(sql_type = Text)]
+    pub title: String,
+    #[diesel(sql_type = Text)]
+    pub level: String,
+    #[diesel(sql_type = BigInt)]
+    pub posts: i64,
+    #[diesel(sql_type = BigInt)]
+    pub views: i64,
+    #[diesel(sql_type = BigInt)]
+    pub likes: i64,
+    #[diesel(sql_type = BigInt)]
+    pub reactions: i64,
+    #[diesel(sql_type = BigInt)]
+    pub comments: i64,
+    #[diesel(sql_type = BigInt)]
+    pub shares: i64,
+    /// likes + reactions + comments + shares
+    #[diesel(sql_type = BigInt)]
+    pub engagement: i64,
+    /// engagement / views; `None`, если просмотров провайдеры не отдали
+    #[diesel(sql_type = Nullable<Double>)]
+    pub engagement_rate: Option<f64>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub last_published_at: Option<DateTime<Utc>>,
+}
+
+// GET /api/admin/social/metrics/report?kind=&provider=&days=&limit=&sort=
+pub async fn engagement_report(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<MetricsReportQuery>,
+) -> Result<Json<Vec<NewsEngagementRow>>, StatusCode> {
+    require_publish(&ctx)?;
+
+    if q.kind.as_deref().is_some_and(|k| !NEWS_KINDS.contains(&k)) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    if q.provider.as_deref().is_some_and(|p| SocialProvider::from_db(p).is_none()) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    // имя колонки подставляется в SQL — только из белого списка
+    let order_by = match q.sort.as_deref().unwrap_or("engagement") {
+        "engagement" => "engagement DESC",
+        "views" => "views DESC",
+        "engagement_rate" => "engagement_rate DESC NULLS LAST",
+        _ => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+    };
+    let days = q.days.unwrap_or(DEFAULT_DAYS).clamp(1, 365);
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 200);
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    // одна строка на новость: метрики всех её публикаций во всех сетях складываются
+    let sql = format!(
+        r#"
+        SELECT
+            np.id AS news_id,
+            np.kind,
+            np.title,
+            np.level,
+            COUNT(j.id) AS posts,
+            COALESCE(SUM(m.views), 0)::BIGINT AS views,
+            COALESCE(SUM(m.likes), 0)::BIGINT AS likes,
+            COALESCE(SUM(m.reactions), 0)::BIGINT AS reactions,
+            COALESCE(SUM(m.comments), 0)::BIGINT AS comments,
+            COALESCE(SUM(m.shares), 0)::BIGINT AS shares,
+            (COALESCE(SUM(m.likes), 0) + COALESCE(SUM(m.reactions), 0)
+                + COALESCE(SUM(m.comments), 0) + COALESCE(SUM(m.shares), 0))::BIGINT AS engagement,
+            (COALESCE(SUM(m.likes), 0) + COALESCE(SUM(m.reactions), 0)
+                + COALESCE(SUM(m.comments), 0) + COALESCE(SUM(m.shares), 0))::DOUBLE PRECISION
+                / NULLIF(SUM(m.views), 0) AS engagement_rate,
+            MAX(j.published_at) AS last_published_at
+        FROM social_post_jobs j
+        JOIN news_posts np ON np.id = j.news_post_id
+        JOIN social_accounts a ON a.id = j.social_account_id
+        LEFT JOIN social_post_metrics m ON m.social_post_job_id = j.id
+        WHERE j.status = 'posted'
+          AND j.published_at >= now() - make_interval(days => $1)
+          AND ($2::TEXT IS NULL OR np.kind = $2)
+          AND ($3::TEXT IS NULL OR a.provider = $3)
+        GROUP BY np.id
+        ORDER BY {order_by}, np.id
+        LIMIT $4
+        "#
+    );
+
+    let rows = diesel::sql_query(sql)
+        .bind::<Integer, _>(days)
+        .bind::<Nullable<Text>, _>(q.kind)
+        .bind::<Nullable<Text>, _>(q.provider)
+        .bind::<BigInt, _>(limit)
+        .load::<NewsEngagementRow>(&mut conn)
+        .map_err(|e| {
+            eprintln!("engagement_report error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(rows))
+}
@@ -1,47 +1,68 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use crate::AppState;
+use crate::models::social::SocialAccount;
+use crate::social::accounts::{load_active_accounts, redact_account_secrets};
 use crate::social::adapters::vk::VkPublisher;
 use crate::social::adapters::instagram::InstagramPublisher;
-use crate::social::adapters::{PublishPayload, SocialPublisher};
-use crate::social::instagram_config::instagram_account_from_env;
+use crate::social::adapters::{MediaItem, PublishPayload, SocialPublisher};
+use crate::social::types::SocialProvider;
 
-pub async fn test_instagram_post() -> Result<impl IntoResponse, (StatusCode, String)> {
-    let account = instagram_account_from_env()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+/// Первый активный аккаунт провайдера из social_accounts.
+fn first_active_account(
+    state: &AppState,
+    provider: SocialProvider,
+) -> Result<SocialAccount, (StatusCode, String)> {
+    let mut conn = state
+        .pool
+        .get()
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+
+    load_active_accounts(&mut conn, Some(provider))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("no active {} account", provider.as_str())))
+}
+
+pub async fn test_instagram_post(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let account = first_active_account(&state, SocialProvider::Instagram)?;
 
     let publisher = InstagramPublisher::new();
 
     let payload = PublishPayload {
         text: format!("Test from server at {}", chrono::Utc::now()),
-        image_url: Some(
-            "https://upload.wikimedia.org/wikipedia/commons/3/3a/Cat03.jpg".to_string(),
-        ),
+        media: vec![MediaItem::image(
+            "https://upload.wikimedia.org/wikipedia/commons/3/3a/Cat03.jpg",
+        )],
+        ..Default::default()
     };
 
     let result = publisher
         .publish(&account, payload)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, redact_account_secrets(&e.to_string(), &account)))?;
 
     Ok(format!("ok: {:?}", result.external_post_id))
 }
 
-use crate::social::vk_config::vk_account_from_env;
-
-pub async fn test_vk_post() -> Result<impl IntoResponse, (StatusCode, String)> {
-    let account = vk_account_from_env()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+pub async fn test_vk_post(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let account = first_active_account(&state, SocialProvider::Vk)?;
 
     let publisher = VkPublisher::new();
 
     let payload = PublishPayload {
         text: format!("Test VK post from server at {}", chrono::Utc::now()),
-        image_url: None,
+        ..Default::default()
     };
 
     let result = publisher
         .publish(&account, payload)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, redact_account_secrets(&e.to_string(), &account)))?;
 
     Ok(format!("ok: {:?}", result.external_post_id))
-}
\ No newline at end of file
+}
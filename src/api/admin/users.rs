@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use diesel::sql_types::Text;
 use axum::{
     extract::{State, Path, Query, Extension},
@@ -12,19 +14,65 @@ use crate::models::{UpdateUserDb, UpdateUserReq, users::{AdminCreateUserReq, New
 
 use crate::{
     AppState,
+    auth::audit::{snapshot, Audit},
     auth::context::AuthContext,
-    models::audit::write_audit,
 };
-use crate::schema::{users, user_roles, roles, local_credentials};
+use crate::schema::{
+    class_members, local_credentials, roles, school_classes, telegram_link_codes, telegram_links, user_roles, users,
+};
 use crate::schema::users::dsl as u;
 
 
 
-#[derive(Deserialize)]
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 200;
+
+/// Фильтры/сортировка/страница списка пользователей (JSON API и askama-страница).
+/// Строки, чтобы пустые поля HTML-формы означали "не задано".
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct UsersQuery {
+    /// поиск по username/email/full_name/auth0_id
     pub q: Option<String>,
+    /// ключ роли
+    pub role: Option<String>,
+    /// название класса
+    pub class: Option<String>,
+    /// `true` / `false`
+    pub active: Option<String>,
+    /// показывать и удалённых (soft delete)
+    pub include_deleted: Option<String>,
+    /// created_at | username | full_name | email
+    pub sort: Option<String>,
+    /// asc | desc
+    pub order: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+fn non_empty(v: &Option<String>) -> Option<&str> {
+    v.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn parse_bool(v: &Option<String>) -> Result<Option<bool>, StatusCode> {
+    match non_empty(v) {
+        None => Ok(None),
+        Some("true" | "1" | "on") => Ok(Some(true)),
+        Some("false" | "0" | "off") => Ok(Some(false)),
+        Some(_) => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+impl UsersQuery {
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+    }
 }
-#[derive(serde::Serialize)]
+
+#[derive(Debug, Clone, Serialize)]
 pub struct AdminUserDto {
     pub id: Uuid,
     pub username: Option<String>,
@@ -32,12 +80,43 @@ pub struct AdminUserDto {
     pub full_name: Option<String>,
     pub gender: Option<String>,
     pub created_at: Option<chrono::NaiveDateTime>,
-    pub role: String, // ОДНА роль
+    pub roles: Vec<String>,
+    pub classes: Vec<String>,
+    pub is_active: bool,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct UsersResponse {
     pub users: Vec<AdminUserDto>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+pub(crate) fn user_snapshot(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Option<serde_json::Value>, diesel::result::Error> {
+    let Some(user) = u::users
+        .find(user_id)
+        .select(User::as_select())
+        .first::<User>(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    let role_keys: Vec<String> = user_roles::table
+        .inner_join(roles::table.on(roles::id.eq(user_roles::role_id)))
+        .filter(user_roles::user_id.eq(user_id))
+        .select(roles::key)
+        .order(roles::key.asc())
+        .load(conn)?;
+
+    let mut v = snapshot(&user).unwrap_or_default();
+    v["roles"] = serde_json::json!(role_keys);
+    Ok(Some(v))
 }
 
 #[derive(QueryableByName)]
@@ -45,72 +124,169 @@ struct HashResult {
     #[diesel(sql_type = Text)]
     crypt: String,
 }
-pub async fn list_users(
-    State(state): State<AppState>,
-    Query(params): Query<UsersQuery>,
-) -> Result<Json<UsersResponse>, StatusCode> {
-    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
-    let mut q = users::table
-        .left_join(user_roles::table.on(user_roles::user_id.eq(u::id)))
-        .left_join(roles::table.on(roles::id.eq(user_roles::role_id)))
-        .into_boxed();
+/// bcrypt-хэш пароля через pgcrypto (тот же формат, что проверяет login).
+pub(crate) fn hash_password(conn: &mut PgConnection, password: &str) -> Result<String, diesel::result::Error> {
+    let result: HashResult = diesel::sql_query("SELECT crypt($1, gen_salt('bf')) as crypt")
+        .bind::<diesel::sql_types::Text, _>(password)
+        .get_result(conn)?;
+    Ok(result.crypt)
+}
+/// users + фильтры из запроса; используется и для выборки страницы, и для count(*).
+fn filtered_users<'a>(params: &UsersQuery) -> Result<users::BoxedQuery<'a, diesel::pg::Pg>, StatusCode> {
+    let mut q = users::table.into_boxed();
+
+    if parse_bool(&params.include_deleted)? != Some(true) {
+        q = q.filter(u::deleted_at.is_null());
+    }
+
+    if let Some(active) = parse_bool(&params.active)? {
+        q = q.filter(u::is_active.eq(active));
+    }
 
-    if let Some(s) = params.q.as_ref().filter(|s| !s.trim().is_empty()) {
-        let pattern = format!("%{}%", s.trim());
+    if let Some(s) = non_empty(&params.q) {
+        let pattern = format!("%{}%", s);
         q = q.filter(
             u::email.ilike(pattern.clone())
                 .or(u::username.ilike(pattern.clone()))
+                .or(u::full_name.ilike(pattern.clone()))
                 .or(u::auth0_id.ilike(pattern)),
         );
     }
 
-    let rows: Vec<(
-        Uuid,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        Option<chrono::NaiveDateTime>,
-        Option<String>,
-    )> = q
-        .select((
-            u::id,
-            u::username,
-            u::email,
-            u::full_name,
-            u::gender,
-            u::created_at,
-            roles::key.nullable(),
-        ))
-        .order(u::created_at.desc())
-        .load(&mut conn)
+    if let Some(role_key) = non_empty(&params.role) {
+        let with_role = user_roles::table
+            .inner_join(roles::table.on(roles::id.eq(user_roles::role_id)))
+            .filter(roles::key.eq(role_key.to_lowercase()))
+            .select(user_roles::user_id);
+        q = q.filter(u::id.eq_any(with_role));
+    }
+
+    if let Some(class_name) = non_empty(&params.class) {
+        let in_class = class_members::table
+            .inner_join(school_classes::table)
+            .filter(school_classes::name.eq(class_name.to_string()))
+            .select(class_members::user_id);
+        q = q.filter(u::id.eq_any(in_class));
+    }
+
+    Ok(q)
+}
+
+/// user_id -> отсортированный список (ключи ролей или названия классов)
+type UserLabels = HashMap<Uuid, Vec<String>>;
+
+/// Роли и классы для набора пользователей.
+fn load_user_labels(
+    conn: &mut PgConnection,
+    ids: &[Uuid],
+) -> Result<(UserLabels, UserLabels), diesel::result::Error> {
+    let mut by_role = UserLabels::new();
+    let role_rows: Vec<(Uuid, String)> = user_roles::table
+        .inner_join(roles::table.on(roles::id.eq(user_roles::role_id)))
+        .filter(user_roles::user_id.eq_any(ids))
+        .select((user_roles::user_id, roles::key))
+        .order(roles::key.asc())
+        .load(conn)?;
+    for (uid, key) in role_rows {
+        by_role.entry(uid).or_default().push(key);
+    }
+
+    let mut by_class = UserLabels::new();
+    let class_rows: Vec<(Uuid, String)> = class_members::table
+        .inner_join(school_classes::table)
+        .filter(class_members::user_id.eq_any(ids))
+        .select((class_members::user_id, school_classes::name))
+        .order(school_classes::name.asc())
+        .load(conn)?;
+    for (uid, name) in class_rows {
+        by_class.entry(uid).or_default().push(name);
+    }
+
+    Ok((by_role, by_class))
+}
+
+/// Одна страница пользователей с total (используется JSON API и askama-страницей).
+pub fn load_users_page(conn: &mut PgConnection, params: &UsersQuery) -> Result<UsersResponse, StatusCode> {
+    let page = params.page();
+    let per_page = params.per_page();
+    let desc = match non_empty(&params.order) {
+        None | Some("desc") => true,
+        Some("asc") => false,
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let total: i64 = filtered_users(params)?
+        .count()
+        .get_result(conn)
+        .map_err(|e| {
+            eprintln!("list_users count error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let q = filtered_users(params)?;
+    let q = match (non_empty(&params.sort).unwrap_or("created_at"), desc) {
+        ("created_at", true) => q.order(u::created_at.desc().nulls_last()),
+        ("created_at", false) => q.order(u::created_at.asc().nulls_first()),
+        ("username", true) => q.order(u::username.desc()),
+        ("username", false) => q.order(u::username.asc()),
+        ("full_name", true) => q.order(u::full_name.desc()),
+        ("full_name", false) => q.order(u::full_name.asc()),
+        ("email", true) => q.order(u::email.desc()),
+        ("email", false) => q.order(u::email.asc()),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    let rows: Vec<User> = q
+        .then_order_by(u::id.asc())
+        .limit(per_page)
+        .offset((page - 1) * per_page)
+        .select(User::as_select())
+        .load(conn)
         .map_err(|e| {
             eprintln!("list_users error: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    let ids: Vec<Uuid> = rows.iter().map(|r| r.id).collect();
+    let (mut by_role, mut by_class) = load_user_labels(conn, &ids).map_err(|e| {
+        eprintln!("list_users roles/classes error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
     let users = rows
         .into_iter()
-        .map(|(user_id, user_username, user_email, user_full_name, user_gender, user_created_at, role_key)| {
-            AdminUserDto {
-                id: user_id,
-                username: user_username,
-                email: user_email,
-                full_name: user_full_name,
-                gender: user_gender,
-                created_at: user_created_at,
-                role: role_key.unwrap_or_else(|| "guest".to_string()),
-            }
+        .map(|r| AdminUserDto {
+            roles: by_role.remove(&r.id).unwrap_or_default(),
+            classes: by_class.remove(&r.id).unwrap_or_default(),
+            id: r.id,
+            username: r.username,
+            email: r.email,
+            full_name: r.full_name,
+            gender: r.gender,
+            created_at: r.created_at,
+            is_active: r.is_active,
+            deleted_at: r.deleted_at,
         })
         .collect();
 
-    Ok(Json(UsersResponse { users }))
+    Ok(UsersResponse { users, total, page, per_page })
+}
+
+// GET /api/admin/users?q=&role=&class=&active=&include_deleted=&sort=&order=&page=&per_page=
+pub async fn list_users(
+    State(state): State<AppState>,
+    Query(params): Query<UsersQuery>,
+) -> Result<Json<UsersResponse>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    Ok(Json(load_users_page(&mut conn, &params)?))
 }
 
 pub async fn create_user(
     State(state): State<AppState>,
     Extension(ctx): Extension<AuthContext>,
+    audit: Audit,
     Json(payload): Json<AdminCreateUserReq>,
 ) -> Result<Json<User>, StatusCode> {
     // Если хотите не только admin-роль, а permission:
@@ -128,12 +304,6 @@ pub async fn create_user(
         email: Some(payload.email.trim().to_string()),
     };
 
-    let created = diesel::insert_into(u::users)
-        .values(&new_user)
-        .returning(User::as_select())
-        .get_result::<User>(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
     // audit
 //    let hash: String = diesel::select(diesel::dsl::sql::<diesel::sql_types::Text>(
 //        "crypt($1, gen_salt('bf'))"
@@ -142,26 +312,11 @@ pub async fn create_user(
 //    .get_result(&mut conn)
 //    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-let result: HashResult  = diesel::sql_query("SELECT crypt($1, gen_salt('bf')) as crypt")
-        .bind::<diesel::sql_types::Text, _>(&payload.password)
-        .get_result(&mut conn)
-       .map_err(|e| {
+    let hash = hash_password(&mut conn, &payload.password).map_err(|e| {
         eprintln!("Failed to generate password hash: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    let hash = result.crypt;
-    diesel::insert_into(local_credentials::table)
-        .values(&NewLocalCredentialDb {
-            user_id: created.id,
-            password_hash: hash,
-        })
-        .execute(&mut conn)
-        .map_err(|e| {
-            eprintln!("Failed to save password credentials: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
     let role_key = payload.role.trim().to_lowercase();
 
     let role_id: Uuid = roles::table
@@ -173,35 +328,40 @@ let result: HashResult  = diesel::sql_query("SELECT crypt($1, gen_salt('bf')) as
         StatusCode::BAD_REQUEST // или NOT_FOUND, если хотите различать
     })?;
 
-    diesel::insert_into(user_roles::table)
-        .values((
-            user_roles::user_id.eq(created.id),
-            user_roles::role_id.eq(role_id),
-            user_roles::assigned_by.eq(Some(ctx.user_id)),
-        ))
-        .on_conflict_do_nothing()
-        .execute(&mut conn)
-        .map_err(|e| {
-        eprintln!("Failed to assign role: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    // пользователь, пароль, роль и запись аудита — вместе или ничего
+    let created = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let created = diesel::insert_into(u::users)
+                .values(&new_user)
+                .returning(User::as_select())
+                .get_result::<User>(conn)?;
+
+            diesel::insert_into(local_credentials::table)
+                .values(&NewLocalCredentialDb {
+                    user_id: created.id,
+                    password_hash: hash,
+                    must_change_password: false,
+                })
+                .execute(conn)?;
 
-    write_audit(
-        &mut conn,
-        Some(ctx.user_id),
-        "user.create",
-        "user",
-        created.id,
-            Some(serde_json::json!({
-            "auth0_id": created.auth0_id,
-            "email": created.email,
-            "username": created.username,
-            "role": role_key,
-        })),
-    ).map_err(|e| {
-        eprintln!("Failed to write audit log: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+            diesel::insert_into(user_roles::table)
+                .values((
+                    user_roles::user_id.eq(created.id),
+                    user_roles::role_id.eq(role_id),
+                    user_roles::assigned_by.eq(Some(ctx.user_id)),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            let after = user_snapshot(conn, created.id)?;
+            audit.record(conn, "user.create", "user", created.id, None, after)?;
+
+            Ok(created)
+        })
+        .map_err(|e| {
+            eprintln!("create_user error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
     Ok(Json(created))
 }
@@ -210,79 +370,237 @@ pub async fn update_user(
     Path(user_id): Path<Uuid>,
     State(state): State<AppState>,
     Extension(ctx): Extension<AuthContext>,
+    audit: Audit,
     Json(req): Json<UpdateUserReq>,
 ) -> Result<Json<User>, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
+    let before = user_snapshot(&mut conn, user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
     let (changes, role_opt) = <(UpdateUserDb, Option<String>)>::from(req);
 
-    // 1) update users
-    let updated = diesel::update(u::users.find(user_id))
-        .set(&changes)
-        .get_result::<User>(&mut conn)
-        .map_err(|e| {
-            if let diesel::result::Error::NotFound = e {
-                StatusCode::NOT_FOUND
-            } else {
+    let role_id = match role_opt.map(|r| r.trim().to_lowercase()).filter(|r| !r.is_empty()) {
+        Some(role_key) => Some(
+            roles::table
+                .filter(roles::key.eq(&role_key))
+                .select(roles::id)
+                .first::<Uuid>(&mut conn)
+                .map_err(|_| StatusCode::BAD_REQUEST)?,
+        ),
+        None => None,
+    };
+
+    let updated = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            // 1) update users
+            let updated = diesel::update(u::users.find(user_id))
+                .set(&changes)
+                .get_result::<User>(conn)?;
+
+            // 2) role (если пришла) только добавляется: ролей у пользователя может быть несколько,
+            // снимать их — через DELETE /users/{id}/roles/{role_key}
+            if let Some(rid) = role_id {
+                diesel::insert_into(user_roles::table)
+                    .values((
+                        user_roles::user_id.eq(user_id),
+                        user_roles::role_id.eq(rid),
+                        user_roles::assigned_by.eq(Some(ctx.user_id)),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+
+            let after = user_snapshot(conn, user_id)?;
+            audit.record(conn, "user.update", "user", user_id, Some(before), after)?;
+
+            Ok(updated)
+        })
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+            e => {
+                eprintln!("update_user error: {:?}", e);
                 StatusCode::INTERNAL_SERVER_ERROR
             }
         })?;
 
-    // 2) update role (если пришла)
-    if let Some(role_key_raw) = role_opt {
-        let role_key = role_key_raw.trim().to_lowercase();
-        if !role_key.is_empty() {
-            let rid: Uuid = roles::table
-                .filter(roles::key.eq(&role_key))
-                .select(roles::id)
-                .first(&mut conn)
-                .map_err(|_| StatusCode::BAD_REQUEST)?;
+    Ok(Json(updated))
+}
 
-            // если у тебя "одна роль на пользователя" — заменяем:
-            diesel::delete(user_roles::table.filter(user_roles::user_id.eq(user_id)))
-                .execute(&mut conn)
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-            diesel::insert_into(user_roles::table)
-                .values((
-                    user_roles::user_id.eq(user_id),
-                    user_roles::role_id.eq(rid),
-                    user_roles::assigned_by.eq(Some(ctx.user_id)),
-                ))
-                .execute(&mut conn)
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        }
+/// Меняет is_active; при блокировке отзывает все сессии.
+fn set_user_active(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    audit: &Audit,
+    user_id: Uuid,
+    active: bool,
+) -> Result<Json<User>, StatusCode> {
+    if !active && user_id == ctx.user_id {
+        return Err(StatusCode::CONFLICT);
     }
 
+    let before = user_snapshot(conn, user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if before["deleted_at"] != serde_json::Value::Null {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let updated = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated = diesel::update(u::users.find(user_id))
+                .set(u::is_active.eq(active))
+                .returning(User::as_select())
+                .get_result::<User>(conn)?;
+
+            if !active {
+                crate::auth::session::revoke_user_sessions(conn, user_id)?;
+            }
+
+            let after = user_snapshot(conn, user_id)?;
+            let action = if active { "user.activate" } else { "user.deactivate" };
+            audit.record(conn, action, "user", user_id, Some(before), after)?;
+
+            Ok(updated)
+        })
+        .map_err(|e| {
+            eprintln!("set_user_active error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     Ok(Json(updated))
 }
 
+// POST /api/admin/users/{id}/deactivate
+pub async fn deactivate_user(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    audit: Audit,
+) -> Result<Json<User>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    set_user_active(&mut conn, &ctx, &audit, user_id, false)
+}
+
+// POST /api/admin/users/{id}/activate
+pub async fn activate_user(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    audit: Audit,
+) -> Result<Json<User>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    set_user_active(&mut conn, &ctx, &audit, user_id, true)
+}
 
+/// Soft delete: строка остаётся (на неё ссылаются content_items, subjects, audit_log),
+/// вход и сессии блокируются. Окончательное удаление — `purge_user`.
 pub async fn delete_user(
     Path(user_id): Path<Uuid>,
     State(state): State<AppState>,
     Extension(ctx): Extension<AuthContext>,
+    audit: Audit,
 ) -> Result<StatusCode, StatusCode> {
-    // if !ctx.has_perm("users.delete") { return Err(StatusCode::FORBIDDEN); }
+    if user_id == ctx.user_id {
+        return Err(StatusCode::CONFLICT);
+    }
 
     let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
 
-    let affected = diesel::delete(u::users.find(user_id))
-        .execute(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let before = user_snapshot(&mut conn, user_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    if affected == 0 {
-        return Err(StatusCode::NOT_FOUND);
+    // повторный DELETE ничего не меняет
+    if before["deleted_at"] != serde_json::Value::Null {
+        return Ok(StatusCode::NO_CONTENT);
     }
 
-    write_audit(
-        &mut conn,
-        Some(ctx.user_id),
-        "user.delete",
-        "user",
-        user_id,
-        None,
-    ).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(u::users.find(user_id))
+            .set((
+                u::is_active.eq(false),
+                u::deleted_at.eq(Some(chrono::Utc::now().naive_utc())),
+            ))
+            .execute(conn)?;
+
+        crate::auth::session::revoke_user_sessions(conn, user_id)?;
+
+        let after = user_snapshot(conn, user_id)?;
+        audit.record(conn, "user.delete", "user", user_id, Some(before), after)
+    })
+    .map_err(|e| {
+        eprintln!("delete_user error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Окончательное удаление (только для уже удалённых): персональные данные затираются,
+/// креды/сессии/роли/классы удаляются, сама строка users остаётся анонимной.
+/// Из прошлых записей audit_log по этому пользователю убираются снимки before/after.
+pub async fn purge_user(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    audit: Audit,
+) -> Result<StatusCode, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let deleted_at: Option<chrono::NaiveDateTime> = u::users
+        .find(user_id)
+        .select(u::deleted_at)
+        .first(&mut conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if deleted_at.is_none() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let short = user_id.simple().to_string();
+
+        diesel::update(u::users.find(user_id))
+            .set((
+                u::auth0_id.eq(format!("deleted|{short}")),
+                u::username.eq(Some(format!("deleted-{}", &short[..12]))),
+                u::email.eq(None::<String>),
+                u::full_name.eq(None::<String>),
+                u::gender.eq(None::<String>),
+                u::is_active.eq(false),
+            ))
+            .execute(conn)?;
+
+        diesel::delete(local_credentials::table.filter(local_credentials::user_id.eq(user_id))).execute(conn)?;
+        diesel::delete(crate::schema::sessions::table.filter(crate::schema::sessions::user_id.eq(user_id)))
+            .execute(conn)?;
+        diesel::delete(user_roles::table.filter(user_roles::user_id.eq(user_id))).execute(conn)?;
+        // привязка к Telegram — идентификатор человека, а не учебные данные
+        diesel::delete(telegram_links::table.filter(telegram_links::user_id.eq(user_id))).execute(conn)?;
+        diesel::delete(telegram_link_codes::table.filter(telegram_link_codes::user_id.eq(user_id))).execute(conn)?;
+        diesel::delete(
+            class_members::table.filter(class_members::user_id.eq(user_id)),
+        )
+        .execute(conn)?;
+
+        diesel::sql_query(
+            "UPDATE audit_log
+             SET metadata = metadata - 'before' - 'after' - 'changes'
+             WHERE entity_type = 'user' AND entity_id = $1 AND metadata IS NOT NULL",
+        )
+        .bind::<Text, _>(user_id.to_string())
+        .execute(conn)?;
+
+        audit.record(conn, "user.purge", "user", user_id, None, None)
+    })
+    .map_err(|e| {
+        eprintln!("purge_user error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
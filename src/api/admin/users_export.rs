@@ -0,0 +1,187 @@
+use std::io::{Cursor, Write};
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use diesel::prelude::*;
+use diesel::sql_types::{Jsonb, Uuid as SqlUuid};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{AppState, auth::audit::Audit, auth::context::AuthContext, schema::users};
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// `json` (по умолчанию) или `zip` — по файлу на раздел
+    pub format: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct JsonRow {
+    #[diesel(sql_type = Jsonb)]
+    data: Value,
+}
+
+/// Результат подзапроса `sql` (с параметром $1 = user_id) как JSON-массив.
+fn section(conn: &mut PgConnection, sql: &str, user_id: Uuid) -> Result<Value, diesel::result::Error> {
+    let row: JsonRow = diesel::sql_query(format!(
+        "SELECT COALESCE(json_agg(t), '[]'::json)::jsonb AS data FROM ({sql}) t"
+    ))
+    .bind::<SqlUuid, _>(user_id)
+    .get_result(conn)?;
+    Ok(row.data)
+}
+
+/// Все данные пользователя: профиль, роли и классы, сессии, учебные наборы и прогресс, привязка Telegram,
+/// авторский контент и его действия из audit_log. Хэши паролей и sid не выгружаются.
+pub fn build_user_export(conn: &mut PgConnection, user_id: Uuid) -> Result<Value, diesel::result::Error> {
+    let profile = section(
+        conn,
+        "SELECT id, username, email, full_name, gender, created_at, is_active, deleted_at
+         FROM users WHERE id = $1",
+        user_id,
+    )?;
+
+    Ok(json!({
+        "exported_at": chrono::Utc::now(),
+        "user_id": user_id,
+        "profile": profile.get(0).cloned().unwrap_or(Value::Null),
+        "roles": section(conn,
+            "SELECT r.key, r.name, ur.assigned_at
+             FROM user_roles ur JOIN roles r ON r.id = ur.role_id
+             WHERE ur.user_id = $1 ORDER BY r.key", user_id)?,
+        "classes": section(conn,
+            "SELECT c.name, m.added_at
+             FROM class_members m JOIN school_classes c ON c.id = m.class_id
+             WHERE m.user_id = $1 ORDER BY c.name", user_id)?,
+        "sessions": section(conn,
+            "SELECT id, created_at, last_seen_at, expires_at, revoked_at
+             FROM sessions WHERE user_id = $1 ORDER BY created_at", user_id)?,
+        "study_history": {
+            "quizlet_sets": section(conn,
+                "SELECT s.id, s.slug, s.title, s.language_level, s.created_at, s.updated_at,
+                        (SELECT count(*) FROM quizlet_cards c WHERE c.set_id = s.id) AS cards
+                 FROM quizlet_sets s WHERE s.owner_id = $1 ORDER BY s.created_at", user_id)?,
+            "quizlet_folders": section(conn,
+                "SELECT f.id, f.title, f.created_at,
+                        (SELECT coalesce(json_agg(fs.set_id ORDER BY fs.position), '[]'::json)
+                         FROM quizlet_folder_sets fs WHERE fs.folder_id = f.id) AS set_ids
+                 FROM quizlet_folders f WHERE f.owner_id = $1 ORDER BY f.created_at", user_id)?,
+            "card_progress": section(conn,
+                "SELECT p.card_id, c.term, p.box_no, p.correct_count, p.wrong_count, p.due_at, p.last_reviewed_at
+                 FROM quizlet_card_progress p JOIN quizlet_cards c ON c.id = p.card_id
+                 WHERE p.user_id = $1 ORDER BY p.last_reviewed_at", user_id)?,
+        },
+        "telegram": section(conn,
+            "SELECT telegram_user_id, telegram_username, linked_at FROM telegram_links WHERE user_id = $1", user_id)?,
+        "authored_content": {
+            "content_items": section(conn,
+                "SELECT id, kind, status, title, grade_level, created_at, published_at
+                 FROM content_items WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "content_versions": section(conn,
+                "SELECT content_id, version, change_summary, created_at
+                 FROM content_versions WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "subjects": section(conn,
+                "SELECT id, name, created_at FROM subjects WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "publishers": section(conn,
+                "SELECT id, key, name, created_at FROM publishers WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "course_series": section(conn,
+                "SELECT id, key, title, created_at FROM course_series WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "course_levels": section(conn,
+                "SELECT id, level_code, title, created_at FROM course_levels WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "course_units": section(conn,
+                "SELECT id, unit_code, title, created_at FROM course_units WHERE created_by = $1 ORDER BY created_at", user_id)?,
+            "course_modules": section(conn,
+                "SELECT id, module_code, title, created_at FROM course_modules WHERE created_by = $1 ORDER BY created_at", user_id)?,
+        },
+        "activity": section(conn,
+            "SELECT action, entity_type, entity_id, created_at
+             FROM audit_log WHERE actor_id = $1 ORDER BY created_at", user_id)?,
+    }))
+}
+
+/// ZIP: `<раздел>.json` для каждого ключа верхнего уровня + `export.json` целиком.
+fn export_zip(export: &Value) -> Result<Vec<u8>, zip::result::ZipError> {
+    let mut zw = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let opts = zip::write::SimpleFileOptions::default();
+
+    if let Some(obj) = export.as_object() {
+        for (key, value) in obj.iter().filter(|(_, v)| v.is_object() || v.is_array()) {
+            zw.start_file(format!("{key}.json"), opts)?;
+            zw.write_all(serde_json::to_string_pretty(value).unwrap_or_default().as_bytes())?;
+        }
+    }
+    zw.start_file("export.json", opts)?;
+    zw.write_all(serde_json::to_string_pretty(export).unwrap_or_default().as_bytes())?;
+
+    Ok(zw.finish()?.into_inner())
+}
+
+fn export_response(conn: &mut PgConnection, user_id: Uuid, format: Option<&str>) -> Result<Response, StatusCode> {
+    let exists = users::table
+        .find(user_id)
+        .select(users::id)
+        .first::<Uuid>(conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if exists.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let export = build_user_export(conn, user_id).map_err(|e| {
+        eprintln!("user export error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if format != Some("zip") {
+        return Ok(Json(export).into_response());
+    }
+
+    let bytes = export_zip(&export).map_err(|e| {
+        eprintln!("user export zip error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let filename = format!("user-{}-{}.zip", user_id, chrono::Utc::now().format("%Y%m%d"));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+// GET /api/admin/users/{id}/export?format=json|zip
+pub async fn export_user(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Query(q): Query<ExportQuery>,
+    audit: Audit,
+) -> Result<Response, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let resp = export_response(&mut conn, user_id, q.format.as_deref())?;
+
+    // выгрузка персональных данных тоже попадает в аудит
+    audit
+        .record(&mut conn, "user.export", "user", user_id, None, None)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(resp)
+}
+
+// GET /api/me/export?format=json|zip — свои данные
+pub async fn export_me(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    export_response(&mut conn, ctx.user_id, q.format.as_deref())
+}
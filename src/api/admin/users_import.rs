@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+
+use askama::Template;
+use axum::{
+    body::Bytes,
+    extract::{Extension, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
+};
+use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::admin::views::CredentialsSheetTemplate;
+use crate::api::admin::users::{hash_password, user_snapshot};
+use crate::models::users::{NewLocalCredentialDb, NewUserDb, User};
+use crate::schema::{class_members, local_credentials, roles, school_classes, user_roles, users};
+use crate::{
+    AppState,
+    auth::audit::Audit,
+    auth::context::AuthContext,
+};
+
+const IMPORT_PERMISSION: &str = "users.import";
+const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+const MAX_ROWS: usize = 2000;
+const DEFAULT_ROLE: &str = "student";
+const PASSWORD_LEN: usize = 10;
+// без похожих символов (0/O, 1/l/I) — пароль переписывают с бумажки
+const PASSWORD_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    /// `json` (по умолчанию) или `sheet` — HTML-лист с логинами и паролями для печати
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRow {
+    /// номер строки в файле (1 — заголовок)
+    pub line: usize,
+    pub username: String,
+    pub full_name: String,
+    pub email: Option<String>,
+    pub gender: Option<String>,
+    pub roles: Vec<String>,
+    pub class_name: Option<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedCredential {
+    pub user_id: Uuid,
+    pub username: String,
+    pub full_name: String,
+    pub class_name: Option<String>,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    /// классы, которых ещё нет в school_classes (будут созданы)
+    pub new_classes: Vec<String>,
+    pub rows: Vec<ImportRow>,
+    /// одноразовые пароли; только при реальном импорте, больше нигде не хранятся
+    pub credentials: Vec<ImportedCredential>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Username,
+    FullName,
+    Email,
+    Gender,
+    Roles,
+    Class,
+}
+
+fn column_for(header: &str) -> Option<Column> {
+    match header.trim().to_lowercase().as_str() {
+        "username" | "login" | "логин" => Some(Column::Username),
+        "full_name" | "name" | "фио" | "имя" => Some(Column::FullName),
+        "email" | "e-mail" | "почта" => Some(Column::Email),
+        "gender" | "пол" => Some(Column::Gender),
+        "roles" | "role" | "роль" | "роли" => Some(Column::Roles),
+        "class" | "класс" => Some(Column::Class),
+        _ => None,
+    }
+}
+
+fn is_xlsx(headers: &HeaderMap, body: &[u8]) -> bool {
+    let ct = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    // xlsx — это zip, начинается с "PK"
+    ct.starts_with(XLSX_MIME) || body.starts_with(b"PK")
+}
+
+fn read_csv(body: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let body = body.strip_prefix("\u{feff}".as_bytes()).unwrap_or(body);
+
+    // Excel в русской локали сохраняет CSV через ';'
+    let first_line = body.split(|b| *b == b'\n').next().unwrap_or(&[]);
+    let semicolons = first_line.iter().filter(|b| **b == b';').count();
+    let commas = first_line.iter().filter(|b| **b == b',').count();
+    let delimiter = if semicolons > commas { b';' } else { b',' };
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(body);
+
+    rdr.records()
+        .map(|r| {
+            r.map(|rec| rec.iter().map(|f| f.trim().to_string()).collect())
+                .map_err(|e| format!("csv: {e}"))
+        })
+        .collect()
+}
+
+fn read_xlsx(body: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    use calamine::{Reader, Xlsx};
+
+    let mut wb: Xlsx<_> = Xlsx::new(Cursor::new(body)).map_err(|e| format!("xlsx: {e}"))?;
+    let range = wb
+        .worksheet_range_at(0)
+        .ok_or_else(|| "xlsx: workbook has no sheets".to_string())?
+        .map_err(|e| format!("xlsx: {e}"))?;
+
+    Ok(range
+        .rows()
+        .map(|row| row.iter().map(|c| c.to_string().trim().to_string()).collect())
+        .collect())
+}
+
+/// Разбирает таблицу (первая строка — заголовок) в строки импорта без проверок по БД.
+fn parse_rows(table: Vec<Vec<String>>) -> Result<Vec<ImportRow>, String> {
+    let mut it = table.into_iter();
+    let header = it.next().ok_or_else(|| "file is empty".to_string())?;
+    let columns: Vec<Option<Column>> = header.iter().map(|h| column_for(h)).collect();
+
+    for required in [Column::Username, Column::FullName] {
+        if !columns.contains(&Some(required)) {
+            return Err(format!("missing required column {:?}", required));
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (idx, cells) in it.enumerate() {
+        if cells.iter().all(|c| c.is_empty()) {
+            continue;
+        }
+
+        let mut row = ImportRow {
+            line: idx + 2,
+            username: String::new(),
+            full_name: String::new(),
+            email: None,
+            gender: None,
+            roles: Vec::new(),
+            class_name: None,
+            errors: Vec::new(),
+        };
+
+        for (col, value) in columns.iter().zip(cells) {
+            let value = value.trim().to_string();
+            let opt = Some(value.clone()).filter(|v| !v.is_empty());
+            match col {
+                Some(Column::Username) => row.username = value,
+                Some(Column::FullName) => row.full_name = value,
+                Some(Column::Email) => row.email = opt,
+                Some(Column::Gender) => row.gender = opt,
+                Some(Column::Roles) => {
+                    row.roles = value
+                        .split([',', ';', '|'])
+                        .map(|r| r.trim().to_lowercase())
+                        .filter(|r| !r.is_empty())
+                        .collect();
+                }
+                Some(Column::Class) => row.class_name = opt,
+                None => {}
+            }
+        }
+
+        if row.roles.is_empty() {
+            row.roles.push(DEFAULT_ROLE.to_string());
+        }
+        rows.push(row);
+    }
+
+    if rows.len() > MAX_ROWS {
+        return Err(format!("too many rows: {} (max {MAX_ROWS})", rows.len()));
+    }
+
+    Ok(rows)
+}
+
+diesel::define_sql_function!(fn lower(x: Nullable<Text>) -> Nullable<Text>);
+
+fn valid_username(s: &str) -> bool {
+    (3..=64).contains(&s.len())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Проверки строк: формат, дубли внутри файла и с уже существующими пользователями, роли.
+fn validate_rows(conn: &mut PgConnection, rows: &mut [ImportRow]) -> Result<(), diesel::result::Error> {
+    // логины и почта сравниваются без учёта регистра: Ivanov и ivanov — один человек
+    let usernames: Vec<String> = rows.iter().map(|r| r.username.to_lowercase()).collect();
+    let emails: Vec<String> = rows.iter().filter_map(|r| r.email.as_deref()).map(str::to_lowercase).collect();
+
+    let taken_usernames: HashSet<String> = users::table
+        .filter(lower(users::username).eq_any(&usernames))
+        .select(users::username)
+        .load::<Option<String>>(conn)?
+        .into_iter()
+        .flatten()
+        .map(|u| u.to_lowercase())
+        .collect();
+    let taken_emails: HashSet<String> = users::table
+        .filter(lower(users::email).eq_any(&emails))
+        .select(users::email)
+        .load::<Option<String>>(conn)?
+        .into_iter()
+        .flatten()
+        .map(|e| e.to_lowercase())
+        .collect();
+    let known_roles: HashSet<String> = roles::table.select(roles::key).load(conn)?.into_iter().collect();
+
+    let mut seen_usernames = HashSet::new();
+    let mut seen_emails = HashSet::new();
+
+    for row in rows.iter_mut() {
+        if row.username.is_empty() {
+            row.errors.push("username is required".into());
+        } else if !valid_username(&row.username) {
+            row.errors.push("username: 3-64 chars, latin letters, digits, '.', '_' or '-'".into());
+        } else if taken_usernames.contains(&row.username.to_lowercase()) {
+            row.errors.push("username already exists".into());
+        } else if !seen_usernames.insert(row.username.to_lowercase()) {
+            row.errors.push("duplicate username in file".into());
+        }
+
+        if row.full_name.is_empty() {
+            row.errors.push("full_name is required".into());
+        }
+
+        if let Some(email) = &row.email {
+            let key = email.to_lowercase();
+            if !email.contains('@') {
+                row.errors.push("invalid email".into());
+            } else if taken_emails.contains(&key) {
+                row.errors.push("email already exists".into());
+            } else if !seen_emails.insert(key) {
+                row.errors.push("duplicate email in file".into());
+            }
+        }
+
+        for role in &row.roles {
+            if role == "admin" {
+                row.errors.push("role admin cannot be assigned by import".into());
+            } else if !known_roles.contains(role) {
+                row.errors.push(format!("unknown role '{role}'"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Одноразовый пароль: случайность из uuid v4 (как sid), равномерно по алфавиту.
+fn one_time_password() -> String {
+    let n = PASSWORD_ALPHABET.len();
+    // отбрасываем хвост байтов, чтобы не было смещения по модулю
+    let limit = 256 - 256 % n;
+    let mut out = String::with_capacity(PASSWORD_LEN);
+
+    while out.len() < PASSWORD_LEN {
+        let mut h = Sha256::new();
+        h.update(Uuid::new_v4().as_bytes());
+        h.update(Uuid::new_v4().as_bytes());
+        for b in h.finalize() {
+            if (b as usize) < limit && out.len() < PASSWORD_LEN {
+                out.push(PASSWORD_ALPHABET[b as usize % n] as char);
+            }
+        }
+    }
+    out
+}
+
+fn create_imported_users(
+    conn: &mut PgConnection,
+    rows: &[ImportRow],
+    assigned_by: Uuid,
+    audit: &Audit,
+) -> Result<Vec<ImportedCredential>, diesel::result::Error> {
+    let role_ids: HashMap<String, Uuid> = roles::table
+        .select((roles::key, roles::id))
+        .load::<(String, Uuid)>(conn)?
+        .into_iter()
+        .collect();
+    let mut class_ids: HashMap<String, Uuid> = HashMap::new();
+    let mut credentials = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let created = diesel::insert_into(users::table)
+            .values(&NewUserDb {
+                auth0_id: format!("local|{}", row.username),
+                username: Some(row.username.clone()),
+                full_name: Some(row.full_name.clone()),
+                gender: row.gender.clone(),
+                email: row.email.clone(),
+            })
+            .returning(User::as_select())
+            .get_result::<User>(conn)?;
+
+        let password = one_time_password();
+        diesel::insert_into(local_credentials::table)
+            .values(&NewLocalCredentialDb {
+                user_id: created.id,
+                password_hash: hash_password(conn, &password)?,
+                must_change_password: true,
+            })
+            .execute(conn)?;
+
+        for role in &row.roles {
+            diesel::insert_into(user_roles::table)
+                .values((
+                    user_roles::user_id.eq(created.id),
+                    user_roles::role_id.eq(role_ids[role]),
+                    user_roles::assigned_by.eq(Some(assigned_by)),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        if let Some(class_name) = &row.class_name {
+            let class_id = match class_ids.get(class_name) {
+                Some(id) => *id,
+                None => {
+                    let id: Uuid = diesel::insert_into(school_classes::table)
+                        .values(school_classes::name.eq(class_name))
+                        .on_conflict(school_classes::name)
+                        .do_update()
+                        .set(school_classes::name.eq(class_name))
+                        .returning(school_classes::id)
+                        .get_result(conn)?;
+                    class_ids.insert(class_name.clone(), id);
+                    id
+                }
+            };
+
+            diesel::insert_into(class_members::table)
+                .values((
+                    class_members::class_id.eq(class_id),
+                    class_members::user_id.eq(created.id),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        let mut after = user_snapshot(conn, created.id)?;
+        if let Some(v) = after.as_mut() {
+            v["class"] = serde_json::json!(row.class_name);
+        }
+        audit.record(conn, "user.import", "user", created.id, None, after)?;
+
+        credentials.push(ImportedCredential {
+            user_id: created.id,
+            username: row.username.clone(),
+            full_name: row.full_name.clone(),
+            class_name: row.class_name.clone(),
+            password,
+        });
+    }
+
+    Ok(credentials)
+}
+
+// POST /api/admin/users/import?dry_run=true|false&output=json|sheet
+// body: CSV (text/csv) или XLSX; колонки: username, full_name, email, gender, roles, class
+pub async fn import_users(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<ImportQuery>,
+    audit: Audit,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    if !(ctx.has_role("admin") || ctx.has_perm(IMPORT_PERMISSION)) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let table = if is_xlsx(&headers, &body) { read_xlsx(&body) } else { read_csv(&body) };
+    let mut rows = table.and_then(parse_rows).map_err(|e| {
+        eprintln!("users import: {e}");
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    validate_rows(&mut conn, &mut rows).map_err(|e| {
+        eprintln!("users import validation error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut class_names: Vec<String> = rows.iter().filter_map(|r| r.class_name.clone()).collect();
+    class_names.sort();
+    class_names.dedup();
+    let existing_classes: HashSet<String> = school_classes::table
+        .filter(school_classes::name.eq_any(&class_names))
+        .select(school_classes::name)
+        .load::<String>(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .collect();
+
+    let invalid = rows.iter().filter(|r| !r.errors.is_empty()).count();
+    let mut report = ImportReport {
+        dry_run: q.dry_run,
+        total: rows.len(),
+        valid: rows.len() - invalid,
+        invalid,
+        new_classes: class_names.into_iter().filter(|c| !existing_classes.contains(c)).collect(),
+        rows,
+        credentials: Vec::new(),
+    };
+
+    if q.dry_run {
+        return Ok(Json(report).into_response());
+    }
+    // импорт "всё или ничего": с ошибками ничего не создаём
+    if report.invalid > 0 || report.total == 0 {
+        return Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(report)).into_response());
+    }
+
+    report.credentials = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            create_imported_users(conn, &report.rows, ctx.user_id, &audit)
+        })
+        .map_err(|e| {
+            eprintln!("users import failed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if q.output.as_deref() == Some("sheet") {
+        let mut rows = report.credentials;
+        rows.sort_by(|a, b| (&a.class_name, &a.full_name).cmp(&(&b.class_name, &b.full_name)));
+
+        let tpl = CredentialsSheetTemplate {
+            generated_at: chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+            rows,
+        };
+        return Ok(Html(tpl.render().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?).into_response());
+    }
+
+    Ok((StatusCode::CREATED, Json(report)).into_response())
+}
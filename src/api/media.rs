@@ -0,0 +1,47 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::social::media::{local_image_path, mime_for, verify_signed_path};
+
+#[derive(Debug, Deserialize)]
+pub struct SignedMediaQuery {
+    pub expires: i64,
+    pub sig: String,
+}
+
+/// GET /media/{*path}: файл из `images/` по подписанной ссылке.
+/// Так соцсети скачивают картинки, которые сервер не раздаёт наружу напрямую.
+pub async fn signed_media(
+    Path(path): Path<String>,
+    Query(q): Query<SignedMediaQuery>,
+) -> Result<Response, StatusCode> {
+    if !verify_signed_path(&path, q.expires, &q.sig, Utc::now()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let file = local_image_path(&format!("images/{path}")).ok_or(StatusCode::NOT_FOUND)?;
+    let bytes = tokio::fs::read(&file).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            StatusCode::NOT_FOUND
+        } else {
+            eprintln!("signed_media read {}: {e}", file.display());
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, mime_for(&path)),
+            // не дольше, чем живёт подпись
+            (header::CACHE_CONTROL, "private, max-age=3600"),
+        ],
+        Body::from(bytes),
+    )
+        .into_response())
+}
@@ -3,3 +3,5 @@ pub mod subjects;
 pub mod library;
 pub mod quizlet;
 pub mod news; // <- добавляем модуль новостей
+pub mod media;
+pub mod telegram;
@@ -13,6 +13,7 @@ use base64::Engine;
 
 
 use crate::AppState;
+use crate::auth::audit::{snapshot, Audit};
 
 use crate::schema::quizlet_cards::dsl as qc;
 
@@ -89,6 +90,32 @@ fn decode_base64_opt(v: &Option<String>) -> Result<Option<Vec<u8>>, StatusCode>
     }
 }
 
+/// Снимок карточки для аудита (без image_data, чтобы не раздувать audit_log).
+fn card_snapshot(
+    conn: &mut PgConnection,
+    set_id: Uuid,
+    card_id: Uuid,
+) -> Result<Option<serde_json::Value>, diesel::result::Error> {
+    let card = qc::quizlet_cards
+        .filter(qc::set_id.eq(set_id))
+        .filter(qc::id.eq(card_id))
+        .select((
+            qc::id,
+            qc::set_id,
+            qc::position,
+            qc::term,
+            qc::explanation,
+            qc::image_url,
+            qc::image_alt,
+            qc::image_mime,
+            qc::created_at,
+        ))
+        .first::<QuizletCardListItem>(conn)
+        .optional()?;
+
+    Ok(card.as_ref().and_then(snapshot))
+}
+
 pub async fn list_cards(
     State(state): State<AppState>,
     Path(set_id): Path<Uuid>,
@@ -166,6 +193,7 @@ pub async fn get_card(
 pub async fn create_card(
     State(state): State<AppState>,
     Path(set_id): Path<Uuid>,
+    audit: Audit,
     Json(body): Json<CreateCardBody>,
 ) -> Result<(StatusCode, Json<QuizletCard>), StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -193,23 +221,33 @@ pub async fn create_card(
         max_pos.unwrap_or(0) + 1
     };
 
-    // Вставка и возврат строки
-    let inserted: QuizletCard = diesel::insert_into(qc::quizlet_cards)
-        .values((
-            qc::set_id.eq(set_id),
-            qc::position.eq(pos),
-            qc::term.eq(body.term),
-            qc::explanation.eq(body.explanation),
-            qc::image_url.eq(body.image_url),
-            qc::image_alt.eq(body.image_alt),
-            qc::image_data.eq(image_data),
-            qc::image_mime.eq(image_mime),
-        ))
-        .get_result(&mut conn)
+    // Вставка и возврат строки вместе с записью аудита
+    let inserted: QuizletCard = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let inserted: QuizletCard = diesel::insert_into(qc::quizlet_cards)
+                .values((
+                    qc::set_id.eq(set_id),
+                    qc::position.eq(pos),
+                    qc::term.eq(body.term),
+                    qc::explanation.eq(body.explanation),
+                    qc::image_url.eq(body.image_url),
+                    qc::image_alt.eq(body.image_alt),
+                    qc::image_data.eq(image_data),
+                    qc::image_mime.eq(image_mime),
+                ))
+                .get_result(conn)?;
+
+            let after = card_snapshot(conn, set_id, inserted.id)?;
+            audit.record(conn, "quizlet.card.create", "quizlet_card", inserted.id, None, after)?;
+            Ok(inserted)
+        })
         .map_err(|e| {
-            // уникальный (set_id, position) может упасть
             eprintln!("create_card error: {e}");
-            StatusCode::CONFLICT
+            match e {
+                // уникальный (set_id, position) может упасть
+                diesel::result::Error::DatabaseError(..) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            }
         })?;
 
     Ok((StatusCode::CREATED, Json(inserted)))
@@ -218,6 +256,7 @@ pub async fn create_card(
 pub async fn update_card(
     State(state): State<AppState>,
     Path((set_id, card_id)): Path<(Uuid, Uuid)>,
+    audit: Audit,
     Json(body): Json<UpdateCardBody>,
 ) -> Result<Json<QuizletCard>, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -233,16 +272,13 @@ pub async fn update_card(
         }
     }
 
-    // проверка существования
-    qc::quizlet_cards
-        .filter(qc::set_id.eq(set_id))
-        .filter(qc::id.eq(card_id))
-        .select(qc::id)
-        .first::<Uuid>(&mut conn)
-        .map_err(|_| StatusCode::NOT_FOUND)?;
+    // проверка существования (заодно снимок для аудита)
+    let before = card_snapshot(&mut conn, set_id, card_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    // транзакция
-    conn.transaction(|conn| {
+    // транзакция: изменения и запись аудита
+    let updated = conn.transaction(|conn| {
         // 1) простые поля (position/term/explanation)
         diesel::sql_query(
             r#"
@@ -350,40 +386,57 @@ pub async fn update_card(
             }
         }
 
-        Ok::<_, diesel::result::Error>(())
+        // вернуть обновлённую карточку
+        let updated = qc::quizlet_cards
+            .filter(qc::set_id.eq(set_id))
+            .filter(qc::id.eq(card_id))
+            .first::<QuizletCard>(conn)?;
+
+        let after = card_snapshot(conn, set_id, card_id)?;
+        audit.record(conn, "quizlet.card.update", "quizlet_card", card_id, Some(before), after)?;
+
+        Ok::<_, diesel::result::Error>(updated)
     })
     .map_err(|e| {
         eprintln!("update_card tx error: {e}");
-        StatusCode::CONFLICT
+        match e {
+            diesel::result::Error::DatabaseError(..) | diesel::result::Error::RollbackTransaction => {
+                StatusCode::CONFLICT
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
     })?;
 
-    // вернуть обновлённую карточку
-    let updated = qc::quizlet_cards
-        .filter(qc::set_id.eq(set_id))
-        .filter(qc::id.eq(card_id))
-        .first::<QuizletCard>(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
     Ok(Json(updated))
 }
 
 pub async fn delete_card(
     State(state): State<AppState>,
     Path((set_id, card_id)): Path<(Uuid, Uuid)>,
+    audit: Audit,
 ) -> Result<StatusCode, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let deleted = diesel::delete(
-        qc::quizlet_cards
-            .filter(qc::set_id.eq(set_id))
-            .filter(qc::id.eq(card_id)),
-    )
-    .execute(&mut conn)
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let before = card_snapshot(&mut conn, set_id, card_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    if deleted == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deleted = diesel::delete(
+            qc::quizlet_cards
+                .filter(qc::set_id.eq(set_id))
+                .filter(qc::id.eq(card_id)),
+        )
+        .execute(conn)?;
+
+        if deleted == 0 {
+            return Err(diesel::result::Error::NotFound);
+        }
+
+        audit.record(conn, "quizlet.card.delete", "quizlet_card", card_id, before, None)
+    })
+    .map_err(|e| match e {
+        diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
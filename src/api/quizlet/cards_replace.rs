@@ -4,6 +4,7 @@ use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::AppState;
+use crate::auth::audit::Audit;
 
 #[derive(Debug, Deserialize)]
 pub struct ReplaceCardsBody {
@@ -18,13 +19,37 @@ pub struct ReplaceCardItem {
     pub image_mime: Option<String>,
 }
 
+/// Снимок содержимого сета для аудита: только term/explanation по порядку.
+fn set_cards_snapshot(
+    conn: &mut PgConnection,
+    set_id: Uuid,
+) -> Result<serde_json::Value, diesel::result::Error> {
+    use crate::schema::quizlet_cards::dsl as qc;
+
+    let cards: Vec<(String, String)> = qc::quizlet_cards
+        .filter(qc::set_id.eq(set_id))
+        .order(qc::position.asc())
+        .select((qc::term, qc::explanation))
+        .load(conn)?;
+
+    Ok(serde_json::json!({
+        "cards": cards
+            .into_iter()
+            .map(|(term, explanation)| serde_json::json!({ "term": term, "explanation": explanation }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
 pub async fn replace_cards(
     State(state): State<AppState>,
     Path(set_id): Path<Uuid>,
+    audit: Audit,
     Json(body): Json<ReplaceCardsBody>,
 ) -> Result<StatusCode, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let before = set_cards_snapshot(&mut conn, set_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     conn.transaction(|conn| {
         diesel::sql_query("DELETE FROM quizlet_cards WHERE set_id = $1")
             .bind::<diesel::sql_types::Uuid, _>(set_id)
@@ -47,9 +72,13 @@ pub async fn replace_cards(
             .execute(conn)?;
         }
 
-        Ok::<_, diesel::result::Error>(())
+        let after = set_cards_snapshot(conn, set_id)?;
+        audit.record(conn, "quizlet.set.cards_replace", "quizlet_set", set_id, Some(before), Some(after))
     })
-    .map_err(|_| StatusCode::CONFLICT)?;
+    .map_err(|e| match e {
+        diesel::result::Error::DatabaseError(..) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
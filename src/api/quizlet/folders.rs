@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::AppState;
+use crate::auth::audit::{snapshot, Audit};
 use crate::schema::{quizlet_folder_sets, quizlet_folders, quizlet_sets};
 
 #[derive(Debug, Serialize, Queryable)]
@@ -65,6 +66,20 @@ struct NewFolderSet {
     position: i32,
 }
 
+/// Снимок состава папки для аудита: set_id по порядку.
+fn folder_sets_snapshot(
+    conn: &mut PgConnection,
+    folder_id: Uuid,
+) -> Result<serde_json::Value, diesel::result::Error> {
+    let set_ids: Vec<Uuid> = quizlet_folder_sets::table
+        .filter(quizlet_folder_sets::folder_id.eq(folder_id))
+        .order(quizlet_folder_sets::position.asc())
+        .select(quizlet_folder_sets::set_id)
+        .load(conn)?;
+
+    Ok(serde_json::json!({ "set_ids": set_ids }))
+}
+
 pub async fn list_folders(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<FolderListItem>>, StatusCode> {
@@ -99,6 +114,7 @@ pub async fn list_folders(
 
 pub async fn create_folder(
     State(state): State<AppState>,
+    audit: Audit,
     Json(body): Json<CreateFolderBody>,
 ) -> Result<(StatusCode, Json<FolderRow>), StatusCode> {
     if body.title.trim().is_empty() {
@@ -114,13 +130,22 @@ pub async fn create_folder(
         owner_id: Option<Uuid>,
     }
 
-    let inserted: FolderRow = diesel::insert_into(quizlet_folders::table)
-        .values(NewFolder {
-            title: body.title.trim().to_string(),
-            owner_id: body.owner_id,
+    let inserted: FolderRow = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let inserted: FolderRow = diesel::insert_into(quizlet_folders::table)
+                .values(NewFolder {
+                    title: body.title.trim().to_string(),
+                    owner_id: body.owner_id,
+                })
+                .get_result(conn)?;
+
+            audit.record(conn, "quizlet.folder.create", "quizlet_folder", inserted.id, None, snapshot(&inserted))?;
+            Ok(inserted)
         })
-        .get_result(&mut conn)
-        .map_err(|_| StatusCode::CONFLICT)?;
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(..) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
 
     Ok((StatusCode::CREATED, Json(inserted)))
 }
@@ -142,6 +167,7 @@ pub async fn get_folder(
 pub async fn rename_folder(
     State(state): State<AppState>,
     Path(folder_id): Path<Uuid>,
+    audit: Audit,
     Json(body): Json<UpdateFolderBody>,
 ) -> Result<Json<FolderRow>, StatusCode> {
     if body.title.trim().is_empty() {
@@ -150,30 +176,62 @@ pub async fn rename_folder(
 
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let updated: FolderRow = diesel::update(quizlet_folders::table.filter(quizlet_folders::id.eq(folder_id)))
-        .set((
-            quizlet_folders::title.eq(body.title.trim()),
-            quizlet_folders::updated_at.eq(diesel::dsl::now),
-        ))
-        .get_result(&mut conn)
+    let before = quizlet_folders::table
+        .filter(quizlet_folders::id.eq(folder_id))
+        .first::<FolderRow>(&mut conn)
         .map_err(|_| StatusCode::NOT_FOUND)?;
 
+    let updated: FolderRow = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated: FolderRow = diesel::update(quizlet_folders::table.filter(quizlet_folders::id.eq(folder_id)))
+                .set((
+                    quizlet_folders::title.eq(body.title.trim()),
+                    quizlet_folders::updated_at.eq(diesel::dsl::now),
+                ))
+                .get_result(conn)?;
+
+            audit.record(conn, "quizlet.folder.rename", "quizlet_folder", folder_id, snapshot(&before), snapshot(&updated))?;
+            Ok(updated)
+        })
+        .map_err(|e| match e {
+            diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
     Ok(Json(updated))
 }
 
 pub async fn delete_folder(
     State(state): State<AppState>,
     Path(folder_id): Path<Uuid>,
+    audit: Audit,
 ) -> Result<StatusCode, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let deleted = diesel::delete(quizlet_folders::table.filter(quizlet_folders::id.eq(folder_id)))
-        .execute(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let before = quizlet_folders::table
+        .filter(quizlet_folders::id.eq(folder_id))
+        .first::<FolderRow>(&mut conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut before_json = snapshot(&before).unwrap_or_default();
+    before_json["sets"] = folder_sets_snapshot(&mut conn, folder_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?["set_ids"]
+        .take();
 
-    if deleted == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deleted = diesel::delete(quizlet_folders::table.filter(quizlet_folders::id.eq(folder_id))).execute(conn)?;
+
+        if deleted == 0 {
+            return Err(diesel::result::Error::NotFound);
+        }
+
+        audit.record(conn, "quizlet.folder.delete", "quizlet_folder", folder_id, Some(before_json), None)
+    })
+    .map_err(|e| match e {
+        diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -205,6 +263,7 @@ pub async fn list_folder_sets(
 pub async fn add_sets_to_folder(
     State(state): State<AppState>,
     Path(folder_id): Path<Uuid>,
+    audit: Audit,
     Json(body): Json<AddSetsToFolderBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     if body.set_ids.is_empty() {
@@ -220,6 +279,8 @@ pub async fn add_sets_to_folder(
             .select(quizlet_folders::id)
             .first::<Uuid>(conn)?;
 
+        let before = folder_sets_snapshot(conn, folder_id)?;
+
         // append to end
         let max_pos: Option<i32> = quizlet_folder_sets::table
             .filter(quizlet_folder_sets::folder_id.eq(folder_id))
@@ -254,6 +315,9 @@ pub async fn add_sets_to_folder(
             .set(quizlet_folders::updated_at.eq(Utc::now()))
             .execute(conn)?;
 
+        let after = folder_sets_snapshot(conn, folder_id)?;
+        audit.record(conn, "quizlet.folder.sets_add", "quizlet_folder", folder_id, Some(before), Some(after))?;
+
         Ok(())
     })
     .map_err(|e| {
@@ -271,6 +335,7 @@ pub async fn add_sets_to_folder(
 pub async fn replace_folder_sets(
     State(state): State<AppState>,
     Path(folder_id): Path<Uuid>,
+    audit: Audit,
     Json(body): Json<ReplaceFolderSetsBody>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -281,6 +346,8 @@ pub async fn replace_folder_sets(
             .select(quizlet_folders::id)
             .first::<Uuid>(conn)?;
 
+        let before = folder_sets_snapshot(conn, folder_id)?;
+
         // wipe links
         diesel::delete(quizlet_folder_sets::table.filter(quizlet_folder_sets::folder_id.eq(folder_id)))
             .execute(conn)?;
@@ -307,6 +374,9 @@ pub async fn replace_folder_sets(
             .set(quizlet_folders::updated_at.eq(Utc::now()))
             .execute(conn)?;
 
+        let after = folder_sets_snapshot(conn, folder_id)?;
+        audit.record(conn, "quizlet.folder.sets_replace", "quizlet_folder", folder_id, Some(before), Some(after))?;
+
         Ok(())
     })
     .map_err(|e| {
@@ -324,25 +394,40 @@ pub async fn replace_folder_sets(
 pub async fn remove_set_from_folder(
     State(state): State<AppState>,
     Path((folder_id, set_id)): Path<(Uuid, Uuid)>,
+    audit: Audit,
 ) -> Result<StatusCode, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let deleted = diesel::delete(
-        quizlet_folder_sets::table
-            .filter(quizlet_folder_sets::folder_id.eq(folder_id))
-            .filter(quizlet_folder_sets::set_id.eq(set_id)),
-    )
-    .execute(&mut conn)
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deleted = diesel::delete(
+            quizlet_folder_sets::table
+                .filter(quizlet_folder_sets::folder_id.eq(folder_id))
+                .filter(quizlet_folder_sets::set_id.eq(set_id)),
+        )
+        .execute(conn)?;
 
-    if deleted == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+        if deleted == 0 {
+            return Err(diesel::result::Error::NotFound);
+        }
 
-    // bump folder updated_at (не критично, но полезно)
-    let _ = diesel::update(quizlet_folders::table.filter(quizlet_folders::id.eq(folder_id)))
-        .set(quizlet_folders::updated_at.eq(Utc::now()))
-        .execute(&mut conn);
+        // bump folder updated_at (не критично, но полезно)
+        diesel::update(quizlet_folders::table.filter(quizlet_folders::id.eq(folder_id)))
+            .set(quizlet_folders::updated_at.eq(Utc::now()))
+            .execute(conn)?;
+
+        audit.record(
+            conn,
+            "quizlet.folder.set_remove",
+            "quizlet_folder",
+            folder_id,
+            Some(serde_json::json!({ "set_id": set_id })),
+            None,
+        )
+    })
+    .map_err(|e| match e {
+        diesel::result::Error::NotFound => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
\ No newline at end of file
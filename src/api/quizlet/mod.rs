@@ -2,4 +2,5 @@ pub mod cards;
 pub mod cards_replace;
 pub mod sets;
 pub mod full;
-pub mod folders;
\ No newline at end of file
+pub mod folders;
+pub mod review;
@@ -0,0 +1,178 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::auth::context::AuthContext;
+use crate::schema::{quizlet_card_progress, quizlet_cards, quizlet_sets};
+
+/// Через сколько дней показывать карточку из коробки N (индекс — номер коробки).
+const REVIEW_INTERVALS_DAYS: [i64; 6] = [1, 2, 4, 7, 15, 30];
+/// Ошибку повторяем в ту же сессию.
+const RELEARN_MINUTES: i64 = 10;
+
+const DEFAULT_DUE_LIMIT: i64 = 20;
+
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = quizlet_card_progress)]
+pub struct CardProgress {
+    pub user_id: Uuid,
+    pub card_id: Uuid,
+    pub box_no: i32,
+    pub correct_count: i32,
+    pub wrong_count: i32,
+    pub due_at: DateTime<Utc>,
+    pub last_reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// Карточка, которую пора повторить.
+#[derive(Debug, Serialize, Queryable)]
+pub struct DueCard {
+    pub card_id: Uuid,
+    pub set_id: Uuid,
+    pub set_title: String,
+    pub term: String,
+    pub explanation: String,
+    pub image_url: Option<String>,
+    pub box_no: i32,
+    pub due_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DueQuery {
+    pub set_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewBody {
+    pub correct: bool,
+}
+
+/// Следующая коробка и срок: верно — коробкой выше, ошибка — в начало.
+fn schedule(box_no: i32, correct: bool, now: DateTime<Utc>) -> (i32, DateTime<Utc>) {
+    if correct {
+        let next = (box_no + 1).min(REVIEW_INTERVALS_DAYS.len() as i32 - 1);
+        (next, now + Duration::days(REVIEW_INTERVALS_DAYS[next as usize]))
+    } else {
+        (0, now + Duration::minutes(RELEARN_MINUTES))
+    }
+}
+
+/// Карточки пользователя со сроком повторения не позже сейчас, самые просроченные первыми.
+pub fn due_cards(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    set_id: Option<Uuid>,
+    limit: i64,
+) -> QueryResult<Vec<DueCard>> {
+    let mut query = quizlet_card_progress::table
+        .inner_join(quizlet_cards::table.inner_join(quizlet_sets::table))
+        .filter(quizlet_card_progress::user_id.eq(user_id))
+        .filter(quizlet_card_progress::due_at.le(Utc::now()))
+        .into_boxed();
+    if let Some(set_id) = set_id {
+        query = query.filter(quizlet_cards::set_id.eq(set_id));
+    }
+
+    query
+        .order((quizlet_card_progress::due_at.asc(), quizlet_cards::position.asc()))
+        .limit(limit)
+        .select((
+            quizlet_cards::id,
+            quizlet_cards::set_id,
+            quizlet_sets::title,
+            quizlet_cards::term,
+            quizlet_cards::explanation,
+            quizlet_cards::image_url,
+            quizlet_card_progress::box_no,
+            quizlet_card_progress::due_at,
+        ))
+        .load(conn)
+}
+
+/// Ответ на карточку — из веба, `/review` или `/quiz` в Telegram.
+/// Первый ответ заводит прогресс по карточке.
+pub fn record_answer(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    card_id: Uuid,
+    correct: bool,
+) -> QueryResult<CardProgress> {
+    conn.transaction(|conn| {
+        let current = quizlet_card_progress::table
+            .find((user_id, card_id))
+            .select(CardProgress::as_select())
+            .for_update()
+            .first::<CardProgress>(conn)
+            .optional()?;
+
+        let now = Utc::now();
+        let (box_no, due_at) = schedule(current.as_ref().map_or(0, |p| p.box_no), correct, now);
+        let (correct_count, wrong_count) = current.map_or((0, 0), |p| (p.correct_count, p.wrong_count));
+        let row = CardProgress {
+            user_id,
+            card_id,
+            box_no,
+            correct_count: correct_count + i32::from(correct),
+            wrong_count: wrong_count + i32::from(!correct),
+            due_at,
+            last_reviewed_at: Some(now),
+        };
+
+        diesel::insert_into(quizlet_card_progress::table)
+            .values(&row)
+            .on_conflict((quizlet_card_progress::user_id, quizlet_card_progress::card_id))
+            .do_update()
+            .set(&row)
+            .returning(CardProgress::as_returning())
+            .get_result(conn)
+    })
+}
+
+// GET /api/quizlet/review/due?set_id=&limit=
+pub async fn list_due_cards(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(q): Query<DueQuery>,
+) -> Result<Json<Vec<DueCard>>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    let limit = q.limit.unwrap_or(DEFAULT_DUE_LIMIT).clamp(1, 100);
+
+    due_cards(&mut conn, ctx.user_id, q.set_id, limit)
+        .map(Json)
+        .map_err(|e| {
+            eprintln!("list_due_cards error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// POST /api/quizlet/review/{card_id} { "correct": true }
+pub async fn review_card(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Path(card_id): Path<Uuid>,
+    Json(body): Json<ReviewBody>,
+) -> Result<Json<CardProgress>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let exists = diesel::select(diesel::dsl::exists(quizlet_cards::table.find(card_id)))
+        .get_result::<bool>(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !exists {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    record_answer(&mut conn, ctx.user_id, card_id, body.correct)
+        .map(Json)
+        .map_err(|e| {
+            eprintln!("review_card error: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
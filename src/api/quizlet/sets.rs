@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{AppState};
+use crate::auth::audit::{snapshot, Audit};
 use crate::schema::quizlet_sets::dsl as qs;
 use crate::schema::quizlet_sets;
 
@@ -37,48 +38,74 @@ pub struct UpsertSetBody {
 pub async fn upsert_set(
     State(state): State<AppState>,
     Path(set_id): Path<Uuid>,
+    audit: Audit,
     Json(body): Json<UpsertSetBody>,
 ) -> Result<Json<QuizletSet>, StatusCode> {
     let mut conn = state.pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // diesel upsert через sql_query (быстрее и проще)
-    diesel::sql_query(
-        r#"
-        INSERT INTO quizlet_sets (id, slug, title, description, language_level, textbook_id, source_url, created_at, updated_at)
-        VALUES ($1,$2,$3,$4,$5,$6,$7, now(), now())
-        ON CONFLICT (slug) DO UPDATE SET
-        id = EXCLUDED.id,
-        title = EXCLUDED.title,
-        description = EXCLUDED.description,
-        language_level = EXCLUDED.language_level,
-        textbook_id = EXCLUDED.textbook_id,
-        source_url = EXCLUDED.source_url,
-        updated_at = now()
-        RETURNING *
-        "#
-    )
-    .bind::<diesel::sql_types::Uuid, _>(set_id)
-    .bind::<diesel::sql_types::Text, _>(body.slug)
-    .bind::<diesel::sql_types::Text, _>(body.title)
-    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(body.description)
-    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(body.language_level)
-    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Uuid>, _>(body.textbook_id)
-    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(body.source_url)
-    .execute(&mut conn)
-    .map_err(|_| StatusCode::CONFLICT)?;
-
-    let set = qs::quizlet_sets
-        .filter(qs::id.eq(set_id))
+    // upsert идёт по slug, поэтому "до" ищем по нему же
+    let before = qs::quizlet_sets
+        .filter(qs::slug.eq(&body.slug))
         .select(QuizletSet::as_select())
         .first::<QuizletSet>(&mut conn)
+        .optional()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let action = if before.is_some() { "quizlet.set.update" } else { "quizlet.set.create" };
+    let set = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            // diesel upsert через sql_query (быстрее и проще)
+            diesel::sql_query(
+                r#"
+                INSERT INTO quizlet_sets (id, slug, title, description, language_level, textbook_id, source_url, created_at, updated_at)
+                VALUES ($1,$2,$3,$4,$5,$6,$7, now(), now())
+                ON CONFLICT (slug) DO UPDATE SET
+                id = EXCLUDED.id,
+                title = EXCLUDED.title,
+                description = EXCLUDED.description,
+                language_level = EXCLUDED.language_level,
+                textbook_id = EXCLUDED.textbook_id,
+                source_url = EXCLUDED.source_url,
+                updated_at = now()
+                RETURNING *
+                "#
+            )
+            .bind::<diesel::sql_types::Uuid, _>(set_id)
+            .bind::<diesel::sql_types::Text, _>(body.slug)
+            .bind::<diesel::sql_types::Text, _>(body.title)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(body.description)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(body.language_level)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Uuid>, _>(body.textbook_id)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(body.source_url)
+            .execute(conn)?;
+
+            let set = qs::quizlet_sets
+                .filter(qs::id.eq(set_id))
+                .select(QuizletSet::as_select())
+                .first::<QuizletSet>(conn)?;
+
+            audit.record(
+                conn,
+                action,
+                "quizlet_set",
+                set.id,
+                before.as_ref().and_then(snapshot),
+                snapshot(&set),
+            )?;
+            Ok(set)
+        })
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(..) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+
     Ok(Json(set))
 }
 
 pub async fn flip_set_cards(
     State(state): State<AppState>,
     Path(set_id): Path<Uuid>,
+    audit: Audit,
 ) -> Result<StatusCode, (StatusCode, String)> {
     let mut conn = state
         .pool
@@ -86,7 +113,7 @@ pub async fn flip_set_cards(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     conn.transaction::<_, diesel::result::Error, _>(|conn| {
-        diesel::sql_query(
+        let flipped = diesel::sql_query(
             r#"
             UPDATE quizlet_cards
             SET
@@ -98,6 +125,15 @@ pub async fn flip_set_cards(
         .bind::<diesel::sql_types::Uuid, _>(set_id)
         .execute(conn)?;
 
+        audit.record(
+            conn,
+            "quizlet.set.flip",
+            "quizlet_set",
+            set_id,
+            None,
+            Some(serde_json::json!({ "cards_flipped": flipped })),
+        )?;
+
         Ok(())
     })
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
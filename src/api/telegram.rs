@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Extension, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::AppState;
+use crate::auth::context::AuthContext;
+use crate::bot::api::{BotApi, Update};
+use crate::bot::commands::issue_link_code;
+use crate::bot::{handle_update, webhook_secret};
+use crate::schema::telegram_links;
+
+#[derive(Debug, Serialize)]
+pub struct LinkCodeResponse {
+    pub code: String,
+    pub expires_at: DateTime<Utc>,
+    /// Что отправить боту
+    pub command: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TelegramLinkStatus {
+    pub linked: bool,
+    pub telegram_username: Option<String>,
+    pub linked_at: Option<DateTime<Utc>>,
+}
+
+// GET /api/me/telegram
+pub async fn get_telegram_link(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<TelegramLinkStatus>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let link = telegram_links::table
+        .find(ctx.user_id)
+        .select((telegram_links::telegram_username, telegram_links::linked_at))
+        .first::<(Option<String>, DateTime<Utc>)>(&mut conn)
+        .optional()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(match link {
+        Some((telegram_username, linked_at)) => TelegramLinkStatus {
+            linked: true,
+            telegram_username,
+            linked_at: Some(linked_at),
+        },
+        None => TelegramLinkStatus {
+            linked: false,
+            telegram_username: None,
+            linked_at: None,
+        },
+    }))
+}
+
+// POST /api/me/telegram/link-code — одноразовый код для `/link` в боте
+pub async fn create_link_code(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<LinkCodeResponse>, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let (code, expires_at) = issue_link_code(&mut conn, ctx.user_id).map_err(|e| {
+        eprintln!("create_link_code error: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(LinkCodeResponse {
+        command: format!("/link {code}"),
+        code,
+        expires_at,
+    }))
+}
+
+// DELETE /api/me/telegram
+pub async fn unlink_telegram(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, StatusCode> {
+    let mut conn = state.pool.get().map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+
+    diesel::delete(telegram_links::table.find(ctx.user_id))
+        .execute(&mut conn)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/telegram/webhook — обновления учебного бота, если он работает через вебхук
+pub async fn telegram_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(update): Json<Update>,
+) -> StatusCode {
+    let (Some(secret), Some(bot)) = (webhook_secret(), BotApi::from_env()) else {
+        return StatusCode::NOT_FOUND;
+    };
+    let given = headers
+        .get("x-telegram-bot-api-secret-token")
+        .and_then(|v| v.to_str().ok());
+    if given != Some(secret.as_str()) {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    // Telegram ждёт быстрый 200, иначе повторяет обновление
+    tokio::spawn(async move {
+        if let Err(err) = handle_update(&state.pool, &bot, update).await {
+            eprintln!("[bot] {err:#}");
+        }
+    });
+    StatusCode::OK
+}
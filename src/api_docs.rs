@@ -26,7 +26,8 @@ impl Modify for SecurityAddon {
         crate::auth::routes::login,
         crate::auth::routes::logout,
         crate::auth::routes::session_me,
-        crate::auth::routes::me_handler
+        crate::auth::routes::me_handler,
+        crate::auth::routes::change_password
     ),
     components(
         schemas(
@@ -35,6 +36,7 @@ impl Modify for SecurityAddon {
             crate::auth::routes::LogoutResp,
             crate::auth::routes::SessionMeResponse,
             crate::auth::routes::MeResponse,
+            crate::auth::routes::ChangePasswordReq,
             crate::auth::routes::ApiError
         )
     ),
@@ -0,0 +1,87 @@
+use std::convert::Infallible;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use diesel::PgConnection;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::auth::context::AuthContext;
+use crate::models::audit::write_audit;
+
+/// Единый аудит для мутирующих handler'ов.
+///
+/// Extractor берёт actor из `AuthContext` (кладёт session_middleware) и метод/путь запроса.
+/// Handler сам передаёт снимки сущности до/после изменения, а `record` пишет в audit_log:
+/// `{ before, after, changes, request }`, где `changes` — поля, которые реально поменялись.
+#[derive(Clone, Debug)]
+pub struct Audit {
+    pub actor_id: Option<Uuid>,
+    method: String,
+    path: String,
+}
+
+impl<S> FromRequestParts<S> for Audit
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            actor_id: parts.extensions.get::<AuthContext>().map(|c| c.user_id),
+            method: parts.method.to_string(),
+            path: parts.uri.path().to_string(),
+        })
+    }
+}
+
+/// Снимок сущности для `Audit::record`.
+pub fn snapshot<T: Serialize>(v: &T) -> Option<Value> {
+    serde_json::to_value(v).ok()
+}
+
+/// Поля верхнего уровня, значения которых отличаются: `{ field: { from, to } }`.
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let b = before.and_then(Value::as_object).unwrap_or(&empty);
+    let a = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for key in b.keys().chain(a.keys().filter(|k| !b.contains_key(*k))) {
+        let from = b.get(key).unwrap_or(&Value::Null);
+        let to = a.get(key).unwrap_or(&Value::Null);
+        if from != to {
+            changes.insert(key.clone(), json!({ "from": from, "to": to }));
+        }
+    }
+    Value::Object(changes)
+}
+
+impl Audit {
+    pub fn record(
+        &self,
+        conn: &mut PgConnection,
+        action: &str,
+        entity_type: &str,
+        entity_id: impl ToString,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Result<(), diesel::result::Error> {
+        let changes = diff(before.as_ref(), after.as_ref());
+
+        write_audit(
+            conn,
+            self.actor_id,
+            action,
+            entity_type,
+            entity_id,
+            Some(json!({
+                "before": before,
+                "after": after,
+                "changes": changes,
+                "request": { "method": self.method, "path": self.path },
+            })),
+        )
+    }
+}
@@ -53,6 +53,17 @@ pub async fn session_middleware(
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .ok_or(StatusCode::UNAUTHORIZED)?;
 
+    // 3.1) одноразовый пароль ещё не сменён: пускаем только на смену пароля и /auth/me
+    let path = req.uri().path();
+    if !(path.ends_with("/auth/password") || path.ends_with("/auth/me"))
+        && crate::auth::routes::must_change_password(&mut conn, user_id).map_err(|e| {
+            eprintln!("must_change_password error: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     // 4) грузим auth context (roles/permissions)
     let ctx = load_auth_context(&mut conn, user_id)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -67,6 +78,11 @@ pub async fn session_middleware(
         .first::<User>(&mut conn)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // деактивированный/удалённый аккаунт: старые сессии больше не действуют
+    if !u.is_active || u.deleted_at.is_some() {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     req.extensions_mut().insert(AuthenticatedUser {
         id: u.id,
         auth0_id: u.auth0_id,
@@ -1,3 +1,4 @@
+pub mod audit;
 pub mod context;
 pub mod middleware;
 pub mod routes;
@@ -36,6 +36,12 @@ pub struct LoginResp {
     pub reason: String,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangePasswordReq {
+    pub current_password: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct LogoutResp {
     pub ok: bool,
@@ -73,6 +79,7 @@ pub struct ApiError {
     responses(
         (status = 200, description = "Login successful", body = LoginResp),
         (status = 401, description = "Invalid credentials", body = LoginResp),
+        (status = 403, description = "Account deactivated or deleted", body = LoginResp),
         (status = 503, description = "Database unavailable", body = LoginResp),
         (status = 500, description = "Internal server error", body = LoginResp)
     )
@@ -98,12 +105,12 @@ pub async fn login(
 
     let uname = req.username.trim().to_string();
 
-    let user_id: Uuid = match u::users
+    let (user_id, is_active, deleted_at) = match u::users
         .filter(u::username.eq(Some(uname)))
-        .select(u::id)
-        .first::<Uuid>(&mut conn)
+        .select((u::id, u::is_active, u::deleted_at))
+        .first::<(Uuid, bool, Option<chrono::NaiveDateTime>)>(&mut conn)
     {
-        Ok(uid) => uid,
+        Ok(row) => row,
 
         Err(diesel::result::Error::NotFound) => {
             return (
@@ -152,6 +159,18 @@ pub async fn login(
         );
     }
 
+    // причину блокировки сообщаем только после верного пароля
+    if !is_active || deleted_at.is_some() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(LoginResp {
+                ok: false,
+                roles: vec![],
+                reason: "account_disabled".into(),
+            }),
+        );
+    }
+
     let sid = match crate::auth::session::create_session(
         &mut conn,
         &state.session_secret,
@@ -190,16 +209,93 @@ pub async fn login(
         Err(_) => vec![],
     };
 
+    // одноразовый пароль (массовый импорт): сессия есть, но до смены пароля доступны только
+    // /api/auth/me и /api/auth/password (см. session_middleware)
+    // ошибка БД — считаем, что смена нужна: middleware всё равно не пустит дальше
+    let reason = if must_change_password(&mut conn, user_id).unwrap_or(true) {
+        "password_change_required"
+    } else {
+        "ok"
+    };
+
     (
         StatusCode::OK,
         Json(LoginResp {
             ok: true,
             roles,
-            reason: "ok".into(),
+            reason: reason.into(),
         }),
     )
 }
 
+pub fn must_change_password(conn: &mut PgConnection, user_id: Uuid) -> QueryResult<bool> {
+    use crate::schema::local_credentials::dsl as lc;
+
+    // нет локального пароля (вход через OAuth) — менять нечего
+    Ok(lc::local_credentials
+        .find(user_id)
+        .select(lc::must_change_password)
+        .first::<bool>(conn)
+        .optional()?
+        .unwrap_or(false))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/password",
+    tag = "Auth",
+    security(
+        ("cookieAuth" = [])
+    ),
+    request_body = ChangePasswordReq,
+    responses(
+        (status = 200, description = "Password changed", body = ApiError),
+        (status = 400, description = "New password is too short or equals the current one", body = ApiError),
+        (status = 401, description = "Wrong current password", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(req): Json<ChangePasswordReq>,
+) -> impl IntoResponse {
+    let fail = |status: StatusCode, reason: &str| {
+        (status, Json(ApiError { ok: false, reason: reason.into() }))
+    };
+
+    if req.new_password.chars().count() < 8 {
+        return fail(StatusCode::BAD_REQUEST, "password_too_short");
+    }
+    if req.new_password == req.current_password {
+        return fail(StatusCode::BAD_REQUEST, "password_unchanged");
+    }
+
+    let Ok(mut conn) = state.pool.get() else {
+        return fail(StatusCode::SERVICE_UNAVAILABLE, "db_unavailable");
+    };
+
+    // проверка текущего пароля и замена хэша одним запросом
+    let updated = diesel::sql_query(
+        "UPDATE local_credentials
+         SET password_hash = crypt($1, gen_salt('bf')), must_change_password = FALSE
+         WHERE user_id = $2 AND password_hash = crypt($3, password_hash)",
+    )
+    .bind::<diesel::sql_types::Text, _>(&req.new_password)
+    .bind::<diesel::sql_types::Uuid, _>(ctx.user_id)
+    .bind::<diesel::sql_types::Text, _>(&req.current_password)
+    .execute(&mut conn);
+
+    match updated {
+        Ok(0) => fail(StatusCode::UNAUTHORIZED, "wrong_password"),
+        Ok(_) => (StatusCode::OK, Json(ApiError { ok: true, reason: "ok".into() })),
+        Err(e) => {
+            eprintln!("change_password error: {:?}", e);
+            fail(StatusCode::INTERNAL_SERVER_ERROR, "db_error")
+        }
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/api/auth/logout",
@@ -60,6 +60,18 @@ pub fn revoke_session(
     Ok(())
 }
 
+/// Отзывает все активные сессии пользователя (деактивация, удаление).
+pub fn revoke_user_sessions(
+    conn: &mut PgConnection,
+    uid: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    use crate::schema::sessions::dsl::*;
+
+    diesel::update(sessions.filter(user_id.eq(uid)).filter(revoked_at.is_null()))
+        .set(revoked_at.eq(chrono::Utc::now()))
+        .execute(conn)
+}
+
 pub fn lookup_session_user(
     conn: &mut PgConnection,
     secret: &str,
@@ -0,0 +1,157 @@
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::social::accounts::redact_secrets;
+
+/// Токен учебного бота; отдельный от ботов, через которые идут публикации.
+const BOT_TOKEN_ENV: &str = "TELEGRAM_LEARNING_BOT_TOKEN";
+
+/// Какие обновления бот просит у Telegram.
+pub const ALLOWED_UPDATES: [&str; 3] = ["message", "callback_query", "poll_answer"];
+
+#[derive(Debug, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+    pub callback_query: Option<CallbackQuery>,
+    pub poll_answer: Option<PollAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Message {
+    pub message_id: i64,
+    pub chat: Chat,
+    pub from: Option<User>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    pub id: String,
+    pub from: User,
+    pub message: Option<Message>,
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PollAnswer {
+    pub poll_id: String,
+    pub user: Option<User>,
+    pub option_ids: Vec<i32>,
+}
+
+/// Минимальный клиент Bot API для учебного бота.
+#[derive(Clone)]
+pub struct BotApi {
+    client: Client,
+    token: String,
+    /// `TELEGRAM_API_BASE_URL`, как у адаптера публикаций.
+    base_url: String,
+}
+
+impl BotApi {
+    /// `None` — бот не настроен.
+    pub fn from_env() -> Option<Self> {
+        let token = std::env::var(BOT_TOKEN_ENV).ok().filter(|t| !t.trim().is_empty())?;
+        Some(Self {
+            client: Client::new(),
+            token,
+            base_url: std::env::var("TELEGRAM_API_BASE_URL")
+                .ok()
+                .map(|u| u.trim().trim_end_matches('/').to_string())
+                .filter(|u| !u.is_empty())
+                .unwrap_or_else(|| "https://api.telegram.org".to_string()),
+        })
+    }
+
+    /// `result` ответа; ошибки без токена в тексте.
+    pub async fn call(&self, method: &str, body: Value) -> Result<Value> {
+        let resp: Value = self
+            .client
+            .post(format!("{}/bot{}/{method}", self.base_url, self.token))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| e.without_url())?
+            .json()
+            .await
+            .map_err(|e| e.without_url())?;
+
+        if !resp.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+            let description = resp.get("description").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(anyhow!("telegram {method}: {}", redact_secrets(description)));
+        }
+        Ok(resp.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Long polling: ждёт обновления до `timeout_secs`.
+    pub async fn get_updates(&self, offset: i64, timeout_secs: u64) -> Result<Vec<Update>> {
+        let result = self
+            .call(
+                "getUpdates",
+                json!({ "offset": offset, "timeout": timeout_secs, "allowed_updates": ALLOWED_UPDATES }),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// HTML-сообщение; `keyboard` — строки inline-кнопок `[(текст, callback_data)]`.
+    pub async fn send_message(&self, chat_id: i64, text: &str, keyboard: &[Vec<(&str, String)>]) -> Result<()> {
+        let mut body = json!({ "chat_id": chat_id, "text": text, "parse_mode": "HTML" });
+        if !keyboard.is_empty() {
+            body["reply_markup"] = inline_keyboard(keyboard);
+        }
+        self.call("sendMessage", body).await?;
+        Ok(())
+    }
+
+    pub async fn edit_message(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        text: &str,
+        keyboard: &[Vec<(&str, String)>],
+    ) -> Result<()> {
+        let body = json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+            "text": text,
+            "parse_mode": "HTML",
+            "reply_markup": inline_keyboard(keyboard),
+        });
+        self.call("editMessageText", body).await?;
+        Ok(())
+    }
+
+    /// Убирает «часики» на нажатой кнопке.
+    pub async fn answer_callback(&self, callback_query_id: &str) -> Result<()> {
+        self.call("answerCallbackQuery", json!({ "callback_query_id": callback_query_id }))
+            .await?;
+        Ok(())
+    }
+}
+
+fn inline_keyboard(rows: &[Vec<(&str, String)>]) -> Value {
+    let rows: Vec<Vec<Value>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|(text, data)| json!({ "text": text, "callback_data": data }))
+                .collect()
+        })
+        .collect();
+    json!({ "inline_keyboard": rows })
+}
@@ -0,0 +1,385 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Nullable, Text, Uuid as SqlUuid};
+use uuid::Uuid;
+
+use crate::api::quizlet::review::{due_cards, record_answer};
+use crate::bot::api::{BotApi, CallbackQuery, Message, PollAnswer};
+use crate::db::DbPool;
+use crate::models::news::NewsPost;
+use crate::schema::{news_posts, telegram_link_codes, telegram_links, telegram_quiz_polls, users};
+use crate::social::render::{escape_html, render_post, PostContext};
+use crate::social::types::SocialProvider;
+
+pub const LEVELS: [&str; 6] = ["A1", "A2", "B1", "B2", "C1", "C2"];
+/// Сколько живёт код привязки, выданный на сайте.
+pub const LINK_CODE_TTL_MINUTES: i64 = 15;
+
+const IDIOM_TEMPLATE: &str = "<b>{title}</b> {phonetic}\n\n{explanation}\n\n{excerpt}\n\n{level}";
+/// Лимиты Bot API для опросов.
+const POLL_QUESTION_MAX: usize = 300;
+const POLL_OPTION_MAX: usize = 100;
+const QUIZ_OPTIONS: i64 = 4;
+
+const HELP: &str = "Команды:\n\
+    /idiom [A1..C2] — случайная идиома\n\
+    /quiz [набор] — вопрос по карточкам набора\n\
+    /review — повторить карточки, срок которых подошёл\n\
+    /link КОД — привязать школьный аккаунт (код на сайте в профиле)\n\
+    /unlink — отвязать аккаунт";
+
+const NOT_LINKED: &str = "Сначала привяжите школьный аккаунт: получите код в профиле на сайте и отправьте /link КОД";
+
+/// Вопрос `/quiz`: термин карточки и варианты-объяснения из того же набора.
+#[derive(QueryableByName)]
+struct QuizOption {
+    #[diesel(sql_type = SqlUuid)]
+    card_id: Uuid,
+    #[diesel(sql_type = Text)]
+    term: String,
+    #[diesel(sql_type = Text)]
+    explanation: String,
+}
+
+/// Запрос к БД в blocking-пуле. Соединение возвращается в пул сразу, а не держится
+/// на время запросов к Bot API.
+async fn db<T, E>(pool: &DbPool, f: impl FnOnce(&mut PgConnection) -> Result<T, E> + Send + 'static) -> Result<T>
+where
+    T: Send + 'static,
+    E: Into<anyhow::Error>,
+{
+    let pool = pool.clone();
+    tokio::task::spawn_blocking(move || -> Result<T> {
+        let mut conn = pool.get()?;
+        f(&mut conn).map_err(Into::into)
+    })
+    .await?
+}
+
+pub async fn handle_message(pool: &DbPool, bot: &BotApi, message: Message) -> Result<()> {
+    let (Some(text), Some(from)) = (message.text.as_deref(), message.from.as_ref()) else {
+        return Ok(());
+    };
+    let chat_id = message.chat.id;
+    let telegram_user_id = from.id;
+    let mut parts = text.split_whitespace();
+    // в группах команда приходит как /quiz@bot_name
+    let command = parts.next().unwrap_or_default().split('@').next().unwrap_or_default();
+    let arg = parts.next();
+
+    match command {
+        "/start" | "/link" => match arg {
+            Some(code) => {
+                let (code, username) = (code.to_string(), from.username.clone());
+                let linked = db(pool, move |conn| {
+                    consume_link_code(conn, &code, telegram_user_id, chat_id, username.as_deref())
+                })
+                .await?;
+                let reply = match linked {
+                    Some(name) => format!("Готово: аккаунт {} привязан. /review — повторить карточки", escape_html(&name)),
+                    None => "Код не подошёл или устарел — получите новый на сайте".to_string(),
+                };
+                bot.send_message(chat_id, &reply, &[]).await
+            }
+            None => bot.send_message(chat_id, HELP, &[]).await,
+        },
+        "/unlink" => {
+            db(pool, move |conn| {
+                diesel::delete(telegram_links::table.filter(telegram_links::telegram_user_id.eq(telegram_user_id)))
+                    .execute(conn)
+            })
+            .await?;
+            bot.send_message(chat_id, "Аккаунт отвязан", &[]).await
+        }
+        "/idiom" => {
+            let level = arg.map(str::to_uppercase);
+            if level.as_deref().is_some_and(|l| !LEVELS.contains(&l)) {
+                return bot.send_message(chat_id, "Уровень — один из A1, A2, B1, B2, C1, C2", &[]).await;
+            }
+            let reply = match db(pool, move |conn| random_idiom(conn, level.as_deref())).await? {
+                Some(post) => {
+                    render_post(IDIOM_TEMPLATE, &PostContext::from(&post), SocialProvider::Telegram, false).text
+                }
+                None => "Идиом этого уровня пока нет".to_string(),
+            };
+            bot.send_message(chat_id, &reply, &[]).await
+        }
+        "/quiz" => send_quiz(pool, bot, chat_id, arg.map(str::to_string)).await,
+        "/review" => match db(pool, move |conn| linked_user(conn, telegram_user_id)).await? {
+            Some(user_id) => send_next_due(pool, bot, chat_id, user_id).await,
+            None => bot.send_message(chat_id, NOT_LINKED, &[]).await,
+        },
+        "/help" => bot.send_message(chat_id, HELP, &[]).await,
+        _ => Ok(()),
+    }
+}
+
+/// Кнопки `/review`: `show:<card>` — открыть ответ, `ok:<card>` / `no:<card>` — оценка.
+pub async fn handle_callback(pool: &DbPool, bot: &BotApi, query: CallbackQuery) -> Result<()> {
+    bot.answer_callback(&query.id).await?;
+    let (Some(data), Some(message)) = (query.data.as_deref(), query.message) else {
+        return Ok(());
+    };
+    let Some((action, card_id)) = data.split_once(':') else {
+        return Ok(());
+    };
+    let Ok(card_id) = card_id.parse::<Uuid>() else {
+        return Ok(());
+    };
+    let chat_id = message.chat.id;
+    let telegram_user_id = query.from.id;
+
+    let (user_id, card) = db(pool, move |conn| -> QueryResult<_> {
+        Ok((linked_user(conn, telegram_user_id)?, card_text(conn, card_id)?))
+    })
+    .await?;
+    let Some(user_id) = user_id else {
+        return bot.send_message(chat_id, NOT_LINKED, &[]).await;
+    };
+    let Some((term, explanation)) = card else {
+        return Ok(());
+    };
+    let card = format!("<b>{}</b>\n\n{}", escape_html(&term), escape_html(&explanation));
+
+    match action {
+        "show" => {
+            let buttons = vec![vec![("✅ Помню", format!("ok:{card_id}")), ("❌ Не помню", format!("no:{card_id}"))]];
+            bot.edit_message(chat_id, message.message_id, &card, &buttons).await
+        }
+        "ok" | "no" => {
+            let correct = action == "ok";
+            db(pool, move |conn| record_answer(conn, user_id, card_id, correct)).await?;
+            let mark = if correct { "✅" } else { "❌" };
+            bot.edit_message(chat_id, message.message_id, &format!("{card}\n\n{mark}"), &[])
+                .await?;
+            send_next_due(pool, bot, chat_id, user_id).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Ответ на опрос `/quiz` засчитывается привязанному ученику как повторение карточки.
+pub async fn handle_poll_answer(pool: &DbPool, answer: PollAnswer) -> Result<()> {
+    let Some(user) = answer.user else {
+        return Ok(());
+    };
+    // отозванный голос приходит с пустым option_ids
+    let Some(&chosen) = answer.option_ids.first() else {
+        return Ok(());
+    };
+
+    db(pool, move |conn| -> QueryResult<()> {
+        let Some((card_id, correct_option)) = telegram_quiz_polls::table
+            .find(&answer.poll_id)
+            .select((telegram_quiz_polls::card_id, telegram_quiz_polls::correct_option))
+            .first::<(Uuid, i32)>(conn)
+            .optional()?
+        else {
+            return Ok(());
+        };
+        if let Some(user_id) = linked_user(conn, user.id)? {
+            record_answer(conn, user_id, card_id, chosen == correct_option)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Активный школьный пользователь, привязанный к этому Telegram-аккаунту.
+fn linked_user(conn: &mut PgConnection, telegram_user_id: i64) -> QueryResult<Option<Uuid>> {
+    telegram_links::table
+        .inner_join(users::table)
+        .filter(telegram_links::telegram_user_id.eq(telegram_user_id))
+        .filter(users::is_active.eq(true))
+        .filter(users::deleted_at.is_null())
+        .select(users::id)
+        .first(conn)
+        .optional()
+}
+
+/// Гасит одноразовый код и привязывает Telegram-аккаунт; возвращает имя пользователя.
+/// Прежняя привязка этого Telegram-аккаунта (или этого пользователя) заменяется.
+fn consume_link_code(
+    conn: &mut PgConnection,
+    code: &str,
+    telegram_user_id: i64,
+    chat_id: i64,
+    telegram_username: Option<&str>,
+) -> QueryResult<Option<String>> {
+    conn.transaction(|conn| {
+        let Some(user_id) = diesel::delete(
+            telegram_link_codes::table
+                .filter(telegram_link_codes::code.eq(code.to_uppercase()))
+                .filter(telegram_link_codes::expires_at.gt(Utc::now())),
+        )
+        .returning(telegram_link_codes::user_id)
+        .get_result::<Uuid>(conn)
+        .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let Some(name) = users::table
+            .find(user_id)
+            .filter(users::is_active.eq(true))
+            .filter(users::deleted_at.is_null())
+            .select((users::full_name, users::username))
+            .first::<(Option<String>, Option<String>)>(conn)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        diesel::delete(telegram_links::table.filter(telegram_links::telegram_user_id.eq(telegram_user_id)))
+            .execute(conn)?;
+        diesel::insert_into(telegram_links::table)
+            .values((
+                telegram_links::user_id.eq(user_id),
+                telegram_links::telegram_user_id.eq(telegram_user_id),
+                telegram_links::chat_id.eq(chat_id),
+                telegram_links::telegram_username.eq(telegram_username),
+            ))
+            .on_conflict(telegram_links::user_id)
+            .do_update()
+            .set((
+                telegram_links::telegram_user_id.eq(telegram_user_id),
+                telegram_links::chat_id.eq(chat_id),
+                telegram_links::telegram_username.eq(telegram_username),
+                telegram_links::linked_at.eq(Utc::now()),
+            ))
+            .execute(conn)?;
+
+        Ok(Some(name.0.or(name.1).unwrap_or_default()))
+    })
+}
+
+fn random_idiom(conn: &mut PgConnection, level: Option<&str>) -> QueryResult<Option<NewsPost>> {
+    let mut query = news_posts::table.filter(news_posts::kind.eq("idiom")).into_boxed();
+    if let Some(level) = level {
+        query = query.filter(news_posts::level.eq(level));
+    }
+    query
+        .order(diesel::dsl::sql::<diesel::sql_types::Double>("random()"))
+        .first(conn)
+        .optional()
+}
+
+fn card_text(conn: &mut PgConnection, card_id: Uuid) -> QueryResult<Option<(String, String)>> {
+    use crate::schema::quizlet_cards;
+    quizlet_cards::table
+        .find(card_id)
+        .select((quizlet_cards::term, quizlet_cards::explanation))
+        .first(conn)
+        .optional()
+}
+
+/// Четыре карточки одного набора (указанного slug'ом или случайного) с разными объяснениями.
+/// Первая — вопрос; варианты потом идут в порядке id, так что правильный оказывается где угодно.
+fn quiz_cards(conn: &mut PgConnection, set_slug: Option<&str>) -> QueryResult<Vec<QuizOption>> {
+    diesel::sql_query(
+        r#"
+        WITH picked_set AS (
+            SELECT s.id
+            FROM quizlet_sets s
+            WHERE ($1::TEXT IS NULL OR s.slug = $1)
+              AND (SELECT count(DISTINCT c.explanation) FROM quizlet_cards c WHERE c.set_id = s.id) >= $2
+            ORDER BY random()
+            LIMIT 1
+        )
+        SELECT card_id, term, explanation
+        FROM (
+            SELECT DISTINCT ON (c.explanation) c.id AS card_id, c.term, c.explanation
+            FROM quizlet_cards c
+            WHERE c.set_id = (SELECT id FROM picked_set)
+            ORDER BY c.explanation, random()
+        ) t
+        ORDER BY random()
+        LIMIT $2
+        "#,
+    )
+    .bind::<Nullable<Text>, _>(set_slug)
+    .bind::<diesel::sql_types::BigInt, _>(QUIZ_OPTIONS)
+    .load(conn)
+}
+
+async fn send_quiz(pool: &DbPool, bot: &BotApi, chat_id: i64, set_slug: Option<String>) -> Result<()> {
+    let mut cards = db(pool, move |conn| quiz_cards(conn, set_slug.as_deref())).await?;
+    if cards.len() < QUIZ_OPTIONS as usize {
+        return bot
+            .send_message(chat_id, "Не нашлось набора хотя бы с четырьмя разными карточками", &[])
+            .await;
+    }
+
+    let answer = cards[0].card_id;
+    let question = format!("Что значит «{}»?", cards[0].term);
+    cards.sort_by_key(|c| c.card_id);
+    let correct_option = cards.iter().position(|c| c.card_id == answer).unwrap_or_default();
+    let options: Vec<String> = cards
+        .iter()
+        .map(|c| c.explanation.chars().take(POLL_OPTION_MAX).collect())
+        .collect();
+
+    // не анонимный: иначе Telegram не присылает poll_answer и ответ не попадёт в прогресс
+    let poll = bot
+        .call(
+            "sendPoll",
+            serde_json::json!({
+                "chat_id": chat_id,
+                "question": question.chars().take(POLL_QUESTION_MAX).collect::<String>(),
+                "options": options,
+                "type": "quiz",
+                "is_anonymous": false,
+                "correct_option_id": correct_option,
+            }),
+        )
+        .await?;
+
+    if let Some(poll_id) = poll.pointer("/poll/id").and_then(|v| v.as_str()).map(str::to_string) {
+        db(pool, move |conn| {
+            diesel::insert_into(telegram_quiz_polls::table)
+                .values((
+                    telegram_quiz_polls::poll_id.eq(poll_id),
+                    telegram_quiz_polls::chat_id.eq(chat_id),
+                    telegram_quiz_polls::card_id.eq(answer),
+                    telegram_quiz_polls::correct_option.eq(correct_option as i32),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Следующая карточка к повторению — термин и кнопка «Показать ответ».
+async fn send_next_due(pool: &DbPool, bot: &BotApi, chat_id: i64, user_id: Uuid) -> Result<()> {
+    let Some(card) = db(pool, move |conn| due_cards(conn, user_id, None, 1)).await?.into_iter().next() else {
+        return bot
+            .send_message(chat_id, "На сегодня всё повторено 🎉 Новые карточки — в наборах на сайте и в /quiz", &[])
+            .await;
+    };
+
+    let text = format!("<b>{}</b>\n\n<i>{}</i>", escape_html(&card.term), escape_html(&card.set_title));
+    let buttons = vec![vec![("Показать ответ", format!("show:{}", card.card_id))]];
+    bot.send_message(chat_id, &text, &buttons).await
+}
+
+/// Новый код привязки для пользователя; прежние коды гаснут.
+pub fn issue_link_code(conn: &mut PgConnection, user_id: Uuid) -> QueryResult<(String, chrono::DateTime<Utc>)> {
+    let code = Uuid::new_v4().simple().to_string()[..8].to_uppercase();
+    let expires_at = Utc::now() + Duration::minutes(LINK_CODE_TTL_MINUTES);
+
+    conn.transaction(|conn| {
+        diesel::delete(telegram_link_codes::table.filter(telegram_link_codes::user_id.eq(user_id)))
+            .execute(conn)?;
+        diesel::insert_into(telegram_link_codes::table)
+            .values((
+                telegram_link_codes::code.eq(&code),
+                telegram_link_codes::user_id.eq(user_id),
+                telegram_link_codes::expires_at.eq(expires_at),
+            ))
+            .execute(conn)?;
+        Ok((code, expires_at))
+    })
+}
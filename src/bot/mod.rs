@@ -0,0 +1,84 @@
+pub mod api;
+pub mod commands;
+
+use anyhow::Result;
+use serde_json::json;
+use tokio::time::{sleep, Duration};
+
+use crate::bot::api::{BotApi, Update, ALLOWED_UPDATES};
+use crate::db::DbPool;
+use crate::social::media::public_base_url;
+
+/// Секрет вебхука (`X-Telegram-Bot-Api-Secret-Token`); без него бот работает через long polling.
+pub const WEBHOOK_SECRET_ENV: &str = "TELEGRAM_WEBHOOK_SECRET";
+pub const WEBHOOK_PATH: &str = "/api/telegram/webhook";
+
+const POLL_TIMEOUT_SECS: u64 = 30;
+
+pub fn webhook_secret() -> Option<String> {
+    std::env::var(WEBHOOK_SECRET_ENV).ok().filter(|s| !s.trim().is_empty())
+}
+
+/// Учебный бот: `/idiom`, `/quiz`, `/review`, привязка аккаунта.
+/// С `TELEGRAM_WEBHOOK_SECRET` и `PUBLIC_BASE_URL` регистрирует вебхук, иначе опрашивает `getUpdates`.
+pub fn spawn_learning_bot(pool: DbPool) {
+    let Some(bot) = BotApi::from_env() else {
+        eprintln!("TELEGRAM_LEARNING_BOT_TOKEN is not set: learning bot is disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        if let (Some(secret), Some(base)) = (webhook_secret(), public_base_url()) {
+            let registered = bot
+                .call(
+                    "setWebhook",
+                    json!({
+                        "url": format!("{base}{WEBHOOK_PATH}"),
+                        "secret_token": secret,
+                        "allowed_updates": ALLOWED_UPDATES,
+                    }),
+                )
+                .await;
+            match registered {
+                Ok(_) => println!("[bot] webhook registered"),
+                Err(err) => eprintln!("[bot] setWebhook failed: {err:#}"),
+            }
+            return;
+        }
+
+        // getUpdates не работает, пока у бота висит вебхук
+        if let Err(err) = bot.call("deleteWebhook", json!({})).await {
+            eprintln!("[bot] deleteWebhook failed: {err:#}");
+        }
+
+        let mut offset = 0;
+        loop {
+            match bot.get_updates(offset, POLL_TIMEOUT_SECS).await {
+                Ok(updates) => {
+                    for update in updates {
+                        offset = offset.max(update.update_id + 1);
+                        if let Err(err) = handle_update(&pool, &bot, update).await {
+                            eprintln!("[bot] {err:#}");
+                        }
+                    }
+                }
+                Err(err) => {
+                    eprintln!("[bot] getUpdates failed: {err:#}");
+                    sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+}
+
+pub async fn handle_update(pool: &DbPool, bot: &BotApi, update: Update) -> Result<()> {
+    if let Some(message) = update.message {
+        commands::handle_message(pool, bot, message).await
+    } else if let Some(query) = update.callback_query {
+        commands::handle_callback(pool, bot, query).await
+    } else if let Some(answer) = update.poll_answer {
+        commands::handle_poll_answer(pool, answer).await
+    } else {
+        Ok(())
+    }
+}
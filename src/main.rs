@@ -6,6 +6,8 @@ mod social;
 mod schema;
 mod social_jobs;
 mod api_docs;
+mod admin;
+mod bot;
 
 use crate::db::init_pool;
 
@@ -25,25 +27,21 @@ use tower_cookies::CookieManagerLayer;
 use tower_http::services::ServeDir;
 
 use crate::social::worker::SocialWorker;
+use crate::social::worker_pool::WorkerStatus;
 
-use crate::models::social::SocialAccount;
-
-use crate::social::telegram_config::telegram_account_from_env;
-use crate::social::scheduler::telegram_hourly::spawn_hourly_telegram_greeting;
-
-use crate::social::threads_config::threads_account_from_env;
-use crate::social::scheduler::threads_hourly::spawn_hourly_threads_greeting;
-
-use crate::social::instagram_config::instagram_account_from_env;
-use crate::social::scheduler::instagram_hourly::spawn_hourly_instagram_greeting;
-
-use crate::social::vk_config::vk_account_from_env;
-use crate::social::scheduler::vk_hourly::spawn_hourly_vk_greeting;
+use crate::social::accounts::{bootstrap_accounts_from_env, reencrypt_stored_tokens};
+use crate::social::crypto::keyring;
+use crate::social::token_refresher::spawn_token_refresher;
+use crate::social::scheduler::campaigns::spawn_campaign_scheduler;
+use crate::social::metrics::spawn_metrics_collector;
+use crate::bot::spawn_learning_bot;
+use crate::social::media::{signed_urls_configured, IMAGES_DIR};
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: DbPool,
     pub session_secret: String,
+    pub social_worker: WorkerStatus,
 }
    
 #[tokio::main]
@@ -53,20 +51,28 @@ async fn main() {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is required");
     let pool = init_pool(&database_url);
 
-    let worker = SocialWorker::new(pool.clone());
+    // SIGTERM/Ctrl-C: сервер перестаёт принимать запросы, worker дописывает начатые публикации
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
     tokio::spawn(async move {
-        worker.run_forever().await;
+        shutdown_signal().await;
+        println!("shutdown signal received");
+        let _ = shutdown_tx.send(true);
     });
 
+    let worker = SocialWorker::new(pool.clone());
+    let social_worker = worker.status();
+    let worker_task = tokio::spawn(worker.run(shutdown_rx.clone()));
+
     let session_secret = env::var("SESSION_SECRET").expect("SESSION_SECRET is required");
 
-    let state = AppState { pool, session_secret };
+    let state = AppState { pool, session_secret, social_worker };
 
     // public: только login/logout (и, при необходимости, register/reset-password)
     let api_public = Router::new()
         .route("/auth/login", post(auth::routes::login))
         .route("/auth/logout", post(auth::routes::logout))
             .route("/news", get(crate::api::news::list_news))   // ✅ сюда
+        .route("/telegram/webhook", post(crate::api::telegram::telegram_webhook))
         .with_state(state.clone());
 
     // protected: всё бизнесовое
@@ -80,6 +86,61 @@ async fn main() {
         "/users/{id}",
         axum::routing::put(crate::api::admin::users::update_user)
             .delete(crate::api::admin::users::delete_user),
+    )
+    .route("/users/{id}/deactivate", post(crate::api::admin::users::deactivate_user))
+    .route("/users/{id}/activate", post(crate::api::admin::users::activate_user))
+    .route("/users/{id}/purge", post(crate::api::admin::users::purge_user))
+    .route("/users/{id}/export", get(crate::api::admin::users_export::export_user))
+    .route(
+        "/users/{id}/roles",
+        get(crate::api::admin::roles::list_user_roles)
+            .post(crate::api::admin::roles::assign_role),
+    )
+    .route(
+        "/users/{id}/roles/{role_key}",
+        axum::routing::delete(crate::api::admin::roles::revoke_role),
+    )
+    .route(
+        "/users/{id}/permissions",
+        get(crate::api::admin::roles::user_effective_permissions),
+    )
+    .route(
+        "/roles",
+        get(crate::api::admin::roles::list_roles)
+            .post(crate::api::admin::roles::create_role),
+    )
+    .route(
+        "/roles/{role_key}",
+        axum::routing::delete(crate::api::admin::roles::delete_role),
+    )
+    .route(
+        "/roles/{role_key}/permissions",
+        post(crate::api::admin::roles::attach_permission),
+    )
+    .route(
+        "/roles/{role_key}/permissions/{permission_key}",
+        axum::routing::delete(crate::api::admin::roles::detach_permission),
+    )
+    .route("/permissions", get(crate::api::admin::roles::list_permissions))
+    .route(
+        "/social/accounts",
+        get(crate::api::admin::social_accounts::list_social_accounts)
+            .post(crate::api::admin::social_accounts::create_social_account),
+    )
+    .route(
+        "/social/accounts/{id}",
+        get(crate::api::admin::social_accounts::get_social_account)
+            .put(crate::api::admin::social_accounts::update_social_account)
+            .delete(crate::api::admin::social_accounts::delete_social_account),
+    )
+    .route(
+        "/social/accounts/{id}/test",
+        post(crate::api::admin::social_accounts::test_social_account),
+    )
+    .route("/notifications", get(crate::api::admin::notifications::list_notifications))
+    .route(
+        "/notifications/{id}/read",
+        post(crate::api::admin::notifications::mark_notification_read),
     )
         .route(
         "/test-instagram",
@@ -87,6 +148,60 @@ async fn main() {
     )
     .layer(axum::middleware::from_fn(auth::middleware::require_admin));
 
+    // аудит читают все, у кого есть content.audit.read (в т.ч. editor), поэтому без require_admin
+    let audit_routes = Router::new()
+        .route("/admin/audit", get(crate::api::admin::audit::list_audit))
+        .route("/admin/audit/export", get(crate::api::admin::audit::export_audit_csv));
+
+    // импорт разрешён и не-админам с users.import, право проверяется в handler'е
+    let user_import_routes =
+        Router::new().route("/admin/users/import", post(crate::api::admin::users_import::import_users));
+
+    // планирование публикаций: social.publish (admin и editor) проверяется в handler'ах
+    let social_job_routes = Router::new()
+        .route("/admin/news/{id}/social-jobs", post(crate::api::admin::social_jobs::schedule_news))
+        .route("/admin/social/jobs", get(crate::api::admin::social_jobs::list_social_jobs))
+        .route("/admin/social/jobs/{id}/cancel", post(crate::api::admin::social_jobs::cancel_social_job))
+        .route(
+            "/admin/social/jobs/{id}/reschedule",
+            post(crate::api::admin::social_jobs::reschedule_social_job),
+        )
+        .route("/admin/social/jobs/{id}/attempts", get(crate::api::admin::social_jobs::list_job_attempts))
+        .route("/admin/social/jobs/{id}/edit", post(crate::api::admin::social_jobs::edit_social_job))
+        .route("/admin/social/jobs/{id}/delete", post(crate::api::admin::social_jobs::delete_social_post))
+        .route(
+            "/admin/news/{id}/social-jobs/propagate",
+            post(crate::api::admin::social_jobs::propagate_news_update),
+        )
+        .route("/admin/news/{id}", axum::routing::patch(crate::api::admin::news::update_news))
+        .route(
+            "/admin/social/accounts/{id}/preview",
+            post(crate::api::admin::social_jobs::preview_social_post),
+        )
+        .route(
+            "/admin/news/{id}/social-preview",
+            get(crate::api::admin::social_jobs::preview_news_social_posts),
+        )
+        .route("/admin/social/worker", get(crate::api::admin::social_jobs::social_worker_status))
+        .route(
+            "/admin/social/campaigns",
+            get(crate::api::admin::social_campaigns::list_social_campaigns)
+                .post(crate::api::admin::social_campaigns::create_social_campaign),
+        )
+        .route(
+            "/admin/social/campaigns/{id}",
+            axum::routing::put(crate::api::admin::social_campaigns::update_social_campaign)
+                .delete(crate::api::admin::social_campaigns::delete_social_campaign),
+        )
+        .route(
+            "/admin/social/campaigns/{id}/run",
+            post(crate::api::admin::social_campaigns::run_social_campaign_now),
+        )
+        .route(
+            "/admin/social/metrics/report",
+            get(crate::api::admin::social_metrics::engagement_report),
+        );
+
     let subjects_read = Router::new()
     .route("/subjects", get(crate::api::subjects::routes::list_subjects))
     .route("/subjects/{id}", get(crate::api::subjects::routes::get_subject));
@@ -119,7 +234,9 @@ async fn main() {
         .route("/quizlet/sets/{set_id}", axum::routing::put(crate::api::quizlet::sets::upsert_set))
         .route("/quizlet/sets/{set_id}/cards:replace", axum::routing::put(crate::api::quizlet::cards_replace::replace_cards))
         .route("/quizlet/sets/{set_id}/full", get(crate::api::quizlet::full::get_set_full))
-        .route("/quizlet/sets/{set_id}/cards:flip", post(crate::api::quizlet::sets::flip_set_cards));
+        .route("/quizlet/sets/{set_id}/cards:flip", post(crate::api::quizlet::sets::flip_set_cards))
+        .route("/quizlet/review/due", get(crate::api::quizlet::review::list_due_cards))
+        .route("/quizlet/review/{card_id}", post(crate::api::quizlet::review::review_card));
 
     let quizlet_folder_routes = Router::new()
         .route(
@@ -153,19 +270,45 @@ async fn main() {
         .merge(subjects_read)
         .merge(subjects_write)
         .merge(library_routes)
+        .merge(audit_routes)
+        .merge(user_import_routes)
+        .merge(social_job_routes)
         .nest("/admin", admin_routes)
         .route("/me", get(auth::routes::me_handler))
+        .route("/me/export", get(crate::api::admin::users_export::export_me))
+        .route(
+            "/me/telegram",
+            get(crate::api::telegram::get_telegram_link).delete(crate::api::telegram::unlink_telegram),
+        )
+        .route("/me/telegram/link-code", post(crate::api::telegram::create_link_code))
 //        .route("/users", get(routes::get_users))
         .route("/auth/me", get(auth::routes::session_me))
+        .route("/auth/password", post(auth::routes::change_password))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            auth::middleware::session_middleware,
+        ))
+        .with_state(state.clone());
+    // askama-админка: та же sid-сессия, что и у SPA
+    let admin_pages = Router::new()
+        .route("/users", get(crate::admin::routes::admin_users_page))
+        .route("/roles", get(crate::admin::routes::admin_roles_page))
+        .layer(axum::middleware::from_fn(auth::middleware::require_admin))
+        // аудит и публикации проверяют права внутри handler'ов
+        .route("/audit", get(crate::admin::routes::admin_audit_page))
+        .route("/social/jobs", get(crate::admin::routes::admin_social_jobs_page))
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
             auth::middleware::session_middleware,
         ))
         .with_state(state.clone());
+
     // общий app
     let app = Router::new()
         .nest("/api", api_public.merge(api_protected))
-        .nest_service("/images", ServeDir::new("images"))
+        .nest("/admin", admin_pages)
+        .nest_service("/images", ServeDir::new(IMAGES_DIR))
+        .route("/media/{*path}", get(crate::api::media::signed_media))
         .merge(
         SwaggerUi::new("/docs")
             .url("/api-docs/openapi.json", api_docs::ApiDoc::openapi())
@@ -187,20 +330,70 @@ async fn main() {
         std::path::Path::new("server/images/idioms/B1/give-someone-the-cold-shoulder.png").exists()
     );
 
-    let telegram_account = telegram_account_from_env();
-    spawn_hourly_telegram_greeting(telegram_account.expect("REASON"));
-    let threads_account = threads_account_from_env();
-    spawn_hourly_threads_greeting(threads_account.expect("REASON"));
-    let instagram_account = instagram_account_from_env();
-    spawn_hourly_instagram_greeting(instagram_account.expect("REASON"));
-//    let vk_account = vk_account_from_env();
-//    spawn_hourly_vk_greeting(vk_account.expect("REASON"));
+    // аккаунты соцсетей живут в social_accounts; env используется только для первичного заполнения
+    match state.pool.get().map_err(anyhow::Error::from).and_then(|mut conn| bootstrap_accounts_from_env(&mut conn)) {
+        Ok(imported) if !imported.is_empty() => println!("social accounts imported from env: {:?}", imported),
+        Ok(_) => {}
+        Err(err) => eprintln!("social accounts bootstrap failed: {err:#}"),
+    }
+
+    if !signed_urls_configured() {
+        eprintln!("PUBLIC_BASE_URL/MEDIA_URL_SECRET are not set: local images can't be posted to Instagram and Threads");
+    }
+
+    if keyring().is_none() {
+        eprintln!("SOCIAL_TOKEN_KEYS is not set: social tokens are stored unencrypted");
+    }
+    match state.pool.get().map_err(anyhow::Error::from).and_then(|mut conn| reencrypt_stored_tokens(&mut conn)) {
+        Ok(n) if n > 0 => println!("social tokens (re)encrypted: {n} accounts"),
+        Ok(_) => {}
+        Err(err) => eprintln!("social tokens re-encryption failed: {err:#}"),
+    }
+
+    spawn_token_refresher(state.pool.clone());
+    spawn_campaign_scheduler(state.pool.clone());
+    spawn_metrics_collector(state.pool.clone());
+    spawn_learning_bot(state.pool.clone());
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .expect("Failed to bind address");
 
+    let mut server_shutdown = shutdown_rx;
     axum::serve(listener, app.into_make_service())
+    .with_graceful_shutdown(async move {
+        let _ = server_shutdown.wait_for(|stop| *stop).await;
+    })
     .await
     .unwrap();
+
+    if let Err(err) = worker_task.await {
+        eprintln!("social worker task failed: {err}");
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(err) => {
+                eprintln!("SIGTERM handler failed: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
@@ -1,7 +1,9 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use diesel::prelude::*;
 use diesel_json::Json;
+use serde::Serialize;
 
-use crate::schema::audit_log;
+use crate::schema::{audit_log, users};
 
 #[derive(Insertable)]
 #[diesel(table_name = audit_log)]
@@ -9,7 +11,7 @@ pub struct NewAuditEvent {
     actor_id: Option<uuid::Uuid>,
     action: String,
     entity_type: String,
-    entity_id: uuid::Uuid,
+    entity_id: String,
     metadata: Option<Json<serde_json::Value>>,
 }
 
@@ -18,7 +20,7 @@ pub fn write_audit(
     actor_id: Option<uuid::Uuid>,
     action: &str,
     entity_type: &str,
-    entity_id: uuid::Uuid,
+    entity_id: impl ToString,
     metadata: Option<serde_json::Value>,
 ) -> Result<(), diesel::result::Error> {
     diesel::insert_into(audit_log::table)
@@ -26,9 +28,118 @@ pub fn write_audit(
             actor_id,
             action: action.to_string(),
             entity_type: entity_type.to_string(),
-            entity_id,
+            entity_id: entity_id.to_string(),
             metadata: metadata.map(Json),
         })
         .execute(conn)?;
     Ok(())
 }
+
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct AuditEvent {
+    pub id: uuid::Uuid,
+    pub actor_id: Option<uuid::Uuid>,
+    pub actor_username: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub metadata: Option<serde_json::Value>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Фильтры для чтения audit_log (все поля опциональны).
+#[derive(Debug, Default, Clone)]
+pub struct AuditFilter {
+    pub actor_id: Option<uuid::Uuid>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+}
+
+/// Позиция keyset-пагинации: последняя выданная запись (created_at DESC, id DESC).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditCursor {
+    pub created_at: chrono::NaiveDateTime,
+    pub id: uuid::Uuid,
+}
+
+impl AuditCursor {
+    pub fn encode(&self) -> String {
+        let raw = format!("{}|{}", self.created_at.and_utc().timestamp_micros(), self.id);
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(s).ok()?).ok()?;
+        let (micros, id) = raw.split_once('|')?;
+        let created_at = chrono::DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc();
+        Some(Self {
+            created_at,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+/// `%` и `_` в префиксе — обычные символы, а не шаблон LIKE.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Читает audit_log по фильтрам, новые записи первыми.
+/// `after` — курсор предыдущей страницы, `limit` — размер страницы.
+pub fn query_audit(
+    conn: &mut PgConnection,
+    filter: &AuditFilter,
+    after: Option<AuditCursor>,
+    limit: i64,
+) -> Result<Vec<AuditEvent>, diesel::result::Error> {
+    let mut q = audit_log::table
+        .left_join(users::table.on(users::id.nullable().eq(audit_log::actor_id)))
+        .into_boxed();
+
+    if let Some(actor) = filter.actor_id {
+        q = q.filter(audit_log::actor_id.eq(actor));
+    }
+    if let Some(action) = filter.action.as_deref() {
+        // "user.*" — все действия с префиксом "user."
+        match action.strip_suffix('*') {
+            Some(prefix) => q = q.filter(audit_log::action.like(format!("{}%", escape_like(prefix)))),
+            None => q = q.filter(audit_log::action.eq(action.to_string())),
+        }
+    }
+    if let Some(entity_type) = filter.entity_type.as_deref() {
+        q = q.filter(audit_log::entity_type.eq(entity_type.to_string()));
+    }
+    if let Some(entity_id) = filter.entity_id.as_deref() {
+        q = q.filter(audit_log::entity_id.eq(entity_id.to_string()));
+    }
+    if let Some(from) = filter.from {
+        q = q.filter(audit_log::created_at.ge(from));
+    }
+    if let Some(to) = filter.to {
+        q = q.filter(audit_log::created_at.lt(to));
+    }
+    if let Some(c) = after {
+        q = q.filter(
+            audit_log::created_at
+                .lt(c.created_at)
+                .or(audit_log::created_at.eq(c.created_at).and(audit_log::id.lt(c.id))),
+        );
+    }
+
+    q.select((
+        audit_log::id,
+        audit_log::actor_id,
+        users::username.nullable(),
+        audit_log::action,
+        audit_log::entity_type,
+        audit_log::entity_id,
+        audit_log::metadata,
+        audit_log::created_at,
+    ))
+    .order((audit_log::created_at.desc(), audit_log::id.desc()))
+    .limit(limit)
+    .load::<AuditEvent>(conn)
+}
@@ -3,5 +3,6 @@ pub mod content;
 pub mod audit;
 pub mod news; 
 pub mod social;
+pub mod notifications;
 
 pub use users::*;
\ No newline at end of file
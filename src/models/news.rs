@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
@@ -32,4 +32,21 @@ pub struct NewsListResponse {
     pub items: Vec<NewsPost>,
     // если ты добавляла next_cursor — оставь, иначе удали
     // pub next_cursor: Option<String>,
-}
\ No newline at end of file
+}
+
+/// Правка новости из админки: `None` — поле не трогаем.
+#[derive(Debug, Default, Deserialize, diesel::AsChangeset)]
+#[diesel(table_name = crate::schema::news_posts)]
+pub struct NewsPostChanges {
+    pub title: Option<String>,
+    pub excerpt: Option<String>,
+    pub body: Option<String>,
+    pub source: Option<String>,
+    pub url: Option<String>,
+    pub level: Option<String>,
+    pub image_url: Option<String>,
+    pub phonetic: Option<String>,
+    pub is_hot: Option<bool>,
+    pub tags: Option<Vec<Option<String>>>,
+    pub explanation_en: Option<String>,
+}
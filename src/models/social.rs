@@ -4,7 +4,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
-use crate::schema::{social_accounts, social_post_attempts, social_post_jobs};
+use crate::schema::{
+    social_accounts, social_campaigns, social_post_attempts, social_post_jobs, social_post_metrics,
+};
 
 #[derive(Debug, Clone, Queryable, Selectable, Serialize, Deserialize)]
 #[diesel(table_name = social_accounts)]
@@ -20,6 +22,9 @@ pub struct SocialAccount {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub token_refreshed_at: Option<DateTime<Utc>>,
+    pub auth_error: Option<String>,
+    pub auth_error_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Insertable, Serialize, Deserialize)]
@@ -39,7 +44,7 @@ pub struct NewSocialAccount {
 #[diesel(table_name = social_post_jobs)]
 pub struct SocialPostJob {
     pub id: i64,
-    pub news_post_id: uuid::Uuid,
+    pub news_post_id: Uuid,
     pub social_account_id: i64,
     pub status: String,
     pub scheduled_for: DateTime<Utc>,
@@ -51,12 +56,22 @@ pub struct SocialPostJob {
     pub payload_json: Value,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub locked_by: Option<String>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub idempotency_key: Uuid,
+    pub publish_started_at: Option<DateTime<Utc>>,
+    pub publish_state: Option<Value>,
+    pub campaign_id: Option<i64>,
+    /// Больше — раньше среди наступивших.
+    pub priority: i32,
+    /// `PublishResult::published_state` вышедшего поста.
+    pub published_state: Option<Value>,
 }
 
 #[derive(Debug, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = social_post_jobs)]
 pub struct NewSocialPostJob {
-    pub news_post_id: uuid::Uuid,
+    pub news_post_id: Uuid,
     pub social_account_id: i64,
     pub status: String,
     pub scheduled_for: DateTime<Utc>,
@@ -66,6 +81,8 @@ pub struct NewSocialPostJob {
     pub external_post_id: Option<String>,
     pub error_message: Option<String>,
     pub payload_json: Value,
+    pub campaign_id: Option<i64>,
+    pub priority: i32,
 }
 
 #[derive(Debug, AsChangeset, Default, Serialize, Deserialize)]
@@ -83,13 +100,14 @@ pub struct UpdateSocialPostJob {
 #[derive(Debug, Clone, Queryable, Selectable, Serialize, Deserialize)]
 #[diesel(table_name = social_post_attempts)]
 pub struct SocialPostAttempt {
-    pub id: Uuid,
+    pub id: i64,
     pub social_post_job_id: i64,
     pub attempt_no: i32,
     pub status: String,
     pub response_body: Option<String>,
     pub error_message: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub error_kind: Option<String>,
 }
 
 #[derive(Debug, Insertable, Serialize, Deserialize)]
@@ -100,4 +118,46 @@ pub struct NewSocialPostAttempt {
     pub status: String,
     pub response_body: Option<String>,
     pub error_message: Option<String>,
-}
\ No newline at end of file
+    pub error_kind: Option<String>,
+}
+#[derive(Debug, Clone, Queryable, Selectable, Serialize, Deserialize)]
+#[diesel(table_name = social_campaigns)]
+pub struct SocialCampaign {
+    pub id: i64,
+    pub name: String,
+    pub cron_expr: String,
+    pub timezone: String,
+    pub content_source: Value,
+    pub is_active: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = social_campaigns)]
+pub struct NewSocialCampaign {
+    pub name: String,
+    pub cron_expr: String,
+    pub timezone: String,
+    pub content_source: Value,
+    pub is_active: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+/// Снимок метрик; при повторном сборе заменяет предыдущий целиком.
+#[derive(Debug, Insertable, AsChangeset)]
+#[diesel(table_name = social_post_metrics, treat_none_as_null = true)]
+pub struct NewSocialPostMetrics {
+    pub social_post_job_id: i64,
+    pub views: Option<i64>,
+    pub likes: Option<i64>,
+    pub reactions: Option<i64>,
+    pub comments: Option<i64>,
+    pub shares: Option<i64>,
+    pub raw: Option<Value>,
+    pub last_error: Option<String>,
+    pub collected_at: DateTime<Utc>,
+}
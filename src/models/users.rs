@@ -15,6 +15,8 @@ pub struct User {
     pub username: Option<String>,
     pub full_name: Option<String>,
     pub gender: Option<String>,
+    pub is_active: bool,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,7 +35,8 @@ pub struct UpdateUserReq {
     pub username: Option<String>,
     pub full_name: Option<String>,
     pub gender: Option<String>,
-    pub role: Option<String>, // это НЕ колонка users
+    /// Добавляется к ролям пользователя, остальные не снимаются (это НЕ колонка users)
+    pub role: Option<String>,
 }
 
 #[derive(Debug, AsChangeset)]
@@ -74,6 +77,7 @@ pub struct NewUserDb {
 pub struct NewLocalCredentialDb {
     pub user_id: Uuid,
     pub password_hash: String,
+    pub must_change_password: bool,
 }
 #[derive(Insertable, Debug, Deserialize)]
 #[diesel(table_name = users)]
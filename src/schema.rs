@@ -6,18 +6,39 @@ pub mod sql_types {
     pub struct Tsvector;
 }
 
+diesel::table! {
+    admin_notifications (id) {
+        id -> Int8,
+        kind -> Text,
+        title -> Text,
+        body -> Nullable<Text>,
+        entity_type -> Nullable<Text>,
+        entity_id -> Nullable<Text>,
+        created_at -> Timestamptz,
+        read_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     audit_log (id) {
         id -> Uuid,
         actor_id -> Nullable<Uuid>,
         action -> Text,
         entity_type -> Text,
-        entity_id -> Uuid,
+        entity_id -> Text,
         metadata -> Nullable<Jsonb>,
         created_at -> Timestamp,
     }
 }
 
+diesel::table! {
+    class_members (class_id, user_id) {
+        class_id -> Uuid,
+        user_id -> Uuid,
+        added_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     content_items (id) {
         id -> Uuid,
@@ -110,6 +131,7 @@ diesel::table! {
         user_id -> Uuid,
         password_hash -> Text,
         created_at -> Nullable<Timestamp>,
+        must_change_password -> Bool,
     }
 }
 
@@ -151,6 +173,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    quizlet_card_progress (user_id, card_id) {
+        user_id -> Uuid,
+        card_id -> Uuid,
+        box_no -> Int4,
+        correct_count -> Int4,
+        wrong_count -> Int4,
+        due_at -> Timestamptz,
+        last_reviewed_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     quizlet_cards (id) {
         id -> Uuid,
@@ -219,6 +253,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    school_classes (id) {
+        id -> Uuid,
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     sessions (id) {
         id -> Uuid,
@@ -244,6 +286,32 @@ diesel::table! {
         is_active -> Bool,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        token_refreshed_at -> Nullable<Timestamptz>,
+        auth_error -> Nullable<Text>,
+        auth_error_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    social_campaign_accounts (campaign_id, social_account_id) {
+        campaign_id -> Int8,
+        social_account_id -> Int8,
+    }
+}
+
+diesel::table! {
+    social_campaigns (id) {
+        id -> Int8,
+        name -> Text,
+        cron_expr -> Text,
+        timezone -> Text,
+        content_source -> Jsonb,
+        is_active -> Bool,
+        next_run_at -> Nullable<Timestamptz>,
+        last_run_at -> Nullable<Timestamptz>,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -256,6 +324,7 @@ diesel::table! {
         response_body -> Nullable<Text>,
         error_message -> Nullable<Text>,
         created_at -> Timestamptz,
+        error_kind -> Nullable<Text>,
     }
 }
 
@@ -274,6 +343,28 @@ diesel::table! {
         payload_json -> Jsonb,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        locked_by -> Nullable<Text>,
+        locked_until -> Nullable<Timestamptz>,
+        idempotency_key -> Uuid,
+        publish_started_at -> Nullable<Timestamptz>,
+        publish_state -> Nullable<Jsonb>,
+        campaign_id -> Nullable<Int8>,
+        priority -> Int4,
+        published_state -> Nullable<Jsonb>,
+    }
+}
+
+diesel::table! {
+    social_post_metrics (social_post_job_id) {
+        social_post_job_id -> Int8,
+        views -> Nullable<Int8>,
+        likes -> Nullable<Int8>,
+        reactions -> Nullable<Int8>,
+        comments -> Nullable<Int8>,
+        shares -> Nullable<Int8>,
+        raw -> Nullable<Jsonb>,
+        last_error -> Nullable<Text>,
+        collected_at -> Timestamptz,
     }
 }
 
@@ -299,6 +390,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    telegram_link_codes (code) {
+        code -> Text,
+        user_id -> Uuid,
+        expires_at -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    telegram_links (user_id) {
+        user_id -> Uuid,
+        telegram_user_id -> Int8,
+        chat_id -> Int8,
+        telegram_username -> Nullable<Text>,
+        linked_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    telegram_quiz_polls (poll_id) {
+        poll_id -> Text,
+        chat_id -> Int8,
+        card_id -> Uuid,
+        correct_option -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     user_roles (user_id, role_id) {
         user_id -> Uuid,
@@ -317,10 +437,14 @@ diesel::table! {
         username -> Nullable<Text>,
         full_name -> Nullable<Text>,
         gender -> Nullable<Text>,
+        is_active -> Bool,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
 diesel::joinable!(audit_log -> users (actor_id));
+diesel::joinable!(class_members -> school_classes (class_id));
+diesel::joinable!(class_members -> users (user_id));
 diesel::joinable!(content_items -> users (created_by));
 diesel::joinable!(content_versions -> content_items (content_id));
 diesel::joinable!(content_versions -> users (created_by));
@@ -334,22 +458,33 @@ diesel::joinable!(course_units -> course_levels (level_id));
 diesel::joinable!(course_units -> users (created_by));
 diesel::joinable!(local_credentials -> users (user_id));
 diesel::joinable!(publishers -> users (created_by));
+diesel::joinable!(quizlet_card_progress -> quizlet_cards (card_id));
+diesel::joinable!(quizlet_card_progress -> users (user_id));
 diesel::joinable!(quizlet_cards -> quizlet_sets (set_id));
 diesel::joinable!(quizlet_folder_sets -> quizlet_folders (folder_id));
 diesel::joinable!(quizlet_folder_sets -> quizlet_sets (set_id));
 diesel::joinable!(role_permissions -> permissions (permission_id));
 diesel::joinable!(role_permissions -> roles (role_id));
 diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(social_campaign_accounts -> social_accounts (social_account_id));
+diesel::joinable!(social_campaign_accounts -> social_campaigns (campaign_id));
 diesel::joinable!(social_post_attempts -> social_post_jobs (social_post_job_id));
 diesel::joinable!(social_post_jobs -> news_posts (news_post_id));
 diesel::joinable!(social_post_jobs -> social_accounts (social_account_id));
+diesel::joinable!(social_post_jobs -> social_campaigns (campaign_id));
+diesel::joinable!(social_post_metrics -> social_post_jobs (social_post_job_id));
 diesel::joinable!(subject_content_items -> content_items (content_id));
 diesel::joinable!(subject_content_items -> subjects (subject_id));
 diesel::joinable!(subjects -> users (created_by));
+diesel::joinable!(telegram_link_codes -> users (user_id));
+diesel::joinable!(telegram_links -> users (user_id));
+diesel::joinable!(telegram_quiz_polls -> quizlet_cards (card_id));
 diesel::joinable!(user_roles -> roles (role_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    admin_notifications,
     audit_log,
+    class_members,
     content_items,
     content_versions,
     course_levels,
@@ -360,18 +495,26 @@ diesel::allow_tables_to_appear_in_same_query!(
     news_posts,
     permissions,
     publishers,
+    quizlet_card_progress,
     quizlet_cards,
     quizlet_folder_sets,
     quizlet_folders,
     quizlet_sets,
     role_permissions,
     roles,
+    school_classes,
     sessions,
     social_accounts,
+    social_campaign_accounts,
+    social_campaigns,
     social_post_attempts,
     social_post_jobs,
+    social_post_metrics,
     subject_content_items,
     subjects,
+    telegram_link_codes,
+    telegram_links,
+    telegram_quiz_polls,
     user_roles,
     users,
 );
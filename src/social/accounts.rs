@@ -0,0 +1,273 @@
+use anyhow::Result;
+use diesel::prelude::*;
+
+use crate::models::social::{NewSocialAccount, SocialAccount};
+use crate::schema::social_accounts;
+use crate::social::crypto::{keyring, open_token, seal_token};
+use crate::social::instagram_config::instagram_account_from_env;
+use crate::social::telegram_config::telegram_account_from_env;
+use crate::social::threads_config::threads_account_from_env;
+use crate::social::types::SocialProvider;
+use crate::social::vk_config::vk_account_from_env;
+
+/// Токены аккаунта в открытом виде (в БД они зашифрованы, см. `social::crypto`).
+pub fn decrypt_account(mut account: SocialAccount) -> Result<SocialAccount> {
+    account.access_token = open_token(account.access_token)?;
+    account.refresh_token = open_token(account.refresh_token)?;
+    Ok(account)
+}
+
+/// Аккаунт из social_accounts по id, токены расшифрованы.
+pub fn load_account(conn: &mut PgConnection, id: i64) -> Result<Option<SocialAccount>> {
+    social_accounts::table
+        .find(id)
+        .select(SocialAccount::as_select())
+        .first(conn)
+        .optional()?
+        .map(decrypt_account)
+        .transpose()
+}
+
+/// Строка social_accounts как есть, токены зашифрованы: так аккаунт с нерасшифровываемыми
+/// токенами можно поправить или удалить.
+pub fn load_stored_account(conn: &mut PgConnection, id: i64) -> QueryResult<Option<SocialAccount>> {
+    social_accounts::table
+        .find(id)
+        .select(SocialAccount::as_select())
+        .first(conn)
+        .optional()
+}
+
+/// Аккаунт для админки: если токены не расшифровываются, он возвращается без них
+/// и с `auth_error`, как в [`load_all_accounts`].
+pub fn load_account_for_admin(conn: &mut PgConnection, id: i64) -> Result<Option<SocialAccount>> {
+    let Some(stored) = load_stored_account(conn, id)? else {
+        return Ok(None);
+    };
+    Ok(decrypt_accounts(conn, vec![stored], true)?.pop())
+}
+
+/// Расшифровывает батч аккаунтов. Аккаунт, токены которого не открываются (ключ убрали
+/// из env раньше времени, значение побито), помечается `auth_error` и не роняет остальные:
+/// `keep_broken` оставляет его в выдаче без токенов (админке надо его показать), иначе он пропускается.
+fn decrypt_accounts(
+    conn: &mut PgConnection,
+    rows: Vec<SocialAccount>,
+    keep_broken: bool,
+) -> Result<Vec<SocialAccount>> {
+    let mut accounts = Vec::with_capacity(rows.len());
+    for mut account in rows {
+        let (access, refresh) = (account.access_token.clone(), account.refresh_token.clone());
+        let err = match (open_token(access), open_token(refresh)) {
+            (Ok(access), Ok(refresh)) => {
+                account.access_token = access;
+                account.refresh_token = refresh;
+                accounts.push(account);
+                continue;
+            }
+            (Err(err), _) | (_, Err(err)) => err,
+        };
+
+        let msg = format!("token decryption failed: {err:#}");
+        eprintln!("social account #{}: {msg}", account.id);
+        if account.auth_error.as_deref() != Some(msg.as_str()) {
+            mark_account_auth_error(conn, account.id, &msg)?;
+        }
+        if keep_broken {
+            account.access_token = None;
+            account.refresh_token = None;
+            account.auth_error = Some(msg);
+            accounts.push(account);
+        }
+    }
+    Ok(accounts)
+}
+
+/// Все аккаунты (для админки), токены расшифрованы.
+pub fn load_all_accounts(conn: &mut PgConnection) -> Result<Vec<SocialAccount>> {
+    let rows = social_accounts::table
+        .order((social_accounts::provider.asc(), social_accounts::id.asc()))
+        .select(SocialAccount::as_select())
+        .load(conn)?;
+    decrypt_accounts(conn, rows, true)
+}
+
+/// Активные аккаунты (опционально одного провайдера), по id. Аккаунты с нерасшифровываемыми
+/// токенами пропускаются.
+pub fn load_active_accounts(
+    conn: &mut PgConnection,
+    provider: Option<SocialProvider>,
+) -> Result<Vec<SocialAccount>> {
+    let mut q = social_accounts::table
+        .filter(social_accounts::is_active.eq(true))
+        .into_boxed();
+
+    if let Some(p) = provider {
+        q = q.filter(social_accounts::provider.eq(p.as_str()));
+    }
+
+    let rows = q
+        .order(social_accounts::id.asc())
+        .select(SocialAccount::as_select())
+        .load(conn)?;
+    decrypt_accounts(conn, rows, false)
+}
+
+/// Разовый перенос кредов из env в social_accounts: для провайдера, у которого в БД ещё нет
+/// ни одного аккаунта, создаётся запись из `*_account_from_env`. Дальше env не читается.
+pub fn bootstrap_accounts_from_env(conn: &mut PgConnection) -> Result<Vec<String>> {
+    let from_env = [
+        (SocialProvider::Telegram, telegram_account_from_env()),
+        (SocialProvider::Threads, threads_account_from_env()),
+        (SocialProvider::Instagram, instagram_account_from_env()),
+        (SocialProvider::Vk, vk_account_from_env()),
+    ];
+
+    let mut imported = Vec::new();
+    for (provider, account) in from_env {
+        let Ok(account) = account else {
+            continue;
+        };
+
+        let existing: i64 = social_accounts::table
+            .filter(social_accounts::provider.eq(provider.as_str()))
+            .count()
+            .get_result(conn)?;
+        if existing > 0 {
+            continue;
+        }
+
+        diesel::insert_into(social_accounts::table)
+            .values(&NewSocialAccount {
+                provider: account.provider,
+                account_name: account.account_name,
+                external_account_id: account.external_account_id,
+                access_token: seal_token(account.access_token)?,
+                refresh_token: seal_token(account.refresh_token)?,
+                token_expires_at: account.token_expires_at,
+                settings_json: account.settings_json,
+                is_active: true,
+            })
+            .execute(conn)?;
+        imported.push(provider.as_str().to_string());
+    }
+
+    Ok(imported)
+}
+
+/// Шифрует токены, лежащие в БД открытым текстом, и перешифровывает data key основным
+/// ключом после ротации. Без `SOCIAL_TOKEN_KEYS` ничего не делает. Возвращает число
+/// обновлённых аккаунтов.
+pub fn reencrypt_stored_tokens(conn: &mut PgConnection) -> Result<usize> {
+    let Some(keys) = keyring() else {
+        return Ok(0);
+    };
+
+    let rows: Vec<(i64, Option<String>, Option<String>)> = social_accounts::table
+        .select((
+            social_accounts::id,
+            social_accounts::access_token,
+            social_accounts::refresh_token,
+        ))
+        .load(conn)?;
+
+    let rewrap = |v: &Option<String>| -> Result<Option<String>> {
+        match v {
+            Some(s) if keys.needs_rewrap(s) => Ok(Some(keys.rewrap(s)?)),
+            other => Ok(other.clone()),
+        }
+    };
+
+    let mut updated = 0;
+    for (id, access, refresh) in rows {
+        let (new_access, new_refresh) = (rewrap(&access)?, rewrap(&refresh)?);
+        if new_access == access && new_refresh == refresh {
+            continue;
+        }
+        diesel::update(social_accounts::table.find(id))
+            .set((
+                social_accounts::access_token.eq(new_access),
+                social_accounts::refresh_token.eq(new_refresh),
+            ))
+            .execute(conn)?;
+        updated += 1;
+    }
+
+    Ok(updated)
+}
+
+/// Помечает аккаунт ошибкой авторизации. `true`, если это первая ошибка подряд
+/// (до неё аккаунт был в порядке) — по нему решаем, уведомлять ли админов.
+pub fn mark_account_auth_error(conn: &mut PgConnection, id: i64, error: &str) -> QueryResult<bool> {
+    let previous: Option<Option<String>> = social_accounts::table
+        .find(id)
+        .select(social_accounts::auth_error)
+        .first(conn)
+        .optional()?;
+
+    diesel::update(social_accounts::table.find(id))
+        .set((
+            social_accounts::auth_error.eq(error),
+            social_accounts::auth_error_at.eq(chrono::Utc::now()),
+        ))
+        .execute(conn)?;
+
+    Ok(matches!(previous, Some(None)))
+}
+
+/// Маска для показа токена: первые/последние 4 символа, короткие — целиком звёздочками.
+pub fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 12 {
+        return "****".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{head}…{tail}")
+}
+
+/// Заменяет токены аккаунта в произвольном тексте (ошибки reqwest содержат URL с токеном).
+pub fn redact_account_secrets(text: &str, account: &SocialAccount) -> String {
+    let mut out = text.to_string();
+    for secret in [&account.access_token, &account.refresh_token].into_iter().flatten() {
+        if !secret.is_empty() {
+            out = out.replace(secret.as_str(), &mask_token(secret));
+        }
+    }
+    redact_secrets(&out)
+}
+
+/// Маскирует токены, которые провайдеры и reqwest возвращают в тексте: `access_token=...`
+/// в URL, `"access_token": "..."` в JSON и `/bot<id>:<token>/` в URL Telegram Bot API.
+pub fn redact_secrets(text: &str) -> String {
+    let mut out = text.to_string();
+    for key in ["access_token", "refresh_token", "client_secret"] {
+        out = redact_after(&out, &format!("{key}="), |c| c == '&' || c == '"' || c == ')' || c.is_whitespace());
+        out = redact_after(&out, &format!("\"{key}\":\""), |c| c == '"');
+        out = redact_after(&out, &format!("\"{key}\": \""), |c| c == '"');
+    }
+    redact_after(&out, "/bot", |c| c == '/' || c == '"' || c.is_whitespace())
+}
+
+/// Значение после каждого `marker` (до `end`) заменяется маской.
+fn redact_after(text: &str, marker: &str, end: impl Fn(char) -> bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(marker) {
+        let (head, tail) = rest.split_at(pos + marker.len());
+        out.push_str(head);
+        let len = tail.find(&end).unwrap_or(tail.len());
+        let value = &tail[..len];
+        // /bot без токена (например, "/bots") не трогаем
+        if marker == "/bot" && !value.contains(':') {
+            out.push_str(value);
+        } else if !value.is_empty() && !value.starts_with("****") {
+            out.push_str(&mask_token(value));
+        } else {
+            out.push_str(value);
+        }
+        rest = &tail[len..];
+    }
+    out.push_str(rest);
+    out
+}
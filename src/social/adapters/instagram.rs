@@ -1,11 +1,19 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use reqwest::Client;
-use serde_json::Value;
+use serde_json::{json, Value};
 use tokio::time::{sleep, Duration};
 
 use crate::models::social::SocialAccount;
-use crate::social::adapters::{PublishPayload, PublishResult, SocialPublisher};
+use crate::social::errors::ProviderError;
+use crate::social::types::SocialProvider;
+use crate::social::adapters::{
+    api_base_url, parse_insights, parse_refresh_response, Capabilities, MediaItem, MediaKind, PostMetrics,
+    PublishCheckpoint, PublishPayload, PublishResult, Recovery, RefreshedToken, SocialPublisher,
+};
+
+/// Больше элементов карусель Instagram не принимает.
+const MAX_CAROUSEL_ITEMS: usize = 10;
 
 #[derive(Clone)]
 pub struct InstagramPublisher {
@@ -19,25 +27,28 @@ impl InstagramPublisher {
         }
     }
 
+    async fn container_status(&self, base: &str, creation_id: &str, access_token: &str) -> Result<Value> {
+        let status_url = format!("{base}/{creation_id}?fields=status_code,status&access_token={access_token}");
+
+        let resp = self.client.get(&status_url).send().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Instagram, Some(status.as_u16()), &body).into());
+        }
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
     async fn wait_until_container_ready(
         &self,
+        base: &str,
         creation_id: &str,
         access_token: &str,
     ) -> Result<()> {
-        let status_url = format!(
-            "https://graph.instagram.com/{creation_id}?fields=status_code,status&access_token={access_token}"
-        );
-
         for _ in 0..20 {
-            let resp = self.client.get(&status_url).send().await?;
-            let status = resp.status();
-            let body = resp.text().await?;
-
-            if !status.is_success() {
-                return Err(anyhow!("instagram status check HTTP error {status}: {body}"));
-            }
-
-            let json: Value = serde_json::from_str(&body)?;
+            let json = self.container_status(base, creation_id, access_token).await?;
             let status_code = json
                 .get("status_code")
                 .and_then(|v| v.as_str())
@@ -45,7 +56,11 @@ impl InstagramPublisher {
 
             match status_code {
                 "FINISHED" => return Ok(()),
-                "ERROR" => return Err(anyhow!("instagram container failed: {json}")),
+                "ERROR" => {
+                    return Err(
+                        ProviderError::permanent(format!("instagram container failed: {json}")).into()
+                    )
+                }
                 "IN_PROGRESS" | "EXPIRED" | "" => {
                     sleep(Duration::from_secs(3)).await;
                 }
@@ -57,40 +72,24 @@ impl InstagramPublisher {
 
         Err(anyhow!("instagram container was not ready in time"))
     }
-}
 
-#[async_trait]
-impl SocialPublisher for InstagramPublisher {
-    async fn publish(
+    /// POST `/{ig_user_id}/media`: контейнер поста, элемента карусели или самой карусели.
+    async fn create_container(
         &self,
-        account: &SocialAccount,
-        payload: PublishPayload,
-    ) -> Result<PublishResult> {
-        let access_token = account
-            .access_token
-            .as_ref()
-            .ok_or_else(|| anyhow!("instagram access_token is missing"))?;
-
-        let ig_user_id = account
-            .external_account_id
-            .as_ref()
-            .ok_or_else(|| anyhow!("instagram external_account_id / ig_user_id is missing"))?;
-
-        let image_url = payload
-            .image_url
-            .as_ref()
-            .ok_or_else(|| anyhow!("Instagram publishing currently requires image_url"))?;
+        base: &str,
+        ig_user_id: &str,
+        access_token: &str,
+        params: &[(&str, &str)],
+    ) -> Result<String> {
+        let create_url = format!("{base}/v24.0/{ig_user_id}/media");
 
-        let create_url = format!("https://graph.instagram.com/v24.0/{ig_user_id}/media");
+        let mut form = params.to_vec();
+        form.push(("access_token", access_token));
 
         let create_resp = self
             .client
             .post(&create_url)
-            .form(&[
-                ("image_url", image_url.as_str()),
-                ("caption", payload.text.as_str()),
-                ("access_token", access_token.as_str()),
-            ])
+            .form(&form)
             .send()
             .await?;
 
@@ -98,11 +97,12 @@ impl SocialPublisher for InstagramPublisher {
         let create_body = create_resp.text().await?;
 
         if !create_status.is_success() {
-            return Err(anyhow!(
-                "instagram create media HTTP error {}: {}",
-                create_status,
-                create_body
-            ));
+            return Err(ProviderError::from_response(
+                SocialProvider::Instagram,
+                Some(create_status.as_u16()),
+                &create_body,
+            )
+            .into());
         }
 
         let create_json: Value = serde_json::from_str(&create_body)?;
@@ -112,9 +112,123 @@ impl SocialPublisher for InstagramPublisher {
             .ok_or_else(|| anyhow!("instagram create media returned no id: {create_json}"))?
             .to_string();
 
-        self.wait_until_container_ready(&creation_id, access_token).await?;
+        Ok(creation_id)
+    }
+
+    /// Одиночное фото, рилс или карусель из готовых элементов.
+    async fn create_post_container(
+        &self,
+        base: &str,
+        ig_user_id: &str,
+        access_token: &str,
+        caption: &str,
+        media: &[MediaItem],
+    ) -> Result<String> {
+        match media {
+            [item] => {
+                let mut params = media_params(item, false);
+                params.push(("caption", caption));
+                self.create_container(base, ig_user_id, access_token, &params).await
+            }
+            items => {
+                let mut children = Vec::with_capacity(items.len());
+                for item in items {
+                    let id = self
+                        .create_container(base, ig_user_id, access_token, &media_params(item, true))
+                        .await?;
+                    // карусель собирается только из готовых элементов
+                    self.wait_until_container_ready(base, &id, access_token).await?;
+                    children.push(id);
+                }
+                let children = children.join(",");
+                self.create_container(
+                    base,
+                    ig_user_id,
+                    access_token,
+                    &[("media_type", "CAROUSEL"), ("children", &children), ("caption", caption)],
+                )
+                .await
+            }
+        }
+    }
+}
+
+fn media_params(item: &MediaItem, carousel_item: bool) -> Vec<(&'static str, &str)> {
+    let mut params = match (item.kind, carousel_item) {
+        (MediaKind::Image, _) => vec![("image_url", item.url.as_str())],
+        (MediaKind::Video, true) => vec![("media_type", "VIDEO"), ("video_url", item.url.as_str())],
+        // одиночное видео Instagram публикует только как рилс
+        (MediaKind::Video, false) => vec![("media_type", "REELS"), ("video_url", item.url.as_str())],
+    };
+    if carousel_item {
+        params.push(("is_carousel_item", "true"));
+    }
+    // alt-текст Instagram принимает только у фото
+    let alt = item.alt_text.as_deref().filter(|a| !a.trim().is_empty());
+    if let Some(alt) = alt.filter(|_| item.kind == MediaKind::Image) {
+        params.push(("alt_text", alt));
+    }
+    params
+}
+
+#[async_trait]
+impl SocialPublisher for InstagramPublisher {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_media: MAX_CAROUSEL_ITEMS,
+            video: true,
+            local_media: false,
+            requires_media: true,
+            // Graph API не умеет ни править подпись, ни удалять медиа
+            edit: false,
+            delete: false,
+        }
+    }
+
+    async fn publish(
+        &self,
+        account: &SocialAccount,
+        payload: PublishPayload,
+    ) -> Result<PublishResult> {
+        self.capabilities().check(SocialProvider::Instagram, &payload)?;
+
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("instagram access_token is missing"))?;
+
+        let ig_user_id = account
+            .external_account_id
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("instagram external_account_id / ig_user_id is missing"))?;
+        let base = api_base_url(account, SocialProvider::Instagram);
+
+        // контейнер прерванной попытки ещё не опубликован — берём его, а не создаём второй
+        let reusable = match payload.checkpoint.state_str("creation_id") {
+            Some(id) => match self.container_status(&base, id, access_token).await {
+                Ok(json) => matches!(json["status_code"].as_str(), Some("FINISHED" | "IN_PROGRESS"))
+                    .then(|| id.to_string()),
+                // контейнер истёк или удалён
+                Err(_) => None,
+            },
+            None => None,
+        };
+
+        let creation_id = match reusable {
+            Some(id) => id,
+            None => {
+                let id = self
+                    .create_post_container(&base, ig_user_id, access_token, &payload.text, &payload.media)
+                    .await?;
+                // до media_publish: после падения по id можно понять, вышел ли пост
+                payload.checkpoint.save(json!({ "creation_id": id })).await?;
+                id
+            }
+        };
+
+        self.wait_until_container_ready(&base, &creation_id, access_token).await?;
 
-        let publish_url = format!("https://graph.instagram.com/v24.0/{ig_user_id}/media_publish");
+        let publish_url = format!("{base}/v24.0/{ig_user_id}/media_publish");
 
         let publish_resp = self
             .client
@@ -130,11 +244,12 @@ impl SocialPublisher for InstagramPublisher {
         let publish_body = publish_resp.text().await?;
 
         if !publish_status.is_success() {
-            return Err(anyhow!(
-                "instagram publish media HTTP error {}: {}",
-                publish_status,
-                publish_body
-            ));
+            return Err(ProviderError::from_response(
+                SocialProvider::Instagram,
+                Some(publish_status.as_u16()),
+                &publish_body,
+            )
+            .into());
         }
 
         let publish_json: Value = serde_json::from_str(&publish_body)?;
@@ -153,6 +268,125 @@ impl SocialPublisher for InstagramPublisher {
         Ok(PublishResult {
             external_post_id: media_id,
             raw_response: Some(publish_json.to_string()),
+            published_state: None,
+        })
+    }
+
+    async fn test_connection(&self, account: &SocialAccount) -> Result<String> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("instagram access_token is missing"))?;
+
+        let resp = self
+            .client
+            .get(format!("{}/v24.0/me", api_base_url(account, SocialProvider::Instagram)))
+            .query(&[("fields", "id,username"), ("access_token", access_token.as_str())])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("instagram HTTP error {status}: {body}"));
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+        Ok(format!(
+            "@{} (id {})",
+            json["username"].as_str().unwrap_or("?"),
+            json["id"].as_str().unwrap_or("?")
+        ))
+    }
+
+    async fn refresh_token(&self, account: &SocialAccount) -> Result<Option<RefreshedToken>> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("instagram access_token is missing"))?;
+
+        let resp = self
+            .client
+            .get(format!("{}/refresh_access_token", api_base_url(account, SocialProvider::Instagram)))
+            .query(&[("grant_type", "ig_refresh_token"), ("access_token", access_token.as_str())])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("instagram token refresh HTTP error {status}: {body}"));
+        }
+
+        Ok(Some(parse_refresh_response(&body)?))
+    }
+
+    async fn recover(
+        &self,
+        account: &SocialAccount,
+        checkpoint: &PublishCheckpoint,
+    ) -> Result<Recovery> {
+        // без контейнера media_publish не вызывался
+        let Some(creation_id) = checkpoint.state_str("creation_id") else {
+            return Ok(Recovery::NotPublished);
+        };
+
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("instagram access_token is missing"))?;
+
+        let base = api_base_url(account, SocialProvider::Instagram);
+        let json = self.container_status(&base, creation_id, access_token).await?;
+        Ok(match json["status_code"].as_str() {
+            // id медиа контейнер не отдаёт
+            Some("PUBLISHED") => Recovery::Published(PublishResult {
+                external_post_id: None,
+                raw_response: Some(json.to_string()),
+                published_state: None,
+            }),
+            _ => Recovery::NotPublished,
         })
     }
-}
\ No newline at end of file
+
+    async fn fetch_metrics(
+        &self,
+        account: &SocialAccount,
+        external_post_id: &str,
+    ) -> Result<Option<PostMetrics>> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("instagram access_token is missing"))?;
+
+        let resp = self
+            .client
+            .get(format!(
+                "{}/v24.0/{external_post_id}/insights",
+                api_base_url(account, SocialProvider::Instagram)
+            ))
+            .query(&[
+                ("metric", "views,likes,comments,shares"),
+                ("access_token", access_token.as_str()),
+            ])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Instagram, Some(status.as_u16()), &body).into());
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+        let m = parse_insights(&json);
+        Ok(Some(PostMetrics {
+            views: m.get("views").copied(),
+            likes: m.get("likes").copied(),
+            reactions: None,
+            comments: m.get("comments").copied(),
+            shares: m.get("shares").copied(),
+            raw: Some(json),
+        }))
+    }
+}
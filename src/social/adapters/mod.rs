@@ -2,30 +2,358 @@ pub mod telegram;
 pub mod vk;
 pub mod instagram;
 pub mod threads;
+pub mod sandbox;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 
 use async_trait::async_trait;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 use crate::models::news::NewsPost;
 use crate::models::social::SocialAccount;
+use crate::social::errors::ProviderError;
+use crate::social::media::is_remote;
+use crate::social::types::SocialProvider;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    #[default]
+    Image,
+    Video,
+}
+
+/// Картинка или видео поста. `alt_text` — описание для незрячих, его передают провайдеры, которые это умеют.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaItem {
+    #[serde(rename = "type", default)]
+    pub kind: MediaKind,
+    /// `https://…` или `/images/…` — файл из каталога, который раздаёт сервер.
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+}
+
+impl MediaItem {
+    pub fn image(url: impl Into<String>) -> Self {
+        Self {
+            kind: MediaKind::Image,
+            url: url.into(),
+            alt_text: None,
+        }
+    }
+
+    /// Локальный файл провайдер сам скачать не может — его надо загружать байтами.
+    pub fn is_local(&self) -> bool {
+        !is_remote(&self.url)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct PublishPayload {
+    /// Готовый текст: ссылка и хештеги в нём уже стоят, если их поставил шаблон.
     pub text: String,
-    pub image_url: Option<String>,
+    /// В порядке показа.
+    pub media: Vec<MediaItem>,
+    /// Ссылка на источник — для превью ссылки там, где провайдер его строит.
+    pub link: Option<String>,
+    /// Теги без `#`.
+    pub hashtags: Vec<String>,
+    /// Заполняет worker; у разовых публикаций (тест, алерты) пустой.
+    pub checkpoint: PublishCheckpoint,
+}
+
+impl PublishPayload {
+    /// То, что ушло бы провайдеру: для dry-run и превью.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "text": self.text,
+            "media": self.media,
+            "link": self.link,
+            "hashtags": self.hashtags,
+            "idempotency_key": self.checkpoint.idempotency_key,
+        })
+    }
+}
+
+/// Что адаптер умеет публиковать. Проверяется до первого запроса к провайдеру.
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub max_media: usize,
+    pub video: bool,
+    /// Умеет загружать файлы из `images/` байтами.
+    pub local_media: bool,
+    /// Пост без медиа провайдер не принимает (Instagram).
+    pub requires_media: bool,
+    /// Можно поправить текст опубликованного поста.
+    pub edit: bool,
+    /// Можно удалить опубликованный пост.
+    pub delete: bool,
+}
+
+impl Capabilities {
+    pub fn check(&self, provider: SocialProvider, payload: &PublishPayload) -> Result<(), ProviderError> {
+        let unsupported = |what: String| {
+            ProviderError::permanent(format!("{} does not support {what}", provider.as_str()))
+        };
+
+        if self.requires_media && payload.media.is_empty() {
+            return Err(unsupported("posts without media".to_string()));
+        }
+        if payload.media.len() > self.max_media {
+            return Err(unsupported(format!(
+                "{} media items (max {})",
+                payload.media.len(),
+                self.max_media
+            )));
+        }
+        if let Some(item) = payload.media.iter().find(|m| m.kind == MediaKind::Video && !self.video) {
+            return Err(unsupported(format!("video ({})", item.url)));
+        }
+        if let Some(item) = payload.media.iter().find(|m| m.is_local() && !self.local_media) {
+            return Err(unsupported(format!("local media files ({}), a public URL is required", item.url)));
+        }
+        Ok(())
+    }
+}
+
+/// Куда адаптер сохраняет промежуточное состояние публикации.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    async fn save(&self, state: Value) -> Result<()>;
+}
+
+/// Данные job'а, переживающие падение процесса посреди публикации.
+#[derive(Clone, Default)]
+pub struct PublishCheckpoint {
+    /// Один и тот же ключ во всех попытках job'а.
+    pub idempotency_key: Option<String>,
+    /// Что сохранила прерванная попытка (например, id контейнера Meta).
+    pub state: Option<Value>,
+    /// Когда прерванная попытка отправила запрос провайдеру.
+    pub started_at: Option<DateTime<Utc>>,
+    store: Option<Arc<dyn CheckpointStore>>,
+}
+
+impl PublishCheckpoint {
+    pub fn new(
+        idempotency_key: String,
+        state: Option<Value>,
+        started_at: Option<DateTime<Utc>>,
+        store: Arc<dyn CheckpointStore>,
+    ) -> Self {
+        Self {
+            idempotency_key: Some(idempotency_key),
+            state,
+            started_at,
+            store: Some(store),
+        }
+    }
+
+    /// Сохраняет состояние до шага, после которого пост становится виден.
+    pub async fn save(&self, state: Value) -> Result<()> {
+        match &self.store {
+            Some(store) => store.save(state).await,
+            None => Ok(()),
+        }
+    }
+
+    pub fn state_str(&self, key: &str) -> Option<&str> {
+        self.state.as_ref()?.get(key)?.as_str()
+    }
+}
+
+impl fmt::Debug for PublishCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublishCheckpoint")
+            .field("idempotency_key", &self.idempotency_key)
+            .field("state", &self.state)
+            .field("started_at", &self.started_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Итог проверки попытки, прерванной после отправки запроса провайдеру.
+#[derive(Debug)]
+pub enum Recovery {
+    /// Пост уже вышел — повторять нельзя.
+    Published(PublishResult),
+    /// Пост точно не вышел либо повтор безопасен (провайдер дедуплицирует по ключу).
+    NotPublished,
+    /// Провайдер не даёт это выяснить.
+    Unknown,
 }
 
 #[derive(Debug, Clone)]
 pub struct PublishResult {
     pub external_post_id: Option<String>,
     pub raw_response: Option<String>,
+    /// Что понадобится для правки/удаления поста, кроме `external_post_id`
+    /// (id всех сообщений альбома Telegram и т.п.). Хранится в `social_post_jobs.published_state`.
+    pub published_state: Option<Value>,
+}
+
+/// Вышедший пост, который правят или удаляют.
+#[derive(Debug, Clone, Copy)]
+pub struct PublishedPost<'a> {
+    pub external_post_id: &'a str,
+    /// `PublishResult::published_state`; у постов, вышедших до появления колонки, `None`.
+    pub state: Option<&'a Value>,
+}
+
+/// Вовлечённость поста. `None` — провайдер эту метрику не отдаёт.
+#[derive(Debug, Clone, Default)]
+pub struct PostMetrics {
+    pub views: Option<i64>,
+    pub likes: Option<i64>,
+    pub reactions: Option<i64>,
+    pub comments: Option<i64>,
+    pub shares: Option<i64>,
+    pub raw: Option<Value>,
+}
+
+/// Новый токен после продления.
+#[derive(Debug, Clone)]
+pub struct RefreshedToken {
+    pub access_token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Адрес API провайдера без `/` на конце: `settings_json.api_base_url` аккаунта,
+/// иначе `{PROVIDER}_API_BASE_URL`, иначе боевой. На стендах и в тестах — mock-сервер.
+pub fn api_base_url(account: &SocialAccount, provider: SocialProvider) -> String {
+    let (env, default) = match provider {
+        SocialProvider::Telegram => ("TELEGRAM_API_BASE_URL", "https://api.telegram.org"),
+        SocialProvider::Vk => ("VK_API_BASE_URL", "https://api.vk.com"),
+        SocialProvider::Instagram => ("INSTAGRAM_API_BASE_URL", "https://graph.instagram.com"),
+        SocialProvider::Threads => ("THREADS_API_BASE_URL", "https://graph.threads.net"),
+        // песочница в сеть не ходит
+        SocialProvider::Sandbox => return String::new(),
+    };
+    let base = account
+        .settings_json
+        .get("api_base_url")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .filter(|u| !u.trim().is_empty())
+        .or_else(|| std::env::var(env).ok().filter(|u| !u.trim().is_empty()))
+        .unwrap_or_else(|| default.to_string());
+    base.trim().trim_end_matches('/').to_string()
+}
+
+/// `settings_json.dry_run` или провайдер-песочница: worker пишет готовый payload
+/// в `social_post_attempts` вместо запроса к провайдеру.
+pub fn is_dry_run(account: &SocialAccount) -> bool {
+    account.provider == SocialProvider::Sandbox.as_str()
+        || account.settings_json.get("dry_run").and_then(Value::as_bool) == Some(true)
+}
+
+pub fn validate_dry_run(settings: &Value) -> Result<(), String> {
+    match settings.get("dry_run") {
+        None | Some(Value::Bool(_)) => Ok(()),
+        Some(_) => Err("dry_run must be a boolean".to_string()),
+    }
+}
+
+/// Проверка `settings_json.api_base_url` при сохранении аккаунта: туда уйдут токены.
+pub fn validate_api_base_url(settings: &Value) -> Result<(), String> {
+    let Some(base) = settings.get("api_base_url") else {
+        return Ok(());
+    };
+    let base = base.as_str().ok_or("api_base_url must be a string")?;
+    let url = url::Url::parse(base).map_err(|e| format!("api_base_url: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+        return Err("api_base_url must be an http(s) URL".to_string());
+    }
+    Ok(())
+}
+
+/// Ответ `refresh_access_token` Instagram/Threads: `{access_token, token_type, expires_in}`.
+pub(crate) fn parse_refresh_response(body: &str) -> Result<RefreshedToken> {
+    let json: Value = serde_json::from_str(body)?;
+    let access_token = json["access_token"]
+        .as_str()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| anyhow!("refresh response has no access_token"))?;
+
+    Ok(RefreshedToken {
+        access_token: access_token.to_string(),
+        expires_at: json["expires_in"].as_i64().map(|s| Utc::now() + Duration::seconds(s)),
+    })
+}
+
+/// Ответ `/{media_id}/insights` Instagram/Threads: `{data: [{name, values: [{value}]} | {name, total_value: {value}}]}`.
+pub(crate) fn parse_insights(json: &Value) -> HashMap<String, i64> {
+    json["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| {
+            let value = m["total_value"]["value"]
+                .as_i64()
+                .or_else(|| m["values"][0]["value"].as_i64())?;
+            Some((m["name"].as_str()?.to_string(), value))
+        })
+        .collect()
 }
 
 #[async_trait]
 pub trait SocialPublisher: Send + Sync {
+    fn capabilities(&self) -> Capabilities;
+
     async fn publish(
         &self,
         account: &SocialAccount,
         payload: PublishPayload,
     ) -> Result<PublishResult>;
+
+    /// Проверка кредов без публикации: read-only запрос к API провайдера.
+    /// Возвращает описание того, куда будут уходить посты.
+    async fn test_connection(&self, account: &SocialAccount) -> Result<String>;
+
+    /// Продление долгоживущего токена до истечения.
+    /// `None` — у провайдера токены не истекают (Telegram, VK).
+    async fn refresh_token(&self, _account: &SocialAccount) -> Result<Option<RefreshedToken>> {
+        Ok(None)
+    }
+
+    /// Вызывается перед повтором, если прошлая попытка оборвалась после отправки запроса.
+    async fn recover(
+        &self,
+        _account: &SocialAccount,
+        _checkpoint: &PublishCheckpoint,
+    ) -> Result<Recovery> {
+        Ok(Recovery::Unknown)
+    }
+
+    /// Замена текста опубликованного поста; медиа остаются прежними.
+    /// Вызывается только при `capabilities().edit`.
+    async fn edit(
+        &self,
+        _account: &SocialAccount,
+        _post: PublishedPost<'_>,
+        _payload: &PublishPayload,
+    ) -> Result<()> {
+        Err(ProviderError::permanent("editing published posts is not supported").into())
+    }
+
+    /// Удаление опубликованного поста. Вызывается только при `capabilities().delete`.
+    async fn delete(&self, _account: &SocialAccount, _post: PublishedPost<'_>) -> Result<()> {
+        Err(ProviderError::permanent("deleting published posts is not supported").into())
+    }
+
+    /// Просмотры/лайки/комментарии опубликованного поста по `external_post_id`.
+    /// `None` — провайдер (или этот аккаунт) статистику не отдаёт.
+    async fn fetch_metrics(
+        &self,
+        _account: &SocialAccount,
+        _external_post_id: &str,
+    ) -> Result<Option<PostMetrics>> {
+        Ok(None)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::models::social::SocialAccount;
+use crate::social::adapters::{Capabilities, PublishPayload, PublishResult, PublishedPost, SocialPublisher};
+use crate::social::types::SocialProvider;
+
+/// Провайдер-песочница: принимает всё, что умеет хоть один настоящий, и ничего не отправляет.
+/// Worker до `publish` не доходит (аккаунт всегда dry-run); сюда попадают тестовая публикация,
+/// правки и алерты.
+#[derive(Clone, Default)]
+pub struct SandboxPublisher;
+
+#[async_trait]
+impl SocialPublisher for SandboxPublisher {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_media: 10,
+            video: true,
+            local_media: true,
+            requires_media: false,
+            edit: true,
+            delete: true,
+        }
+    }
+
+    async fn publish(
+        &self,
+        _account: &SocialAccount,
+        payload: PublishPayload,
+    ) -> Result<PublishResult> {
+        self.capabilities().check(SocialProvider::Sandbox, &payload)?;
+
+        Ok(PublishResult {
+            external_post_id: Some(format!("sandbox-{}", Uuid::new_v4().simple())),
+            raw_response: Some(payload.to_json().to_string()),
+            published_state: None,
+        })
+    }
+
+    async fn test_connection(&self, account: &SocialAccount) -> Result<String> {
+        Ok(format!("sandbox «{}»: posts are rendered but never sent", account.account_name))
+    }
+
+    async fn edit(
+        &self,
+        _account: &SocialAccount,
+        _post: PublishedPost<'_>,
+        _payload: &PublishPayload,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn delete(&self, _account: &SocialAccount, _post: PublishedPost<'_>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,11 +1,21 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
 use reqwest::Client;
 use serde_json::{json, Value};
 
 use crate::models::news::NewsPost;
 use crate::models::social::SocialAccount;
-use crate::social::adapters::{PublishPayload, PublishResult, SocialPublisher};
+use crate::social::errors::ProviderError;
+use crate::social::media::load_image;
+use crate::social::types::SocialProvider;
+use crate::social::adapters::{
+    api_base_url, Capabilities, MediaKind, PostMetrics, PublishPayload, PublishResult, PublishedPost,
+    SocialPublisher,
+};
+
+/// Больше элементов в альбоме Telegram не принимает.
+const MAX_MEDIA_GROUP: usize = 10;
 
 #[derive(Clone)]
 pub struct TelegramPublisher {
@@ -18,89 +28,331 @@ impl TelegramPublisher {
             client: Client::new(),
         }
     }
+
+    fn credentials(account: &SocialAccount) -> Result<(&str, &str)> {
+        let bot_token = account
+            .access_token
+            .as_deref()
+            .ok_or_else(|| ProviderError::auth("telegram access_token is missing"))?;
+        let chat_id = account
+            .external_account_id
+            .as_deref()
+            .ok_or_else(|| ProviderError::auth("telegram external_account_id/chat_id is missing"))?;
+        Ok((bot_token, chat_id))
+    }
+
+    fn endpoint(account: &SocialAccount, bot_token: &str, method: &str) -> String {
+        format!("{}/bot{bot_token}/{method}", api_base_url(account, SocialProvider::Telegram))
+    }
+
+    async fn call(&self, account: &SocialAccount, bot_token: &str, method: &str, body: Value) -> Result<Value> {
+        let resp: Value = self
+            .client
+            .post(Self::endpoint(account, bot_token, method))
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if !resp.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+            return Err(ProviderError::from_response(SocialProvider::Telegram, None, &resp.to_string()).into());
+        }
+        Ok(resp)
+    }
 }
 
 #[async_trait]
 impl SocialPublisher for TelegramPublisher {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_media: MAX_MEDIA_GROUP,
+            video: true,
+            local_media: true,
+            requires_media: false,
+            edit: true,
+            delete: true,
+        }
+    }
+
     async fn publish(
         &self,
         account: &SocialAccount,
         payload: PublishPayload,
     ) -> Result<PublishResult> {
+        self.capabilities().check(SocialProvider::Telegram, &payload)?;
+
         let bot_token = account
             .access_token
             .as_ref()
-            .ok_or_else(|| anyhow!("telegram access_token is missing"))?;
+            .ok_or_else(|| ProviderError::auth("telegram access_token is missing"))?;
 
         let chat_id = account
             .external_account_id
             .as_ref()
-            .ok_or_else(|| anyhow!("telegram external_account_id/chat_id is missing"))?;
+            .ok_or_else(|| ProviderError::auth("telegram external_account_id/chat_id is missing"))?;
 
-        if let Some(image_url) = payload.image_url {
-            let url = format!("https://api.telegram.org/bot{bot_token}/sendPhoto");
+        // локальные файлы из images/ уходят multipart'ом: (имя части, ссылка)
+        let mut files: Vec<(String, String)> = Vec::new();
 
-            let resp: Value = self
-                .client
-                .post(url)
-                .json(&json!({
+        // вид поста определяет, чем потом править текст: у медиа это подпись
+        let media = match payload.media.as_slice() {
+            [] => "text",
+            [_] => "single",
+            _ => "album",
+        };
+
+        let (method, body) = match payload.media.as_slice() {
+            [] => {
+                let mut body = json!({
+                    "chat_id": chat_id,
+                    "text": payload.text,
+                    "parse_mode": "HTML"
+                });
+                // превью источника, а не первой попавшейся ссылки из текста
+                if let Some(link) = &payload.link {
+                    body["link_preview_options"] = json!({ "url": link });
+                }
+                ("sendMessage", body)
+            }
+            [item] => {
+                let (method, field) = match item.kind {
+                    MediaKind::Image => ("sendPhoto", "photo"),
+                    MediaKind::Video => ("sendVideo", "video"),
+                };
+                let mut body = json!({
                     "chat_id": chat_id,
-                    "photo": image_url,
                     "caption": payload.text,
                     "parse_mode": "HTML"
-                }))
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            let ok = resp.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
-            if !ok {
-                return Err(anyhow!("telegram sendPhoto failed: {}", resp));
+                });
+                if item.is_local() {
+                    files.push((field.to_string(), item.url.clone()));
+                } else {
+                    body[field] = json!(item.url);
+                }
+                (method, body)
+            }
+            items => {
+                // подпись альбома — подпись первого элемента
+                let media: Vec<Value> = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let mut m = json!({
+                            "type": match item.kind {
+                                MediaKind::Image => "photo",
+                                MediaKind::Video => "video",
+                            },
+                            "media": item.url
+                        });
+                        if item.is_local() {
+                            let name = format!("file{i}");
+                            m["media"] = json!(format!("attach://{name}"));
+                            files.push((name, item.url.clone()));
+                        }
+                        if i == 0 {
+                            m["caption"] = json!(payload.text);
+                            m["parse_mode"] = json!("HTML");
+                        }
+                        m
+                    })
+                    .collect();
+                ("sendMediaGroup", json!({ "chat_id": chat_id, "media": media }))
+            }
+        };
+
+        let request = self
+            .client
+            .post(Self::endpoint(account, bot_token, method));
+        let request = if files.is_empty() {
+            request.json(&body)
+        } else {
+            let mut form = Form::new();
+            for (key, value) in body.as_object().into_iter().flatten() {
+                // вложенные поля (media) multipart принимает JSON-строкой
+                let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+                form = form.text(key.clone(), value);
             }
+            for (name, url) in files {
+                let file = load_image(&self.client, &url).await?;
+                let part = Part::bytes(file.bytes)
+                    .file_name(file.file_name)
+                    .mime_str(file.mime)?;
+                form = form.part(name, part);
+            }
+            request.multipart(form)
+        };
+
+        let resp: Value = request
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        let ok = resp.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
+        if !ok {
+            return Err(ProviderError::from_response(SocialProvider::Telegram, None, &resp.to_string()).into());
+        }
+
+        // sendMediaGroup возвращает массив сообщений альбома
+        let result = &resp["result"];
+        let message_ids: Vec<i64> = match result.as_array() {
+            Some(messages) => messages.iter().filter_map(|m| m["message_id"].as_i64()).collect(),
+            None => result["message_id"].as_i64().into_iter().collect(),
+        };
+
+        Ok(PublishResult {
+            external_post_id: message_ids.first().map(|id| id.to_string()),
+            raw_response: Some(resp.to_string()),
+            published_state: Some(json!({ "media": media, "message_ids": message_ids })),
+        })
+    }
+
+    async fn test_connection(&self, account: &SocialAccount) -> Result<String> {
+        let bot_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("telegram access_token is missing"))?;
 
-            let message_id = resp
-                .get("result")
-                .and_then(|r| r.get("message_id"))
-                .and_then(|v| v.as_i64())
-                .map(|v| v.to_string());
+        let chat_id = account
+            .external_account_id
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("telegram external_account_id/chat_id is missing"))?;
+
+        let me: Value = self
+            .client
+            .get(Self::endpoint(account, bot_token, "getMe"))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if !me.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+            return Err(anyhow!("telegram getMe failed: {}", me));
+        }
 
-            Ok(PublishResult {
-                external_post_id: message_id,
-                raw_response: Some(resp.to_string()),
-            })
+        let chat: Value = self
+            .client
+            .post(Self::endpoint(account, bot_token, "getChat"))
+            .json(&json!({ "chat_id": chat_id }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if !chat.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+            return Err(anyhow!("telegram getChat failed: {}", chat));
+        }
+
+        let bot = me["result"]["username"].as_str().unwrap_or("?");
+        let title = chat["result"]["title"]
+            .as_str()
+            .or_else(|| chat["result"]["username"].as_str())
+            .unwrap_or("?");
+
+        Ok(format!("bot @{bot} -> chat {title}"))
+    }
+
+    /// Текст поста с медиа — это подпись, её правит `editMessageCaption`. Вид поста берётся
+    /// из сохранённого при публикации состояния: картинку у новости могли с тех пор сменить.
+    async fn edit(
+        &self,
+        account: &SocialAccount,
+        post: PublishedPost<'_>,
+        payload: &PublishPayload,
+    ) -> Result<()> {
+        let (bot_token, chat_id) = Self::credentials(account)?;
+        let message_id: i64 = post.external_post_id.parse()?;
+
+        let text_only = match post.state.and_then(|s| s["media"].as_str()) {
+            Some(media) => media == "text",
+            // пост вышел до того, как вид стали сохранять
+            None => payload.media.is_empty(),
+        };
+        let (method, mut body) = if text_only {
+            ("editMessageText", json!({ "text": payload.text }))
         } else {
-            let url = format!("https://api.telegram.org/bot{bot_token}/sendMessage");
+            ("editMessageCaption", json!({ "caption": payload.text }))
+        };
+        body["chat_id"] = json!(chat_id);
+        body["message_id"] = json!(message_id);
+        body["parse_mode"] = json!("HTML");
 
-            let resp: Value = self
-                .client
-                .post(url)
-                .json(&json!({
-                    "chat_id": chat_id,
-                    "text": payload.text,
-                    "parse_mode": "HTML",
-                    "disable_web_page_preview": false
-                }))
-                .send()
-                .await?
-                .json()
-                .await?;
-
-            let ok = resp.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
-            if !ok {
-                return Err(anyhow!("telegram sendMessage failed: {}", resp));
-            }
+        match self.call(account, bot_token, method, body).await {
+            Ok(_) => Ok(()),
+            // текст совпал с опубликованным — править нечего
+            Err(err) if err.to_string().contains("message is not modified") => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
 
-            let message_id = resp
-                .get("result")
-                .and_then(|r| r.get("message_id"))
-                .and_then(|v| v.as_i64())
-                .map(|v| v.to_string());
+    /// Удаляет все сообщения поста: у альбома каждое фото — отдельное сообщение.
+    async fn delete(&self, account: &SocialAccount, post: PublishedPost<'_>) -> Result<()> {
+        let (bot_token, chat_id) = Self::credentials(account)?;
+        let message_id: i64 = post.external_post_id.parse()?;
 
-            Ok(PublishResult {
-                external_post_id: message_id,
-                raw_response: Some(resp.to_string()),
-            })
+        let mut message_ids: Vec<i64> = post
+            .state
+            .and_then(|s| s["message_ids"].as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_i64)
+            .collect();
+        if !message_ids.contains(&message_id) {
+            message_ids.push(message_id);
         }
+
+        let (method, body) = match message_ids.as_slice() {
+            [id] => ("deleteMessage", json!({ "chat_id": chat_id, "message_id": id })),
+            ids => ("deleteMessages", json!({ "chat_id": chat_id, "message_ids": ids })),
+        };
+        self.call(account, bot_token, method, body).await?;
+        Ok(())
     }
-}
\ No newline at end of file
+
+    /// Bot API просмотры не отдаёт; у публичного канала они есть на странице
+    /// `t.me/{channel}/{id}?embed=1`. Для приватных чатов (числовой chat_id) — `None`.
+    async fn fetch_metrics(
+        &self,
+        account: &SocialAccount,
+        external_post_id: &str,
+    ) -> Result<Option<PostMetrics>> {
+        let Some(channel) = account
+            .external_account_id
+            .as_deref()
+            .and_then(|id| id.strip_prefix('@'))
+        else {
+            return Ok(None);
+        };
+
+        let resp = self
+            .client
+            .get(format!("https://t.me/{channel}/{external_post_id}"))
+            .query(&[("embed", "1")])
+            .send()
+            .await?;
+        let status = resp.status();
+        let html = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Telegram, Some(status.as_u16()), &html).into());
+        }
+
+        Ok(Some(PostMetrics {
+            views: parse_embed_views(&html),
+            ..Default::default()
+        }))
+    }
+}
+
+/// `<span class="tgme_widget_message_views">1.2K</span>` → 1200.
+fn parse_embed_views(html: &str) -> Option<i64> {
+    let marker = "tgme_widget_message_views\">";
+    let start = html.find(marker)? + marker.len();
+    let text = html[start..].split('<').next()?.trim();
+
+    let (number, multiplier) = match text.chars().last()? {
+        'K' => (&text[..text.len() - 1], 1_000.0),
+        'M' => (&text[..text.len() - 1], 1_000_000.0),
+        _ => (text, 1.0),
+    };
+    let value: f64 = number.parse().ok()?;
+    Some((value * multiplier).round() as i64)
+}
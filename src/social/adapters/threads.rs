@@ -1,10 +1,19 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use reqwest::Client;
-use serde_json::Value;
+use serde_json::{json, Value};
+use tokio::time::{sleep, Duration};
 
 use crate::models::social::SocialAccount;
-use crate::social::adapters::{PublishPayload, PublishResult, SocialPublisher};
+use crate::social::errors::ProviderError;
+use crate::social::types::SocialProvider;
+use crate::social::adapters::{
+    api_base_url, parse_insights, parse_refresh_response, Capabilities, MediaItem, MediaKind, PostMetrics,
+    PublishCheckpoint, PublishPayload, PublishResult, PublishedPost, Recovery, RefreshedToken, SocialPublisher,
+};
+
+/// Больше элементов карусель Threads не принимает.
+const MAX_CAROUSEL_ITEMS: usize = 20;
 
 #[derive(Clone)]
 pub struct ThreadsPublisher {
@@ -17,39 +26,257 @@ impl ThreadsPublisher {
             client: Client::new(),
         }
     }
+
+    /// POST `/{user_id}/threads`: создаёт контейнер (текст, картинка, элемент или карусель).
+    async fn create_container(
+        &self,
+        base: &str,
+        threads_user_id: &str,
+        access_token: &str,
+        params: &[(&str, &str)],
+    ) -> Result<String> {
+        let url = format!("{base}/v1.0/{threads_user_id}/threads");
+
+        let mut form = params.to_vec();
+        form.push(("access_token", access_token));
+
+        let response = self
+            .client
+            .post(&url)
+            .form(&form)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Threads, Some(status.as_u16()), &body).into());
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+        json.get("id")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+            .ok_or_else(|| anyhow!("threads create container returned no id: {json}"))
+    }
+
+    /// Контейнер под пост: TEXT, IMAGE/VIDEO или CAROUSEL из готовых элементов.
+    /// Медиа Threads скачивает сам, поэтому url должен быть публичным.
+    async fn create_post_container(
+        &self,
+        base: &str,
+        threads_user_id: &str,
+        access_token: &str,
+        payload: &PublishPayload,
+    ) -> Result<String> {
+        let text = payload.text.as_str();
+        // тема поста: Threads принимает один тег
+        let topic = payload.hashtags.first().map(String::as_str);
+
+        match payload.media.as_slice() {
+            [] => {
+                let mut params = vec![("media_type", "TEXT"), ("text", text)];
+                // превью ссылки бывает только у текстовых постов
+                if let Some(link) = payload.link.as_deref() {
+                    params.push(("link_attachment", link));
+                }
+                params.extend(topic.map(|t| ("topic_tag", t)));
+                self.create_container(base, threads_user_id, access_token, &params).await
+            }
+            [item] => {
+                let mut params = media_params(item);
+                params.push(("text", text));
+                params.extend(topic.map(|t| ("topic_tag", t)));
+                let id = self.create_container(base, threads_user_id, access_token, &params).await?;
+                self.wait_until_container_ready(base, &id, access_token).await?;
+                Ok(id)
+            }
+            items => {
+                let mut children = Vec::with_capacity(items.len());
+                for item in items {
+                    let mut params = media_params(item);
+                    params.push(("is_carousel_item", "true"));
+                    let id = self.create_container(base, threads_user_id, access_token, &params).await?;
+                    // карусель принимает только готовые элементы
+                    self.wait_until_container_ready(base, &id, access_token).await?;
+                    children.push(id);
+                }
+                let children = children.join(",");
+                let mut params = vec![("media_type", "CAROUSEL"), ("children", &children), ("text", text)];
+                params.extend(topic.map(|t| ("topic_tag", t)));
+                let id = self.create_container(base, threads_user_id, access_token, &params).await?;
+                self.wait_until_container_ready(base, &id, access_token).await?;
+                Ok(id)
+            }
+        }
+    }
+
+    async fn wait_until_container_ready(&self, base: &str, creation_id: &str, access_token: &str) -> Result<()> {
+        for _ in 0..20 {
+            let json = self.container_status(base, creation_id, access_token).await?;
+
+            match json["status"].as_str().unwrap_or("") {
+                "FINISHED" => return Ok(()),
+                "ERROR" => {
+                    return Err(ProviderError::permanent(format!(
+                        "threads container failed: {}",
+                        json["error_message"].as_str().unwrap_or("unknown error")
+                    ))
+                    .into())
+                }
+                // истёкший контейнер не оживёт; повтор job'а создаст новый
+                "EXPIRED" => return Err(anyhow!("threads container expired: {json}")),
+                "IN_PROGRESS" | "" => sleep(Duration::from_secs(3)).await,
+                other => return Err(anyhow!("unknown threads container status: {other}; body={json}")),
+            }
+        }
+
+        Err(anyhow!("threads container was not ready in time"))
+    }
+
+    async fn container_status(&self, base: &str, creation_id: &str, access_token: &str) -> Result<Value> {
+        let resp = self
+            .client
+            .get(format!("{base}/v1.0/{creation_id}"))
+            .query(&[("fields", "status,error_message"), ("access_token", access_token)])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Threads, Some(status.as_u16()), &body).into());
+        }
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Id поста, вышедшего из контейнера: контейнер его не отдаёт, поэтому ищем среди постов
+    /// аккаунта, вышедших после начала прерванной попытки, — по тексту, если он сохранён,
+    /// иначе только если такой пост один.
+    async fn find_published(
+        &self,
+        base: &str,
+        threads_user_id: &str,
+        access_token: &str,
+        checkpoint: &PublishCheckpoint,
+    ) -> Result<Option<String>> {
+        let mut query = vec![
+            ("fields", "id,text,timestamp".to_string()),
+            ("limit", "25".to_string()),
+            ("access_token", access_token.to_string()),
+        ];
+        if let Some(started_at) = checkpoint.started_at {
+            // запас на расхождение часов
+            query.push(("since", (started_at.timestamp() - 60).to_string()));
+        }
+
+        let resp = self
+            .client
+            .get(format!("{base}/v1.0/{threads_user_id}/threads"))
+            .query(&query)
+            .send()
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Threads, Some(status.as_u16()), &body).into());
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+        let posts = json["data"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let found = match checkpoint.state_str("text") {
+            Some(text) => posts
+                .iter()
+                .find(|p| p["text"].as_str().map(str::trim) == Some(text.trim())),
+            None if posts.len() == 1 => posts.first(),
+            None => None,
+        };
+        Ok(found.and_then(|p| p["id"].as_str()).map(str::to_string))
+    }
+}
+
+fn media_params(item: &MediaItem) -> Vec<(&'static str, &str)> {
+    let mut params = match item.kind {
+        MediaKind::Image => vec![("media_type", "IMAGE"), ("image_url", item.url.as_str())],
+        MediaKind::Video => vec![("media_type", "VIDEO"), ("video_url", item.url.as_str())],
+    };
+    if let Some(alt) = item.alt_text.as_deref().filter(|a| !a.trim().is_empty()) {
+        params.push(("alt_text", alt));
+    }
+    params
 }
 
 #[async_trait]
 impl SocialPublisher for ThreadsPublisher {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            max_media: MAX_CAROUSEL_ITEMS,
+            video: true,
+            local_media: false,
+            requires_media: false,
+            edit: false,
+            delete: true,
+        }
+    }
+
     async fn publish(
         &self,
         account: &SocialAccount,
         payload: PublishPayload,
     ) -> Result<PublishResult> {
+        self.capabilities().check(SocialProvider::Threads, &payload)?;
+
         let access_token = account
             .access_token
             .as_ref()
-            .ok_or_else(|| anyhow!("threads access_token is missing"))?;
+            .ok_or_else(|| ProviderError::auth("threads access_token is missing"))?;
 
         let threads_user_id = account
             .external_account_id
             .as_ref()
-            .ok_or_else(|| anyhow!("threads external_account_id/user_id is missing"))?;
+            .ok_or_else(|| ProviderError::auth("threads external_account_id/user_id is missing"))?;
+        let base = api_base_url(account, SocialProvider::Threads);
 
-        if payload.image_url.is_some() {
-            return Err(anyhow!("Threads image posting is not implemented yet"));
-        }
+        // контейнер прерванной попытки, если его ещё можно опубликовать
+        let reusable = match payload.checkpoint.state_str("creation_id") {
+            Some(id) => match self.container_status(&base, id, access_token).await {
+                Ok(json) => matches!(json["status"].as_str(), Some("FINISHED" | "IN_PROGRESS"))
+                    .then(|| id.to_string()),
+                Err(_) => None,
+            },
+            None => None,
+        };
 
-        let url = format!("https://graph.threads.net/v1.0/{threads_user_id}/threads");
+        let creation_id = match reusable {
+            Some(id) => {
+                self.wait_until_container_ready(&base, &id, access_token).await?;
+                id
+            }
+            None => {
+                let id = self
+                    .create_post_container(&base, threads_user_id, access_token, &payload)
+                    .await?;
+                // до threads_publish: после падения по id можно понять, вышел ли пост,
+                // а по тексту — найти его среди постов аккаунта
+                payload
+                    .checkpoint
+                    .save(json!({ "creation_id": id, "text": payload.text }))
+                    .await?;
+                id
+            }
+        };
+
+        let url = format!("{base}/v1.0/{threads_user_id}/threads_publish");
 
         let response = self
             .client
             .post(&url)
             .form(&[
-                ("media_type", "TEXT"),
-                ("text", payload.text.as_str()),
+                ("creation_id", creation_id.as_str()),
                 ("access_token", access_token.as_str()),
-                ("auto_publish_text", "true"),
             ])
             .send()
             .await?;
@@ -58,7 +285,7 @@ impl SocialPublisher for ThreadsPublisher {
         let body = response.text().await?;
 
         if !status.is_success() {
-            return Err(anyhow!("threads HTTP error {status}: {body}"));
+            return Err(ProviderError::from_response(SocialProvider::Threads, Some(status.as_u16()), &body).into());
         }
 
         let resp: Value = serde_json::from_str(&body)?;
@@ -75,6 +302,160 @@ impl SocialPublisher for ThreadsPublisher {
         Ok(PublishResult {
             external_post_id: id,
             raw_response: Some(resp.to_string()),
+            published_state: None,
         })
     }
-}
\ No newline at end of file
+
+    async fn test_connection(&self, account: &SocialAccount) -> Result<String> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("threads access_token is missing"))?;
+
+        let resp = self
+            .client
+            .get(format!("{}/v1.0/me", api_base_url(account, SocialProvider::Threads)))
+            .query(&[("fields", "id,username"), ("access_token", access_token.as_str())])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("threads HTTP error {status}: {body}"));
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+        Ok(format!(
+            "@{} (id {})",
+            json["username"].as_str().unwrap_or("?"),
+            json["id"].as_str().unwrap_or("?")
+        ))
+    }
+
+    async fn refresh_token(&self, account: &SocialAccount) -> Result<Option<RefreshedToken>> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("threads access_token is missing"))?;
+
+        let resp = self
+            .client
+            .get(format!("{}/refresh_access_token", api_base_url(account, SocialProvider::Threads)))
+            .query(&[("grant_type", "th_refresh_token"), ("access_token", access_token.as_str())])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("threads token refresh HTTP error {status}: {body}"));
+        }
+
+        Ok(Some(parse_refresh_response(&body)?))
+    }
+
+    async fn recover(
+        &self,
+        account: &SocialAccount,
+        checkpoint: &PublishCheckpoint,
+    ) -> Result<Recovery> {
+        // без контейнера threads_publish не вызывался
+        let Some(creation_id) = checkpoint.state_str("creation_id") else {
+            return Ok(Recovery::NotPublished);
+        };
+
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("threads access_token is missing"))?;
+
+        let base = api_base_url(account, SocialProvider::Threads);
+        let json = self.container_status(&base, creation_id, access_token).await?;
+        if json["status"].as_str() != Some("PUBLISHED") {
+            return Ok(Recovery::NotPublished);
+        }
+
+        // не нашли — пост всё равно вышел, повторять нельзя; без id его не удалить и не снять метрики
+        let external_post_id = match account.external_account_id.as_deref() {
+            Some(user_id) => self.find_published(&base, user_id, access_token, checkpoint).await?,
+            None => None,
+        };
+        Ok(Recovery::Published(PublishResult {
+            external_post_id,
+            raw_response: Some(json.to_string()),
+            published_state: None,
+        }))
+    }
+
+    async fn delete(&self, account: &SocialAccount, post: PublishedPost<'_>) -> Result<()> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("threads access_token is missing"))?;
+
+        let resp = self
+            .client
+            .delete(format!(
+                "{}/v1.0/{}",
+                api_base_url(account, SocialProvider::Threads),
+                post.external_post_id
+            ))
+            .query(&[("access_token", access_token.as_str())])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Threads, Some(status.as_u16()), &body).into());
+        }
+        Ok(())
+    }
+
+    async fn fetch_metrics(
+        &self,
+        account: &SocialAccount,
+        external_post_id: &str,
+    ) -> Result<Option<PostMetrics>> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("threads access_token is missing"))?;
+
+        let resp = self
+            .client
+            .get(format!(
+                "{}/v1.0/{external_post_id}/insights",
+                api_base_url(account, SocialProvider::Threads)
+            ))
+            .query(&[
+                ("metric", "views,likes,replies,reposts,quotes"),
+                ("access_token", access_token.as_str()),
+            ])
+            .send()
+            .await?;
+
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Threads, Some(status.as_u16()), &body).into());
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+        let m = parse_insights(&json);
+        // репост и цитата — оба способа поделиться постом
+        let shares = match (m.get("reposts"), m.get("quotes")) {
+            (None, None) => None,
+            (reposts, quotes) => Some(reposts.copied().unwrap_or(0) + quotes.copied().unwrap_or(0)),
+        };
+        Ok(Some(PostMetrics {
+            views: m.get("views").copied(),
+            likes: m.get("likes").copied(),
+            reactions: None,
+            comments: m.get("replies").copied(),
+            shares,
+            raw: Some(json),
+        }))
+    }
+}
@@ -1,11 +1,25 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
 use reqwest::Client;
-use serde_json::Value;
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::{json, Value};
 use std::env;
 
 use crate::models::social::SocialAccount;
-use crate::social::adapters::{PublishPayload, PublishResult, SocialPublisher};
+use crate::social::errors::ProviderError;
+use crate::social::media::load_image;
+use crate::social::types::SocialProvider;
+use crate::social::adapters::{
+    api_base_url, Capabilities, PostMetrics, PublishCheckpoint, PublishPayload, PublishResult,
+    PublishedPost, Recovery, SocialPublisher,
+};
+
+/// Больше вложений у записи на стене не бывает.
+const MAX_ATTACHMENTS: usize = 10;
+
+/// VK помнит guid `wall.post` час; берём с запасом.
+const GUID_WINDOW_MINUTES: i64 = 50;
 
 #[derive(Clone)]
 pub struct VkPublisher {
@@ -18,63 +32,184 @@ impl VkPublisher {
             client: Client::new(),
         }
     }
+
+    /// access_token, id сообщества (положительный) и версия API.
+    fn credentials(account: &SocialAccount) -> Result<(&str, i64, String)> {
+        let access_token = account
+            .access_token
+            .as_deref()
+            .ok_or_else(|| ProviderError::auth("vk access_token is missing"))?;
+        let group_id = account
+            .external_account_id
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("vk external_account_id/group_id is missing"))?
+            .parse::<i64>()?
+            .abs();
+        let api_version = env::var("VK_API_VERSION").unwrap_or_else(|_| "5.199".to_string());
+        Ok((access_token, group_id, api_version))
+    }
+
+    /// Вызов метода API; возвращает `response`, ошибки VK — как [`ProviderError`].
+    async fn call(&self, account: &SocialAccount, method: &str, form: &[(&str, String)]) -> Result<Value> {
+        let response = self
+            .client
+            .post(format!("{}/method/{method}", api_base_url(account, SocialProvider::Vk)))
+            .form(form)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Vk, Some(status.as_u16()), &body).into());
+        }
+
+        let json: Value = serde_json::from_str(&body)?;
+
+        if let Some(err) = json.get("error") {
+            return Err(ProviderError::from_response(SocialProvider::Vk, None, &json!({ "error": err }).to_string()).into());
+        }
+
+        Ok(json["response"].clone())
+    }
+
+    /// getWallUploadServer → загрузка файла → saveWallPhoto. Возвращает `photo{owner}_{id}` для `attachments`.
+    /// VK принимает только файл, поэтому удалённую картинку сначала скачиваем.
+    async fn upload_wall_photo(
+        &self,
+        account: &SocialAccount,
+        access_token: &str,
+        group_id: i64,
+        api_version: &str,
+        image_url: &str,
+    ) -> Result<String> {
+        let auth = |extra: Vec<(&'static str, String)>| {
+            let mut form = vec![
+                ("group_id", group_id.to_string()),
+                ("access_token", access_token.to_string()),
+                ("v", api_version.to_string()),
+            ];
+            form.extend(extra);
+            form
+        };
+
+        let server = self.call(account, "photos.getWallUploadServer", &auth(vec![])).await?;
+        let upload_url = server["upload_url"]
+            .as_str()
+            .ok_or_else(|| anyhow!("vk returned no upload_url: {server}"))?;
+
+        let image = load_image(&self.client, image_url).await?;
+        let part = Part::bytes(image.bytes)
+            .file_name(image.file_name)
+            .mime_str(image.mime)?;
+
+        let response = self
+            .client
+            .post(upload_url)
+            .multipart(Form::new().part("photo", part))
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(ProviderError::from_response(SocialProvider::Vk, Some(status.as_u16()), &body).into());
+        }
+
+        // {"server": 123, "photo": "[{...}]", "hash": "..."}; пустой photo — файл не принят
+        let uploaded: Value = serde_json::from_str(&body)?;
+        let photo = uploaded["photo"].as_str().unwrap_or_default();
+        if photo.is_empty() || photo == "[]" {
+            return Err(ProviderError::permanent(format!("vk rejected the image {image_url}: {uploaded}")).into());
+        }
+
+        let saved = self
+            .call(
+                account,
+                "photos.saveWallPhoto",
+                &auth(vec![
+                    ("server", uploaded["server"].to_string()),
+                    ("photo", photo.to_string()),
+                    ("hash", uploaded["hash"].as_str().unwrap_or_default().to_string()),
+                ]),
+            )
+            .await?;
+
+        let saved = &saved[0];
+        match (saved["owner_id"].as_i64(), saved["id"].as_i64()) {
+            (Some(owner), Some(id)) => Ok(format!("photo{owner}_{id}")),
+            _ => Err(anyhow!("vk saveWallPhoto returned no photo id: {saved}")),
+        }
+    }
 }
 
 #[async_trait]
 impl SocialPublisher for VkPublisher {
+    fn capabilities(&self) -> Capabilities {
+        // видео у VK загружается отдельным потоком (video.save), он не реализован
+        Capabilities {
+            max_media: MAX_ATTACHMENTS,
+            video: false,
+            local_media: true,
+            requires_media: false,
+            edit: true,
+            delete: true,
+        }
+    }
+
     async fn publish(
         &self,
         account: &SocialAccount,
         payload: PublishPayload,
     ) -> Result<PublishResult> {
+        self.capabilities().check(SocialProvider::Vk, &payload)?;
+
         let access_token = account
             .access_token
             .as_ref()
-            .ok_or_else(|| anyhow!("vk access_token is missing"))?;
+            .ok_or_else(|| ProviderError::auth("vk access_token is missing"))?;
 
         let group_id_str = account
             .external_account_id
             .as_ref()
-            .ok_or_else(|| anyhow!("vk external_account_id/group_id is missing"))?;
+            .ok_or_else(|| ProviderError::auth("vk external_account_id/group_id is missing"))?;
 
-        let group_id: i64 = group_id_str.parse()?;
+        let group_id: i64 = group_id_str.parse::<i64>()?.abs();
         let owner_id = -group_id; // для сообщества owner_id должен быть отрицательным
 
         let api_version = env::var("VK_API_VERSION").unwrap_or_else(|_| "5.199".to_string());
 
-        if payload.image_url.is_some() {
-            return Err(anyhow!("VK image posting is not implemented yet"));
+        let mut attachments = Vec::with_capacity(payload.media.len());
+        for item in &payload.media {
+            attachments.push(
+                self.upload_wall_photo(account, access_token, group_id, &api_version, &item.url)
+                    .await?,
+            );
         }
-
-        let response = self
-            .client
-            .post("https://api.vk.com/method/wall.post")
-            .form(&[
-                ("owner_id", owner_id.to_string()),
-                ("from_group", "1".to_string()),
-                ("message", payload.text),
-                ("access_token", access_token.to_string()),
-                ("v", api_version),
-            ])
-            .send()
-            .await?;
-
-        let status = response.status();
-        let body = response.text().await?;
-
-        if !status.is_success() {
-            return Err(anyhow!("vk HTTP error {status}: {body}"));
+        // без фото VK строит сниппет ссылки
+        if attachments.is_empty() {
+            attachments.extend(payload.link.clone());
         }
 
-        let json: Value = serde_json::from_str(&body)?;
-
-        if let Some(err) = json.get("error") {
-            return Err(anyhow!("vk API error: {err}"));
+        let mut form = vec![
+            ("owner_id", owner_id.to_string()),
+            ("from_group", "1".to_string()),
+            ("message", payload.text),
+            ("access_token", access_token.to_string()),
+            ("v", api_version),
+        ];
+        if !attachments.is_empty() {
+            form.push(("attachments", attachments.join(",")));
+        }
+        // VK не создаёт второй пост с тем же guid — повтор после падения безопасен
+        if let Some(key) = payload.checkpoint.idempotency_key {
+            form.push(("guid", key));
         }
 
+        let json = self.call(account, "wall.post", &form).await?;
+
         let post_id = json
-            .get("response")
-            .and_then(|r| r.get("post_id"))
+            .get("post_id")
             .and_then(|v| v.as_i64())
             .map(|v| v.to_string())
             .ok_or_else(|| anyhow!("vk returned no post_id: {json}"))?;
@@ -82,6 +217,170 @@ impl SocialPublisher for VkPublisher {
         Ok(PublishResult {
             external_post_id: Some(post_id),
             raw_response: Some(json.to_string()),
+            published_state: None,
+        })
+    }
+
+    async fn test_connection(&self, account: &SocialAccount) -> Result<String> {
+        let access_token = account
+            .access_token
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("vk access_token is missing"))?;
+
+        let group_id = account
+            .external_account_id
+            .as_ref()
+            .ok_or_else(|| ProviderError::auth("vk external_account_id/group_id is missing"))?;
+
+        let api_version = env::var("VK_API_VERSION").unwrap_or_else(|_| "5.199".to_string());
+
+        let json: Value = self
+            .client
+            .post(format!("{}/method/groups.getById", api_base_url(account, SocialProvider::Vk)))
+            .form(&[
+                ("group_id", group_id.as_str()),
+                ("access_token", access_token.as_str()),
+                ("v", api_version.as_str()),
+            ])
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(err) = json.get("error") {
+            return Err(ProviderError::from_response(SocialProvider::Vk, None, &json!({ "error": err }).to_string()).into());
+        }
+
+        // 5.199: response.groups[]; старые версии: response[]
+        let group = json["response"]["groups"]
+            .get(0)
+            .or_else(|| json["response"].get(0))
+            .ok_or_else(|| anyhow!("vk returned no group: {json}"))?;
+
+        Ok(format!("group {}", group["name"].as_str().unwrap_or("?")))
+    }
+
+    async fn recover(
+        &self,
+        _account: &SocialAccount,
+        checkpoint: &PublishCheckpoint,
+    ) -> Result<Recovery> {
+        // guid защищает от дубля только в течение часа после первой отправки
+        let within_guid_window = checkpoint
+            .started_at
+            .is_some_and(|at| Utc::now() - at < ChronoDuration::minutes(GUID_WINDOW_MINUTES));
+
+        Ok(if checkpoint.idempotency_key.is_some() && within_guid_window {
+            Recovery::NotPublished
+        } else {
+            Recovery::Unknown
         })
     }
-}
\ No newline at end of file
+
+    /// `wall.edit` заменяет и вложения: без них фото пропали бы, поэтому берём текущие из `wall.getById`.
+    async fn edit(
+        &self,
+        account: &SocialAccount,
+        post: PublishedPost<'_>,
+        payload: &PublishPayload,
+    ) -> Result<()> {
+        let external_post_id = post.external_post_id;
+        let (access_token, group_id, api_version) = Self::credentials(account)?;
+        let auth = [("access_token", access_token.to_string()), ("v", api_version)];
+
+        let mut query = vec![("posts", format!("-{group_id}_{external_post_id}"))];
+        query.extend(auth.iter().cloned());
+        let json = self.call(account, "wall.getById", &query).await?;
+        let post = json
+            .get("items")
+            .unwrap_or(&json)
+            .get(0)
+            .ok_or_else(|| ProviderError::permanent(format!("vk post {external_post_id} not found")))?;
+
+        let mut form = vec![
+            ("owner_id", format!("-{group_id}")),
+            ("post_id", external_post_id.to_string()),
+            ("message", payload.text.clone()),
+        ];
+        let attachments = existing_attachments(post);
+        if !attachments.is_empty() {
+            form.push(("attachments", attachments.join(",")));
+        }
+        form.extend(auth);
+        self.call(account, "wall.edit", &form).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, account: &SocialAccount, post: PublishedPost<'_>) -> Result<()> {
+        let (access_token, group_id, api_version) = Self::credentials(account)?;
+        self.call(
+            account,
+            "wall.delete",
+            &[
+                ("owner_id", format!("-{group_id}")),
+                ("post_id", post.external_post_id.to_string()),
+                ("access_token", access_token.to_string()),
+                ("v", api_version),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn fetch_metrics(
+        &self,
+        account: &SocialAccount,
+        external_post_id: &str,
+    ) -> Result<Option<PostMetrics>> {
+        let (access_token, group_id, api_version) = Self::credentials(account)?;
+
+        let json = self
+            .call(
+                account,
+                "wall.getById",
+                &[
+                    ("posts", format!("-{group_id}_{external_post_id}")),
+                    ("access_token", access_token.to_string()),
+                    ("v", api_version),
+                ],
+            )
+            .await?;
+
+        // с 5.132 ответ — {items: [...]}, раньше был массив
+        let post = json.get("items").unwrap_or(&json).get(0);
+        let Some(post) = post else {
+            // пост удалён со стены
+            return Ok(None);
+        };
+
+        Ok(Some(PostMetrics {
+            views: post["views"]["count"].as_i64(),
+            likes: post["likes"]["count"].as_i64(),
+            reactions: post["reactions"]["count"].as_i64(),
+            comments: post["comments"]["count"].as_i64(),
+            shares: post["reposts"]["count"].as_i64(),
+            raw: Some(post.clone()),
+        }))
+    }
+}
+
+/// Вложения поста в формате `attachments`: `photo-1_2`, `photo-1_2_key`, ссылка — как есть.
+fn existing_attachments(post: &Value) -> Vec<String> {
+    post["attachments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| {
+            let kind = a["type"].as_str()?;
+            let obj = &a[kind];
+            if kind == "link" {
+                return obj["url"].as_str().map(str::to_string);
+            }
+            let mut id = format!("{kind}{}_{}", obj["owner_id"].as_i64()?, obj["id"].as_i64()?);
+            if let Some(key) = obj["access_key"].as_str() {
+                id.push_str(&format!("_{key}"));
+            }
+            Some(id)
+        })
+        .collect()
+}
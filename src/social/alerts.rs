@@ -0,0 +1,124 @@
+use anyhow::Result;
+use diesel::prelude::*;
+
+use crate::api::admin::notifications::notify_admins;
+use crate::db::DbPool;
+use crate::models::notifications::NewAdminNotification;
+use crate::social::accounts::{mark_account_auth_error, redact_account_secrets};
+use crate::social::adapters::PublishPayload;
+use crate::social::render::escape_html;
+use crate::social::service::SocialPublishers;
+use crate::social::types::SocialProvider;
+
+/// Помечает аккаунт `auth_error` и уведомляет админов. Уведомление (в т.ч. в Telegram) —
+/// только на первую ошибку подряд, чтобы не спамить на каждом повторе.
+pub async fn report_account_auth_error(
+    pool: &DbPool,
+    publishers: &SocialPublishers,
+    account_id: i64,
+    kind: &str,
+    title: String,
+    body: String,
+    error: String,
+) -> Result<()> {
+    let first_failure = {
+        let pool = pool.clone();
+        let (kind, title, body) = (kind.to_string(), title.clone(), body.clone());
+        tokio::task::spawn_blocking(move || -> Result<bool> {
+            let mut conn = pool.get()?;
+            let first = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let first = mark_account_auth_error(conn, account_id, &error)?;
+                if first {
+                    notify_admins(
+                        conn,
+                        NewAdminNotification {
+                            kind,
+                            title,
+                            body: Some(body),
+                            entity_type: Some("social_account".to_string()),
+                            entity_id: Some(account_id.to_string()),
+                        },
+                    )?;
+                }
+                Ok(first)
+            })?;
+            Ok(first)
+        })
+        .await??
+    };
+
+    if first_failure {
+        let text = format!("⚠️ <b>{}</b>\n\n{}", escape_html(&title), escape_html(&body));
+        alert_telegram(pool, publishers, &text).await;
+    }
+
+    Ok(())
+}
+
+/// Job требует ручного решения (например, неизвестно, вышел ли пост).
+pub async fn report_job_needs_attention(
+    pool: &DbPool,
+    publishers: &SocialPublishers,
+    job_id: i64,
+    kind: &str,
+    title: String,
+    body: String,
+) -> Result<()> {
+    {
+        let pool = pool.clone();
+        let (kind, title, body) = (kind.to_string(), title.clone(), body.clone());
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut conn = pool.get()?;
+            notify_admins(
+                &mut conn,
+                NewAdminNotification {
+                    kind,
+                    title,
+                    body: Some(body),
+                    entity_type: Some("social_job".to_string()),
+                    entity_id: Some(job_id.to_string()),
+                },
+            )?;
+            Ok(())
+        })
+        .await??;
+    }
+
+    let text = format!("⚠️ <b>{}</b>\n\n{}", escape_html(&title), escape_html(&body));
+    alert_telegram(pool, publishers, &text).await;
+
+    Ok(())
+}
+
+/// Дублирует уведомление в Telegram-чат админов (`ADMIN_ALERT_TELEGRAM_CHAT_ID`),
+/// через первый активный Telegram-аккаунт. Без настройки — ничего не делает.
+async fn alert_telegram(pool: &DbPool, publishers: &SocialPublishers, text: &str) {
+    let Ok(chat_id) = std::env::var("ADMIN_ALERT_TELEGRAM_CHAT_ID") else {
+        return;
+    };
+
+    let Some(mut bot) = crate::social::scheduler::active_accounts(pool, SocialProvider::Telegram)
+        .await
+        .into_iter()
+        .next()
+    else {
+        eprintln!("[alerts] no active telegram account for admin alerts");
+        return;
+    };
+    bot.external_account_id = Some(chat_id);
+
+    let payload = PublishPayload {
+        text: text.to_string(),
+        ..Default::default()
+    };
+    let sent = match publishers.get(SocialProvider::Telegram) {
+        Ok(p) => p.publish(&bot, payload).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = sent {
+        eprintln!(
+            "[alerts] admin alert failed: {}",
+            redact_account_secrets(&format!("{err:#}"), &bot)
+        );
+    }
+}
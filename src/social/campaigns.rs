@@ -0,0 +1,227 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::social::{NewSocialPostJob, SocialCampaign};
+use crate::schema::{news_posts, social_accounts, social_campaign_accounts, social_campaigns, social_post_jobs};
+use crate::social_jobs::create_social_post_job;
+
+/// `news_posts.kind`, из которых можно собирать кампании.
+pub const NEWS_KINDS: [&str; 8] = [
+    "idiom", "news", "tip", "collocation", "vocab", "grammar", "phrase", "anecdote",
+];
+
+/// Сколько дней `random_hot` не повторяет пост в том же аккаунте.
+const RANDOM_HOT_REPEAT_DAYS: i64 = 30;
+
+/// Откуда кампания берёт пост для каждого целевого аккаунта.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentSource {
+    /// Самый старый пост, который в этот аккаунт ещё не ставился.
+    NextUnposted {
+        #[serde(default)]
+        kind: Option<String>,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Случайный `is_hot` пост, не публиковавшийся в аккаунте последние 30 дней.
+    RandomHot {
+        #[serde(default)]
+        kind: Option<String>,
+        #[serde(default)]
+        level: Option<String>,
+    },
+}
+
+impl ContentSource {
+    fn filters(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            Self::NextUnposted { kind, level } | Self::RandomHot { kind, level } => {
+                (kind.as_deref(), level.as_deref())
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let (kind, level) = self.filters();
+        if let Some(kind) = kind.filter(|k| !NEWS_KINDS.contains(k)) {
+            return Err(format!("unknown kind: {kind}"));
+        }
+        if level.is_some_and(|l| l.trim().is_empty()) {
+            return Err("level must not be empty".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Cron кампании в её часовом поясе.
+///
+/// Принимает 5 полей (`мин час день месяц день_недели`) или 6 с секундами.
+/// Дни недели лучше писать именами (`MON-FRI`): в крейте `cron` 1 — воскресенье.
+pub struct CampaignSchedule {
+    schedule: Schedule,
+    tz: Tz,
+}
+
+impl CampaignSchedule {
+    pub fn parse(cron_expr: &str, timezone: &str) -> Result<Self, String> {
+        let expr = cron_expr.trim();
+        let expr = match expr.split_whitespace().count() {
+            5 => format!("0 {expr}"),
+            _ => expr.to_string(),
+        };
+        let schedule = Schedule::from_str(&expr).map_err(|e| format!("invalid cron: {e}"))?;
+        let tz = timezone
+            .parse::<Tz>()
+            .map_err(|_| format!("unknown timezone: {timezone}"))?;
+        Ok(Self { schedule, tz })
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.tz))
+            .next()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+/// Ставит job'ы всех наступивших кампаний. Пропущенные за время простоя запуски
+/// не догоняются: кампания срабатывает один раз, следующий запуск — от текущего момента.
+pub fn run_due_campaigns(conn: &mut PgConnection) -> QueryResult<Vec<(i64, usize)>> {
+    conn.transaction(|conn| {
+        let now = Utc::now();
+        let due = social_campaigns::table
+            .filter(social_campaigns::is_active.eq(true))
+            .filter(social_campaigns::next_run_at.le(now))
+            .select(SocialCampaign::as_select())
+            .for_update()
+            .skip_locked()
+            .load::<SocialCampaign>(conn)?;
+
+        let mut results = Vec::with_capacity(due.len());
+        for campaign in due {
+            let (enqueued, error) = match enqueue_campaign(conn, &campaign) {
+                Ok(0) => (0, Some("no content left for this source".to_string())),
+                Ok(n) => (n, None),
+                Err(CampaignError::Config(msg)) => (0, Some(msg)),
+                Err(CampaignError::Db(e)) => return Err(e),
+            };
+
+            // битый cron — кампания останавливается до исправления
+            let next_run_at = CampaignSchedule::parse(&campaign.cron_expr, &campaign.timezone)
+                .ok()
+                .and_then(|s| s.next_after(now));
+
+            diesel::update(social_campaigns::table.find(campaign.id))
+                .set((
+                    social_campaigns::next_run_at.eq(next_run_at),
+                    social_campaigns::last_run_at.eq(now),
+                    social_campaigns::last_error.eq(error),
+                    social_campaigns::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            results.push((campaign.id, enqueued));
+        }
+        Ok(results)
+    })
+}
+
+enum CampaignError {
+    Config(String),
+    Db(diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for CampaignError {
+    fn from(e: diesel::result::Error) -> Self {
+        Self::Db(e)
+    }
+}
+
+fn enqueue_campaign(conn: &mut PgConnection, campaign: &SocialCampaign) -> Result<usize, CampaignError> {
+    let source: ContentSource = serde_json::from_value(campaign.content_source.clone())
+        .map_err(|e| CampaignError::Config(format!("invalid content_source: {e}")))?;
+
+    let account_ids: Vec<i64> = social_campaign_accounts::table
+        .inner_join(social_accounts::table)
+        .filter(social_campaign_accounts::campaign_id.eq(campaign.id))
+        .filter(social_accounts::is_active.eq(true))
+        .select(social_accounts::id)
+        .load(conn)?;
+
+    let mut enqueued = 0;
+    for account_id in account_ids {
+        let Some(news_post_id) = pick_news_post(conn, &source, account_id)? else {
+            continue;
+        };
+        create_social_post_job(
+            conn,
+            &NewSocialPostJob {
+                news_post_id,
+                social_account_id: account_id,
+                status: "scheduled".to_string(),
+                scheduled_for: Utc::now(),
+                published_at: None,
+                retry_count: 0,
+                next_retry_at: None,
+                external_post_id: None,
+                error_message: None,
+                payload_json: json!({}),
+                campaign_id: Some(campaign.id),
+                priority: 0,
+            },
+        )?;
+        enqueued += 1;
+    }
+    Ok(enqueued)
+}
+
+/// Пост для аккаунта по источнику кампании; `None` — подходящих не осталось.
+pub fn pick_news_post(
+    conn: &mut PgConnection,
+    source: &ContentSource,
+    account_id: i64,
+) -> QueryResult<Option<Uuid>> {
+    let (kind, level) = source.filters();
+
+    let mut query = news_posts::table.select(news_posts::id).into_boxed();
+    if let Some(kind) = kind {
+        query = query.filter(news_posts::kind.eq(kind.to_string()));
+    }
+    if let Some(level) = level {
+        query = query.filter(news_posts::level.eq(level.to_string()));
+    }
+
+    match source {
+        ContentSource::NextUnposted { .. } => {
+            // любая job, даже отменённая: админ уже решал судьбу поста в этом аккаунте
+            let seen = social_post_jobs::table
+                .filter(social_post_jobs::social_account_id.eq(account_id))
+                .select(social_post_jobs::news_post_id);
+            query
+                .filter(diesel::dsl::not(news_posts::id.eq_any(seen)))
+                .order((news_posts::created_at.asc(), news_posts::id.asc()))
+                .first(conn)
+                .optional()
+        }
+        ContentSource::RandomHot { .. } => {
+            let recent = social_post_jobs::table
+                .filter(social_post_jobs::social_account_id.eq(account_id))
+                .filter(social_post_jobs::created_at.gt(Utc::now() - ChronoDuration::days(RANDOM_HOT_REPEAT_DAYS)))
+                .select(social_post_jobs::news_post_id);
+            query
+                .filter(news_posts::is_hot.eq(true))
+                .filter(diesel::dsl::not(news_posts::id.eq_any(recent)))
+                .order(diesel::dsl::sql::<diesel::sql_types::Double>("random()"))
+                .first(conn)
+                .optional()
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+/// Envelope-шифрование токенов social_accounts.
+///
+/// Каждый токен шифруется своим случайным data key (AES-256-GCM), а data key — ключом из env
+/// (key encryption key). В БД лежит строка
+/// `enc:v1:<kid>:<base64(nonce|wrapped data key)>:<base64(nonce|ciphertext)>`.
+///
+/// Ключи: `SOCIAL_TOKEN_KEYS="kid2:<base64 32 bytes>,kid1:<base64 32 bytes>"` — первый
+/// шифрует, остальные только расшифровывают. Ротация: добавить новый ключ первым,
+/// перезапустить (токены перешифруются при старте), затем убрать старый.
+const PREFIX: &str = "enc:v1:";
+const KEYS_ENV: &str = "SOCIAL_TOKEN_KEYS";
+
+pub struct TokenKeyring {
+    primary_kid: String,
+    keys: HashMap<String, Key<Aes256Gcm>>,
+}
+
+struct Envelope<'a> {
+    kid: &'a str,
+    wrapped_key: Vec<u8>,
+    sealed: Vec<u8>,
+}
+
+fn key_from(bytes: &[u8]) -> Result<Key<Aes256Gcm>> {
+    let raw: [u8; 32] = bytes.try_into().map_err(|_| anyhow!("key must be 32 bytes"))?;
+    Ok(raw.into())
+}
+
+fn seal(key: &Key<Aes256Gcm>, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut out = nonce.to_vec();
+    out.extend(
+        Aes256Gcm::new(key)
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("encryption failed"))?,
+    );
+    Ok(out)
+}
+
+fn open(key: &Key<Aes256Gcm>, sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < 12 {
+        bail!("sealed value is too short");
+    }
+    let (nonce, ct) = sealed.split_at(12);
+    let nonce: [u8; 12] = nonce.try_into()?;
+    Aes256Gcm::new(key)
+        .decrypt(&Nonce::from(nonce), ct)
+        .map_err(|_| anyhow!("decryption failed (wrong key or corrupted value)"))
+}
+
+fn parse_envelope(stored: &str) -> Result<Option<Envelope<'_>>> {
+    let Some(rest) = stored.strip_prefix(PREFIX) else {
+        return Ok(None);
+    };
+    let mut parts = rest.splitn(3, ':');
+    let (Some(kid), Some(wrapped), Some(sealed)) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("malformed encrypted token");
+    };
+    Ok(Some(Envelope {
+        kid,
+        wrapped_key: STANDARD.decode(wrapped).context("malformed encrypted token")?,
+        sealed: STANDARD.decode(sealed).context("malformed encrypted token")?,
+    }))
+}
+
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(PREFIX)
+}
+
+impl TokenKeyring {
+    /// `None`, если ключи не заданы.
+    pub fn from_env() -> Result<Option<Self>> {
+        let raw = match std::env::var(KEYS_ENV) {
+            Ok(v) if !v.trim().is_empty() => v,
+            _ => return Ok(None),
+        };
+        Self::parse(&raw).map(Some)
+    }
+
+    fn parse(raw: &str) -> Result<Self> {
+        let mut primary_kid = None;
+        let mut keys = HashMap::new();
+
+        for item in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (kid, b64) = item
+                .split_once(':')
+                .ok_or_else(|| anyhow!("{KEYS_ENV}: expected kid:base64key"))?;
+            let bytes = STANDARD
+                .decode(b64.trim())
+                .with_context(|| format!("{KEYS_ENV}: key {kid} is not base64"))?;
+            let key = key_from(&bytes).with_context(|| format!("{KEYS_ENV}: key {kid}"))?;
+            primary_kid.get_or_insert_with(|| kid.to_string());
+            keys.insert(kid.to_string(), key);
+        }
+
+        Ok(Self {
+            primary_kid: primary_kid.ok_or_else(|| anyhow!("{KEYS_ENV} is empty"))?,
+            keys,
+        })
+    }
+
+    fn key(&self, kid: &str) -> Result<&Key<Aes256Gcm>> {
+        self.keys
+            .get(kid)
+            .ok_or_else(|| anyhow!("token key '{kid}' is not configured in {KEYS_ENV}"))
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let sealed = seal(&data_key, plaintext.as_bytes())?;
+        let wrapped = seal(self.key(&self.primary_kid)?, &data_key)?;
+
+        Ok(format!(
+            "{PREFIX}{}:{}:{}",
+            self.primary_kid,
+            STANDARD.encode(wrapped),
+            STANDARD.encode(sealed)
+        ))
+    }
+
+    /// Значения без префикса (ещё не зашифрованные) возвращаются как есть.
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let Some(env) = parse_envelope(stored)? else {
+            return Ok(stored.to_string());
+        };
+        let data_key = key_from(&open(self.key(env.kid)?, &env.wrapped_key)?)?;
+        let plain = open(&data_key, &env.sealed)?;
+        String::from_utf8(plain).context("decrypted token is not utf-8")
+    }
+
+    /// Нужно ли перезаписать значение: открытый текст или data key обёрнут не основным ключом.
+    pub fn needs_rewrap(&self, stored: &str) -> bool {
+        match parse_envelope(stored) {
+            Ok(Some(env)) => env.kid != self.primary_kid,
+            Ok(None) => true,
+            Err(_) => false,
+        }
+    }
+
+    /// Ротация: data key перешифровывается основным ключом, сам токен не трогаем.
+    pub fn rewrap(&self, stored: &str) -> Result<String> {
+        let Some(env) = parse_envelope(stored)? else {
+            return self.encrypt(stored);
+        };
+        let data_key = open(self.key(env.kid)?, &env.wrapped_key)?;
+        let wrapped = seal(self.key(&self.primary_kid)?, &data_key)?;
+
+        Ok(format!(
+            "{PREFIX}{}:{}:{}",
+            self.primary_kid,
+            STANDARD.encode(wrapped),
+            STANDARD.encode(&env.sealed)
+        ))
+    }
+}
+
+static KEYRING: OnceLock<Option<TokenKeyring>> = OnceLock::new();
+
+/// Ключи из env, читаются один раз. Ошибка конфигурации — паника при старте:
+/// лучше не подняться, чем писать токены открытым текстом при битом ключе.
+pub fn keyring() -> Option<&'static TokenKeyring> {
+    KEYRING
+        .get_or_init(|| TokenKeyring::from_env().expect("invalid SOCIAL_TOKEN_KEYS"))
+        .as_ref()
+}
+
+/// Токен для записи в БД (без ключей — как есть).
+pub fn seal_token(token: Option<String>) -> Result<Option<String>> {
+    match (token, keyring()) {
+        (Some(t), Some(k)) => Ok(Some(k.encrypt(&t)?)),
+        (t, _) => Ok(t),
+    }
+}
+
+/// Токен из БД в открытом виде.
+pub fn open_token(stored: Option<String>) -> Result<Option<String>> {
+    match stored {
+        Some(s) if is_encrypted(&s) => {
+            let k = keyring().ok_or_else(|| anyhow!("encrypted token found but {KEYS_ENV} is not set"))?;
+            Ok(Some(k.decrypt(&s)?))
+        }
+        other => Ok(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyring(raw: &[(&str, u8)]) -> TokenKeyring {
+        let raw: Vec<String> = raw
+            .iter()
+            .map(|(kid, byte)| format!("{kid}:{}", STANDARD.encode([*byte; 32])))
+            .collect();
+        TokenKeyring::parse(&raw.join(",")).unwrap()
+    }
+
+    #[test]
+    fn encrypt_decrypt_roundtrip() {
+        let keys = keyring(&[("k1", 1)]);
+        let stored = keys.encrypt("secret-token").unwrap();
+
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains("secret-token"));
+        assert_eq!(keys.decrypt(&stored).unwrap(), "secret-token");
+        // ещё не зашифрованные значения отдаются как есть
+        assert_eq!(keys.decrypt("plain-token").unwrap(), "plain-token");
+    }
+
+    #[test]
+    fn rewrap_moves_token_to_primary_key() {
+        let old = keyring(&[("k1", 1)]);
+        let stored = old.encrypt("secret-token").unwrap();
+
+        let rotated = keyring(&[("k2", 2), ("k1", 1)]);
+        assert!(rotated.needs_rewrap(&stored));
+        let rewrapped = rotated.rewrap(&stored).unwrap();
+        assert!(rewrapped.starts_with("enc:v1:k2:"));
+        assert!(!rotated.needs_rewrap(&rewrapped));
+
+        // старый ключ больше не нужен
+        let new_only = keyring(&[("k2", 2)]);
+        assert_eq!(new_only.decrypt(&rewrapped).unwrap(), "secret-token");
+    }
+
+    #[test]
+    fn wrong_key_is_rejected() {
+        let stored = keyring(&[("k1", 1)]).encrypt("secret-token").unwrap();
+
+        assert!(keyring(&[("k1", 9)]).decrypt(&stored).is_err());
+        assert!(keyring(&[("k2", 1)]).decrypt(&stored).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::social::types::SocialProvider;
+
+/// Класс ошибки публикации — от него зависит, повторять ли job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Лимит запросов провайдера: повторить позже (не раньше `retry_after`).
+    RateLimited,
+    /// Токен/права/настройки аккаунта: повтор не поможет, нужен админ.
+    Auth,
+    /// Провайдер отверг сам пост (разметка, размер, формат): повтор не поможет.
+    Permanent,
+    /// Сеть, 5xx, таймауты: повторить с backoff.
+    Transient,
+}
+
+impl ErrorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RateLimited => "rate_limited",
+            Self::Auth => "auth",
+            Self::Permanent => "permanent",
+            Self::Transient => "transient",
+        }
+    }
+}
+
+/// Ошибка провайдера с классом. Адаптеры возвращают её через `anyhow`,
+/// worker достаёт через [`classify`].
+#[derive(Debug)]
+pub struct ProviderError {
+    pub kind: ErrorKind,
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl ProviderError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            retry_after: None,
+            message: message.into(),
+        }
+    }
+
+    /// Нет токена/id аккаунта и т.п.
+    pub fn auth(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Auth, message)
+    }
+
+    /// Пост в таком виде провайдер не примет никогда.
+    pub fn permanent(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Permanent, message)
+    }
+
+    /// Ошибка из ответа API. `http_status` — `None`, если провайдер вернул ошибку в теле при 200
+    /// (VK) или статус уже потерян (Telegram отдаёт код в `error_code`).
+    pub fn from_response(provider: SocialProvider, http_status: Option<u16>, body: &str) -> Self {
+        let json: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+        let (kind, retry_after) = match provider {
+            SocialProvider::Telegram => classify_telegram(http_status, &json),
+            SocialProvider::Vk => classify_vk(http_status, &json),
+            SocialProvider::Instagram | SocialProvider::Threads => classify_meta(http_status, &json),
+            SocialProvider::Sandbox => (ErrorKind::Permanent, None),
+        };
+
+        let status = http_status.map(|s| format!(" HTTP {s}")).unwrap_or_default();
+        Self {
+            kind,
+            retry_after,
+            message: format!("{} API error{status}: {body}", provider.as_str()),
+        }
+    }
+}
+
+fn by_http_status(status: Option<u16>) -> Option<ErrorKind> {
+    match status? {
+        429 => Some(ErrorKind::RateLimited),
+        401 | 403 => Some(ErrorKind::Auth),
+        s if s >= 500 => Some(ErrorKind::Transient),
+        _ => None,
+    }
+}
+
+// {"ok":false,"error_code":429,"description":"...","parameters":{"retry_after":35}}
+fn classify_telegram(status: Option<u16>, json: &Value) -> (ErrorKind, Option<Duration>) {
+    let retry_after = json["parameters"]["retry_after"].as_u64().map(Duration::from_secs);
+    let code = json["error_code"].as_u64().map(|c| c as u16).or(status);
+
+    let kind = match code {
+        Some(429) => ErrorKind::RateLimited,
+        // 403: бота удалили из канала или лишили прав
+        Some(401) | Some(403) => ErrorKind::Auth,
+        Some(c) if c >= 500 => ErrorKind::Transient,
+        // 400: битая HTML-разметка, слишком длинная подпись, чат не найден
+        Some(_) => ErrorKind::Permanent,
+        None => ErrorKind::Transient,
+    };
+    (kind, retry_after)
+}
+
+// {"error":{"error_code":9,"error_msg":"Flood control"}}
+fn classify_vk(status: Option<u16>, json: &Value) -> (ErrorKind, Option<Duration>) {
+    let kind = match json["error"]["error_code"].as_i64() {
+        Some(6 | 9 | 29) => ErrorKind::RateLimited,
+        Some(5 | 15 | 17 | 27 | 28 | 203 | 214) => ErrorKind::Auth,
+        Some(1 | 10) => ErrorKind::Transient,
+        Some(_) => ErrorKind::Permanent,
+        None => by_http_status(status).unwrap_or(ErrorKind::Transient),
+    };
+    (kind, None)
+}
+
+// {"error":{"code":4,"error_subcode":2207042,"is_transient":false,"message":"..."}}
+fn classify_meta(status: Option<u16>, json: &Value) -> (ErrorKind, Option<Duration>) {
+    let err = &json["error"];
+    let code = err["code"].as_i64();
+    let subcode = err["error_subcode"].as_i64();
+
+    let kind = if matches!(code, Some(4 | 17 | 32 | 613)) || subcode == Some(2207042) {
+        ErrorKind::RateLimited
+    } else if matches!(code, Some(10 | 102 | 190 | 200..=299)) {
+        ErrorKind::Auth
+    } else if err["is_transient"].as_bool() == Some(true) || matches!(code, Some(1 | 2)) {
+        ErrorKind::Transient
+    } else if let Some(kind) = by_http_status(status) {
+        kind
+    } else if code.is_some() {
+        ErrorKind::Permanent
+    } else {
+        ErrorKind::Transient
+    };
+    (kind, None)
+}
+
+/// Класс ошибки из цепочки `anyhow`. Всё, что адаптер не классифицировал
+/// (сеть, таймауты, неожиданный ответ), считается временным.
+pub fn classify(err: &anyhow::Error) -> (ErrorKind, Option<Duration>) {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<ProviderError>())
+        .map(|e| (e.kind, e.retry_after))
+        .unwrap_or((ErrorKind::Transient, None))
+}
+
+/// Мог ли запрос дойти до провайдера. Ответ с ошибкой и отказ в соединении — точно нет;
+/// таймаут, обрыв после отправки или непонятный ответ — неизвестно.
+pub fn may_have_reached_provider(err: &anyhow::Error) -> bool {
+    for e in err.chain() {
+        if e.downcast_ref::<ProviderError>().is_some() {
+            return false;
+        }
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return !(e.is_connect() || e.is_builder());
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(provider: SocialProvider, status: Option<u16>, body: &str) -> (ErrorKind, Option<Duration>) {
+        classify(&ProviderError::from_response(provider, status, body).into())
+    }
+
+    #[test]
+    fn classifies_telegram_errors() {
+        let rate_limited = r#"{"ok":false,"error_code":429,"parameters":{"retry_after":35}}"#;
+        assert_eq!(
+            kind(SocialProvider::Telegram, None, rate_limited),
+            (ErrorKind::RateLimited, Some(Duration::from_secs(35)))
+        );
+        let kicked = r#"{"ok":false,"error_code":403,"description":"Forbidden: bot was kicked"}"#;
+        assert_eq!(kind(SocialProvider::Telegram, None, kicked).0, ErrorKind::Auth);
+        let bad_markup = r#"{"ok":false,"error_code":400,"description":"Bad Request: can't parse entities"}"#;
+        assert_eq!(kind(SocialProvider::Telegram, None, bad_markup).0, ErrorKind::Permanent);
+        assert_eq!(kind(SocialProvider::Telegram, Some(502), "<html>").0, ErrorKind::Transient);
+    }
+
+    #[test]
+    fn classifies_vk_errors() {
+        let vk = |code: i64| kind(SocialProvider::Vk, Some(200), &format!(r#"{{"error":{{"error_code":{code}}}}}"#)).0;
+        assert_eq!(vk(9), ErrorKind::RateLimited);
+        assert_eq!(vk(5), ErrorKind::Auth);
+        assert_eq!(vk(10), ErrorKind::Transient);
+        assert_eq!(vk(100), ErrorKind::Permanent);
+        assert_eq!(kind(SocialProvider::Vk, Some(503), "").0, ErrorKind::Transient);
+    }
+
+    #[test]
+    fn classifies_meta_errors() {
+        let meta = |status: u16, body: &str| kind(SocialProvider::Instagram, Some(status), body).0;
+        assert_eq!(meta(400, r#"{"error":{"code":4}}"#), ErrorKind::RateLimited);
+        assert_eq!(meta(400, r#"{"error":{"code":9,"error_subcode":2207042}}"#), ErrorKind::RateLimited);
+        assert_eq!(meta(400, r#"{"error":{"code":190}}"#), ErrorKind::Auth);
+        assert_eq!(meta(400, r#"{"error":{"code":100,"is_transient":true}}"#), ErrorKind::Transient);
+        assert_eq!(meta(400, r#"{"error":{"code":100}}"#), ErrorKind::Permanent);
+        assert_eq!(meta(500, "not json"), ErrorKind::Transient);
+    }
+
+    #[test]
+    fn unclassified_errors_are_transient() {
+        assert_eq!(classify(&anyhow::anyhow!("connection reset")), (ErrorKind::Transient, None));
+
+        let wrapped = anyhow::Error::from(ProviderError::auth("token is missing")).context("publish failed");
+        assert_eq!(classify(&wrapped).0, ErrorKind::Auth);
+        assert!(!may_have_reached_provider(&wrapped));
+    }
+}
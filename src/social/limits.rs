@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Nullable, Timestamptz};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::schema::social_accounts;
+
+/// Ограничения частоты постинга аккаунта, `settings_json.posting`:
+/// `{ "max_per_hour": 2, "max_per_day": 12, "min_spacing_minutes": 20,
+///    "quiet_hours": { "from": "23:00", "to": "08:00" }, "timezone": "Europe/Moscow" }`.
+/// Соблюдаются при захвате job'а: пока аккаунт упирается в лимит, его job'ы ждут в очереди.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostingRules {
+    pub max_per_hour: Option<u32>,
+    /// За последние 24 часа.
+    pub max_per_day: Option<u32>,
+    pub min_spacing_minutes: Option<u32>,
+    pub quiet_hours: Option<QuietHours>,
+    /// IANA, по умолчанию UTC.
+    pub timezone: Option<String>,
+}
+
+/// `from`–`to` по местному времени аккаунта, `HH:MM`; может переходить через полночь.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    pub from: String,
+    pub to: String,
+}
+
+/// Что аккаунт уже отправил: в работе сейчас и опубликовано за сутки.
+#[derive(Debug, Default, QueryableByName)]
+struct AccountUsage {
+    #[diesel(sql_type = BigInt)]
+    social_account_id: i64,
+    #[diesel(sql_type = BigInt)]
+    last_hour: i64,
+    #[diesel(sql_type = BigInt)]
+    last_day: i64,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    last_published_at: Option<DateTime<Utc>>,
+    #[diesel(sql_type = BigInt)]
+    in_flight: i64,
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").map_err(|_| format!("invalid time {s:?}, expected HH:MM"))
+}
+
+impl PostingRules {
+    /// `None` — правил нет.
+    pub fn from_settings(settings: &Value) -> Result<Option<Self>, String> {
+        let Some(raw) = settings.get("posting").filter(|v| !v.is_null()) else {
+            return Ok(None);
+        };
+        let rules: Self = serde_json::from_value(raw.clone()).map_err(|e| format!("posting: {e}"))?;
+        Ok(Some(rules))
+    }
+
+    fn tz(&self) -> Result<Tz, String> {
+        let name = self.timezone.as_deref().map(str::trim).filter(|s| !s.is_empty()).unwrap_or("UTC");
+        name.parse::<Tz>().map_err(|_| format!("unknown timezone: {name}"))
+    }
+
+    /// Тихие часы по местному времени аккаунта. Битые настройки тихими часами не считаются.
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        let Some(quiet) = &self.quiet_hours else {
+            return false;
+        };
+        let (Ok(tz), Ok(from), Ok(to)) = (self.tz(), parse_time(&quiet.from), parse_time(&quiet.to)) else {
+            return false;
+        };
+        let local = now.with_timezone(&tz).time();
+        if from <= to {
+            from <= local && local < to
+        } else {
+            local >= from || local < to
+        }
+    }
+
+    /// Можно ли аккаунту отправить ещё один пост сейчас.
+    fn allows(&self, usage: &AccountUsage, now: DateTime<Utc>) -> bool {
+        let exceeds = |limit: Option<u32>, used: i64| limit.is_some_and(|l| used >= i64::from(l));
+
+        if self.is_quiet(now)
+            || exceeds(self.max_per_hour, usage.last_hour)
+            || exceeds(self.max_per_day, usage.last_day)
+        {
+            return false;
+        }
+        match self.min_spacing_minutes.filter(|m| *m > 0) {
+            // пока пост в работе, момент его выхода неизвестен
+            Some(_) if usage.in_flight > 0 => false,
+            Some(minutes) => usage
+                .last_published_at
+                .is_none_or(|at| at + Duration::minutes(i64::from(minutes)) <= now),
+            None => true,
+        }
+    }
+}
+
+/// Проверка `settings_json.posting` при сохранении аккаунта.
+pub fn validate_posting_rules(settings: &Value) -> Result<(), String> {
+    let Some(rules) = PostingRules::from_settings(settings)? else {
+        return Ok(());
+    };
+    rules.tz()?;
+    if let Some(quiet) = &rules.quiet_hours
+        && parse_time(&quiet.from)? == parse_time(&quiet.to)?
+    {
+        return Err("posting.quiet_hours: from and to must differ".to_string());
+    }
+    if rules.max_per_hour == Some(0) || rules.max_per_day == Some(0) {
+        return Err("posting: limits must be positive, deactivate the account to stop posting".to_string());
+    }
+    // публикации старше суток при захвате не смотрим
+    if rules.min_spacing_minutes.is_some_and(|m| m > 24 * 60) {
+        return Err("posting.min_spacing_minutes must not exceed 1440".to_string());
+    }
+    Ok(())
+}
+
+/// Аккаунты, которым сейчас нельзя публиковать: тихие часы, лимит в час/сутки или интервал
+/// с прошлого поста. Job в `processing` считается отправленным.
+pub fn blocked_accounts(conn: &mut PgConnection, now: DateTime<Utc>) -> Result<Vec<i64>> {
+    let with_rules: Vec<(i64, Value)> = social_accounts::table
+        .filter(social_accounts::is_active.eq(true))
+        .filter(social_accounts::settings_json.has_key("posting"))
+        .select((social_accounts::id, social_accounts::settings_json))
+        .load(conn)?;
+
+    let rules: Vec<(i64, PostingRules)> = with_rules
+        .into_iter()
+        .filter_map(|(id, settings)| Some((id, PostingRules::from_settings(&settings).ok()??)))
+        .collect();
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids: Vec<i64> = rules.iter().map(|(id, _)| *id).collect();
+    let usage: HashMap<i64, AccountUsage> = diesel::sql_query(
+        r#"
+        SELECT social_account_id,
+               count(*) FILTER (WHERE status = 'processing' OR published_at > $2 - interval '1 hour') AS last_hour,
+               count(*) AS last_day,
+               max(published_at) AS last_published_at,
+               count(*) FILTER (WHERE status = 'processing') AS in_flight
+        FROM social_post_jobs
+        WHERE social_account_id = ANY($1)
+          AND (status = 'processing' OR published_at > $2 - interval '1 day')
+        GROUP BY social_account_id
+        "#,
+    )
+    .bind::<Array<BigInt>, _>(&ids)
+    .bind::<Timestamptz, _>(now)
+    .load::<AccountUsage>(conn)?
+    .into_iter()
+    .map(|u| (u.social_account_id, u))
+    .collect();
+
+    Ok(rules
+        .into_iter()
+        .filter(|(id, rules)| !rules.allows(usage.get(id).unwrap_or(&AccountUsage::default()), now))
+        .map(|(id, _)| id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use super::*;
+
+    fn rules(posting: Value) -> PostingRules {
+        PostingRules::from_settings(&json!({ "posting": posting })).unwrap().unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight_in_account_timezone() {
+        let night = rules(json!({ "quiet_hours": { "from": "23:00", "to": "08:00" }, "timezone": "Europe/Moscow" }));
+        // 21:30 UTC = 00:30 МСК
+        assert!(night.is_quiet(Utc.with_ymd_and_hms(2026, 3, 23, 21, 30, 0).unwrap()));
+        // 05:00 UTC = 08:00 МСК — уже можно
+        assert!(!night.is_quiet(Utc.with_ymd_and_hms(2026, 3, 23, 5, 0, 0).unwrap()));
+        assert!(!night.is_quiet(Utc.with_ymd_and_hms(2026, 3, 23, 12, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn spacing_and_limits() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 23, 12, 0, 0).unwrap();
+        let r = rules(json!({ "max_per_day": 3, "min_spacing_minutes": 30 }));
+        let usage = |last_day, minutes_ago: i64, in_flight| AccountUsage {
+            last_day,
+            last_published_at: Some(now - Duration::minutes(minutes_ago)),
+            in_flight,
+            ..Default::default()
+        };
+
+        assert!(r.allows(&usage(1, 45, 0), now));
+        assert!(!r.allows(&usage(1, 10, 0), now));
+        assert!(!r.allows(&usage(1, 45, 1), now));
+        assert!(!r.allows(&usage(3, 45, 0), now));
+        assert!(r.allows(&AccountUsage::default(), now));
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let check = |posting: Value| validate_posting_rules(&json!({ "posting": posting }));
+        assert!(check(json!({ "max_per_hour": 2, "timezone": "Asia/Yekaterinburg" })).is_ok());
+        assert!(check(json!({ "timezone": "Mars/Olympus" })).is_err());
+        assert!(check(json!({ "quiet_hours": { "from": "25:00", "to": "08:00" } })).is_err());
+        assert!(check(json!({ "max_per_hour": 0 })).is_err());
+        assert!(check(json!({ "max_per_huor": 2 })).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+
+use crate::social::adapters::{Capabilities, MediaItem};
+use crate::social::errors::{ErrorKind, ProviderError};
+
+/// Внешний адрес сервера, по которому соцсети скачивают наши файлы (`https://example.com`).
+const PUBLIC_BASE_URL_ENV: &str = "PUBLIC_BASE_URL";
+/// Ключ подписи ссылок `/media/...`.
+const MEDIA_URL_SECRET_ENV: &str = "MEDIA_URL_SECRET";
+/// Сколько живёт подписанная ссылка: провайдер скачивает файл при создании контейнера,
+/// а повтор job'а подписывает ссылку заново.
+const SIGNED_URL_TTL_MINUTES: i64 = 60;
+
+/// Картинка, готовая к загрузке провайдеру байтами.
+#[derive(Debug, Clone)]
+pub struct ImageBytes {
+    pub bytes: Vec<u8>,
+    pub file_name: String,
+    pub mime: &'static str,
+}
+
+/// Каталог, который сервер раздаёт как `/images`.
+pub const IMAGES_DIR: &str = "images";
+
+pub fn is_remote(image_url: &str) -> bool {
+    image_url.starts_with("http://") || image_url.starts_with("https://")
+}
+
+/// `/images/idioms/B1/x.png` или `images/idioms/B1/x.png` → путь внутри `IMAGES_DIR`.
+/// Всё, что выходит за каталог (`..`, абсолютные пути вне `/images`), — `None`.
+pub fn local_image_path(image_url: &str) -> Option<PathBuf> {
+    local_rel_path(image_url).map(|rel| Path::new(IMAGES_DIR).join(rel))
+}
+
+/// Часть ссылки после `images/`, если она не выходит за каталог.
+fn local_rel_path(image_url: &str) -> Option<&str> {
+    let rel = image_url.trim_start_matches('/').strip_prefix("images/")?;
+    Path::new(rel)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then_some(rel)
+}
+
+pub fn mime_for(file_name: &str) -> &'static str {
+    let ext = file_name.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        _ => "image/jpeg",
+    }
+}
+
+/// Байты картинки по `image_url` новости: скачивает удалённую или читает локальную из `images/`.
+pub async fn load_image(client: &Client, image_url: &str) -> Result<ImageBytes> {
+    let file_name = image_url
+        .split(['?', '#'])
+        .next()
+        .and_then(|p| p.rsplit('/').next())
+        .filter(|n| !n.is_empty())
+        .unwrap_or("image.jpg")
+        .to_string();
+
+    let bytes = if is_remote(image_url) {
+        let resp = client.get(image_url).send().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            // 404 и т.п. повтором не исправить, 5xx — может быть временным
+            let kind = if status.is_server_error() { ErrorKind::Transient } else { ErrorKind::Permanent };
+            return Err(ProviderError::new(kind, format!("image download {image_url}: HTTP {status}")).into());
+        }
+        resp.bytes().await?.to_vec()
+    } else {
+        let path = local_image_path(image_url)
+            .ok_or_else(|| ProviderError::permanent(format!("image path is outside images/: {image_url}")))?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ProviderError::permanent(format!("image file not found: {}", path.display())).into());
+            }
+            Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+        }
+    };
+
+    Ok(ImageBytes {
+        mime: mime_for(&file_name),
+        bytes,
+        file_name,
+    })
+}
+
+fn signing_key() -> Option<Vec<u8>> {
+    std::env::var(MEDIA_URL_SECRET_ENV)
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(String::into_bytes)
+}
+
+pub(crate) fn public_base_url() -> Option<String> {
+    std::env::var(PUBLIC_BASE_URL_ENV)
+        .ok()
+        .map(|u| u.trim_end_matches('/').to_string())
+        .filter(|u| !u.is_empty())
+}
+
+/// Можно ли отдавать локальные файлы провайдерам ссылкой.
+pub fn signed_urls_configured() -> bool {
+    public_base_url().is_some() && signing_key().is_some()
+}
+
+fn signature(key: &[u8], rel: &str, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(format!("{rel}:{expires}").as_bytes());
+    mac
+}
+
+/// `/images/idioms/B1/x.png` → `{PUBLIC_BASE_URL}/media/idioms/B1/x.png?expires=..&sig=..`.
+/// `None`, если ссылка не локальная или подпись не настроена.
+pub fn signed_public_url(image_url: &str, now: DateTime<Utc>) -> Option<String> {
+    let rel = local_rel_path(image_url)?;
+    let base = public_base_url()?;
+    let key = signing_key()?;
+
+    let expires = (now + ChronoDuration::minutes(SIGNED_URL_TTL_MINUTES)).timestamp();
+    let sig = hex::encode(signature(&key, rel, expires).finalize().into_bytes());
+    // имена файлов с пробелами и кириллицей — сегменты кодируются
+    let mut url = url::Url::parse(&format!("{base}/media/")).ok()?;
+    url.path_segments_mut().ok()?.pop_if_empty().extend(rel.split('/'));
+    url.query_pairs_mut()
+        .append_pair("expires", &expires.to_string())
+        .append_pair("sig", &sig);
+    Some(url.into())
+}
+
+/// Проверка ссылки из [`signed_public_url`]: подпись верна и срок не вышел.
+pub fn verify_signed_path(rel: &str, expires: i64, sig: &str, now: DateTime<Utc>) -> bool {
+    let Some(key) = signing_key() else {
+        return false;
+    };
+    let Ok(sig) = hex::decode(sig) else {
+        return false;
+    };
+    expires >= now.timestamp() && signature(&key, rel, expires).verify_slice(&sig).is_ok()
+}
+
+/// Локальные файлы для провайдеров, которые сами скачивают медиа, заменяются подписанными ссылками.
+/// Без `PUBLIC_BASE_URL` ссылки остаются как есть — адаптер откажет с понятной ошибкой.
+pub fn publishable_media(capabilities: Capabilities, media: Vec<MediaItem>) -> Vec<MediaItem> {
+    if capabilities.local_media {
+        return media;
+    }
+    let now = Utc::now();
+    media
+        .into_iter()
+        .map(|item| match signed_public_url(&item.url, now) {
+            Some(url) if item.is_local() => MediaItem { url, ..item },
+            _ => item,
+        })
+        .collect()
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use diesel::prelude::*;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::db::DbPool;
+use crate::models::social::{NewSocialPostMetrics, SocialAccount};
+use crate::schema::{social_accounts, social_post_jobs, social_post_metrics};
+use crate::social::accounts::{load_active_accounts, redact_account_secrets};
+use crate::social::adapters::PostMetrics;
+use crate::social::service::SocialPublishers;
+use crate::social::types::SocialProvider;
+
+/// Посты старше этого не опрашиваем: цифры к тому времени почти не меняются.
+const COLLECT_FOR_DAYS: i64 = 30;
+/// Как часто обновлять снимок одного поста.
+const REFRESH_EVERY_HOURS: i64 = 6;
+/// Постов за один проход — чтобы не упереться в лимиты провайдеров.
+const BATCH_SIZE: i64 = 100;
+
+/// Фоновый сбор просмотров/лайков/комментариев опубликованных постов в `social_post_metrics`.
+pub fn spawn_metrics_collector(pool: DbPool) {
+    tokio::spawn(async move {
+        let publishers = SocialPublishers::new();
+
+        let mut interval = time::interval(Duration::from_secs(30 * 60));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            match collect_due_metrics(&pool, &publishers).await {
+                Ok(0) => {}
+                Ok(n) => println!("[metrics] {n} post(s) updated"),
+                Err(err) => eprintln!("[metrics] {err:#}"),
+            }
+        }
+    });
+}
+
+/// Опубликованные job'ы активных аккаунтов, чей снимок устарел: (job, аккаунт, id поста).
+fn due_jobs(conn: &mut PgConnection) -> QueryResult<Vec<(i64, i64, String)>> {
+    let now = Utc::now();
+    social_post_jobs::table
+        .inner_join(social_accounts::table)
+        .left_join(social_post_metrics::table)
+        .filter(social_post_jobs::status.eq("posted"))
+        .filter(social_post_jobs::external_post_id.is_not_null())
+        .filter(social_post_jobs::published_at.gt(now - ChronoDuration::days(COLLECT_FOR_DAYS)))
+        .filter(social_accounts::is_active.eq(true))
+        .filter(
+            social_post_metrics::collected_at
+                .is_null()
+                .or(social_post_metrics::collected_at.lt(now - ChronoDuration::hours(REFRESH_EVERY_HOURS))),
+        )
+        .order(social_post_metrics::collected_at.asc().nulls_first())
+        .limit(BATCH_SIZE)
+        .select((
+            social_post_jobs::id,
+            social_post_jobs::social_account_id,
+            social_post_jobs::external_post_id.assume_not_null(),
+        ))
+        .load(conn)
+}
+
+async fn collect_due_metrics(pool: &DbPool, publishers: &SocialPublishers) -> Result<usize> {
+    let (jobs, accounts) = {
+        let pool = pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<_> {
+            let mut conn = pool.get()?;
+            let jobs = due_jobs(&mut conn)?;
+            let accounts: HashMap<i64, SocialAccount> = load_active_accounts(&mut conn, None)?
+                .into_iter()
+                .map(|a| (a.id, a))
+                .collect();
+            Ok((jobs, accounts))
+        })
+        .await??
+    };
+
+    let mut updated = 0;
+    for (job_id, account_id, external_post_id) in jobs {
+        let Some(account) = accounts.get(&account_id) else {
+            continue;
+        };
+        let Some(provider) = SocialProvider::from_db(&account.provider) else {
+            continue;
+        };
+
+        let fetched = publishers
+            .get(provider)?
+            .fetch_metrics(account, &external_post_id)
+            .await
+            .map_err(|err| redact_account_secrets(&format!("{err:#}"), account));
+        if let Err(msg) = &fetched {
+            eprintln!("[metrics] job #{job_id}: {msg}");
+        }
+
+        let pool = pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut conn = pool.get()?;
+            save_metrics(&mut conn, job_id, fetched)
+        })
+        .await??;
+        updated += 1;
+    }
+
+    Ok(updated)
+}
+
+/// Ошибка не затирает прошлые цифры; `None` (статистики нет) тоже запоминается,
+/// чтобы не спрашивать провайдера каждые полчаса.
+fn save_metrics(
+    conn: &mut PgConnection,
+    job_id: i64,
+    fetched: Result<Option<PostMetrics>, String>,
+) -> Result<()> {
+    let now = Utc::now();
+    match fetched {
+        Ok(metrics) => {
+            let m = metrics.unwrap_or_default();
+            let row = NewSocialPostMetrics {
+                social_post_job_id: job_id,
+                views: m.views,
+                likes: m.likes,
+                reactions: m.reactions,
+                comments: m.comments,
+                shares: m.shares,
+                raw: m.raw,
+                last_error: None,
+                collected_at: now,
+            };
+            diesel::insert_into(social_post_metrics::table)
+                .values(&row)
+                .on_conflict(social_post_metrics::social_post_job_id)
+                .do_update()
+                .set(&row)
+                .execute(conn)?;
+        }
+        Err(msg) => {
+            diesel::insert_into(social_post_metrics::table)
+                .values((
+                    social_post_metrics::social_post_job_id.eq(job_id),
+                    social_post_metrics::last_error.eq(&msg),
+                    social_post_metrics::collected_at.eq(now),
+                ))
+                .on_conflict(social_post_metrics::social_post_job_id)
+                .do_update()
+                .set((
+                    social_post_metrics::last_error.eq(&msg),
+                    social_post_metrics::collected_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+    }
+    Ok(())
+}
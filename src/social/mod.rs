@@ -6,4 +6,18 @@ pub mod telegram_config;
 pub mod threads_config;
 pub mod scheduler;
 pub mod instagram_config;
-pub mod vk_config;
\ No newline at end of file
+pub mod vk_config;
+pub mod accounts;
+pub mod crypto;
+pub mod token_refresher;
+pub mod errors;
+pub mod retry;
+pub mod alerts;
+pub mod campaigns;
+pub mod render;
+pub mod media;
+pub mod metrics;
+pub mod limits;
+pub mod worker_pool;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,336 @@
+use serde_json::Value;
+
+use crate::models::news::NewsPost;
+use crate::models::social::SocialAccount;
+use crate::social::adapters::{Capabilities, MediaItem, PublishCheckpoint, PublishPayload};
+use crate::social::media::publishable_media;
+use crate::social::types::SocialProvider;
+use crate::social_jobs::{DueJobRow, JobPayload};
+
+/// Плейсхолдеры шаблона поста (`settings_json.template` аккаунта).
+pub const PLACEHOLDERS: [&str; 9] = [
+    "title",
+    "excerpt",
+    "body",
+    "explanation",
+    "phonetic",
+    "level",
+    "hashtags",
+    "link",
+    "kind",
+];
+
+/// Поля, которые режутся первыми, когда текст не влезает в лимит провайдера.
+const SHRINKABLE: [&str; 3] = ["body", "explanation", "excerpt"];
+
+/// Больше хештегов Instagram не принимает.
+const INSTAGRAM_MAX_HASHTAGS: usize = 30;
+
+/// Данные новости для шаблона.
+#[derive(Debug, Clone, Default)]
+pub struct PostContext {
+    pub kind: String,
+    pub title: String,
+    pub excerpt: Option<String>,
+    pub body: Option<String>,
+    pub explanation_en: Option<String>,
+    pub phonetic: Option<String>,
+    pub level: Option<String>,
+    pub tags: Vec<String>,
+    pub link: Option<String>,
+}
+
+impl From<&NewsPost> for PostContext {
+    fn from(p: &NewsPost) -> Self {
+        Self {
+            kind: p.kind.clone(),
+            title: p.title.clone(),
+            excerpt: p.excerpt.clone(),
+            body: p.body.clone(),
+            explanation_en: p.explanation_en.clone(),
+            phonetic: p.phonetic.clone(),
+            level: Some(p.level.clone()),
+            tags: p.tags.iter().flatten().cloned().collect(),
+            link: p.url.clone(),
+        }
+    }
+}
+
+impl From<&DueJobRow> for PostContext {
+    fn from(j: &DueJobRow) -> Self {
+        Self {
+            kind: j.kind.clone(),
+            title: j.title.clone().unwrap_or_else(|| "Untitled".to_string()),
+            excerpt: j.excerpt.clone(),
+            body: j.body.clone(),
+            explanation_en: j.explanation_en.clone(),
+            phonetic: j.phonetic.clone(),
+            level: Some(j.level.clone()),
+            tags: j.tags.iter().flatten().cloned().collect(),
+            link: j.url.clone(),
+        }
+    }
+}
+
+/// Готовый текст и сколько он занимает относительно лимита.
+#[derive(Debug, Clone)]
+pub struct Rendered {
+    pub text: String,
+    pub length: usize,
+    pub limit: usize,
+    /// Часть полей обрезана, чтобы уложиться в лимит.
+    pub truncated: bool,
+}
+
+/// Лимит длины текста в символах. У Telegram подпись к фото короче сообщения.
+pub fn text_limit(provider: SocialProvider, has_image: bool) -> usize {
+    match provider {
+        SocialProvider::Telegram if has_image => 1024,
+        SocialProvider::Telegram => 4096,
+        SocialProvider::Vk => 16000,
+        SocialProvider::Instagram => 2200,
+        SocialProvider::Threads => 500,
+        SocialProvider::Sandbox => 4096,
+    }
+}
+
+/// Шаблон по умолчанию: то, что раньше собиралось в `build_social_text_from_due_job`.
+pub fn default_template(provider: SocialProvider) -> &'static str {
+    match provider {
+        SocialProvider::Telegram => "<b>{title}</b>\n\n{excerpt}\n\n{explanation}\n\n{body}\n\n{hashtags}",
+        SocialProvider::Threads => "{title}\n\n{excerpt}\n\n{hashtags}",
+        SocialProvider::Vk | SocialProvider::Instagram | SocialProvider::Sandbox => {
+            "{title}\n\n{excerpt}\n\n{explanation}\n\n{body}\n\n{hashtags}"
+        }
+    }
+}
+
+/// Шаблон аккаунта из `settings_json.template`, иначе дефолтный для провайдера.
+pub fn account_template(account: &SocialAccount, provider: SocialProvider) -> String {
+    account
+        .settings_json
+        .get("template")
+        .and_then(Value::as_str)
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or(default_template(provider))
+        .to_string()
+}
+
+/// Payload для публикации или правки поста: переопределения job'а поверх новости.
+pub fn build_payload(
+    account: &SocialAccount,
+    provider: SocialProvider,
+    caps: Capabilities,
+    ctx: &PostContext,
+    image_url: Option<String>,
+    overrides: JobPayload,
+    checkpoint: PublishCheckpoint,
+) -> PublishPayload {
+    let media = match (overrides.media, overrides.image_url.or(image_url)) {
+        (Some(media), _) => media,
+        // картинка новости иллюстрирует заголовок — он и будет alt-текстом
+        (None, Some(url)) => vec![MediaItem {
+            alt_text: Some(ctx.title.clone()),
+            ..MediaItem::image(url)
+        }],
+        (None, None) => Vec::new(),
+    };
+    // локальные файлы уходят ссылкой на /media тем, кто не умеет загрузку байтами
+    let media = publishable_media(caps, media);
+    // текст из override отправляется как есть, иначе — шаблон аккаунта под провайдера
+    let text = overrides.text.unwrap_or_else(|| {
+        let template = account_template(account, provider);
+        render_post(&template, ctx, provider, !media.is_empty()).text
+    });
+
+    PublishPayload {
+        text,
+        media,
+        link: ctx.link.clone(),
+        hashtags: normalize_tags(&ctx.tags),
+        checkpoint,
+    }
+}
+
+/// Проверка `settings_json.template` при сохранении аккаунта.
+pub fn validate_template(settings: &Value) -> Result<(), String> {
+    let Some(template) = settings.get("template") else {
+        return Ok(());
+    };
+    let template = template.as_str().ok_or("template must be a string")?;
+
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + len];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!("unknown placeholder {{{name}}}"));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
+
+/// Рендер поста под провайдера: Telegram — HTML с экранированием значений,
+/// остальные — plain text без тегов. Не влезающий в лимит текст сначала
+/// теряет хвост body/explanation/excerpt, и только потом обрезается целиком.
+pub fn render_post(
+    template: &str,
+    ctx: &PostContext,
+    provider: SocialProvider,
+    has_image: bool,
+) -> Rendered {
+    let limit = text_limit(provider, has_image);
+    let mut values = placeholder_values(ctx, provider);
+    let mut truncated = false;
+
+    let mut text = fill(template, &values, provider);
+    for field in SHRINKABLE {
+        let Some(idx) = values.iter().position(|(name, _)| *name == field) else {
+            continue;
+        };
+        // экранирование HTML удлиняет значение, поэтому за один проход может не хватить
+        loop {
+            let overflow = text.chars().count().saturating_sub(limit);
+            let value = &mut values[idx].1;
+            if overflow == 0 || value.is_empty() {
+                break;
+            }
+            let keep = value.chars().count().saturating_sub(overflow + 1);
+            *value = shorten(value, keep);
+            truncated = true;
+            text = fill(template, &values, provider);
+        }
+    }
+
+    if text.chars().count() > limit {
+        // обрезка посреди HTML сломала бы разметку — в крайнем случае отдаём текст без неё
+        let plain = if provider == SocialProvider::Telegram {
+            escape_html(&strip_tags(&fill(template, &values, SocialProvider::Vk)))
+        } else {
+            text
+        };
+        text = shorten(&plain, limit.saturating_sub(1));
+        truncated = true;
+    }
+
+    Rendered {
+        length: text.chars().count(),
+        text,
+        limit,
+        truncated,
+    }
+}
+
+fn placeholder_values(ctx: &PostContext, provider: SocialProvider) -> Vec<(&'static str, String)> {
+    let opt = |v: &Option<String>| v.as_deref().map(str::trim).unwrap_or_default().to_string();
+    vec![
+        ("title", ctx.title.trim().to_string()),
+        ("excerpt", opt(&ctx.excerpt)),
+        ("body", opt(&ctx.body)),
+        ("explanation", opt(&ctx.explanation_en)),
+        ("phonetic", opt(&ctx.phonetic)),
+        ("level", opt(&ctx.level)),
+        ("hashtags", hashtags(&ctx.tags, provider)),
+        ("link", opt(&ctx.link)),
+        ("kind", ctx.kind.clone()),
+    ]
+}
+
+/// `#tag` из тегов новости: пробелы и пунктуация выкидываются.
+/// Threads превращает в тему только один хештег, Instagram принимает не больше 30.
+fn hashtags(tags: &[String], provider: SocialProvider) -> String {
+    let max = match provider {
+        SocialProvider::Threads => 1,
+        SocialProvider::Instagram => INSTAGRAM_MAX_HASHTAGS,
+        _ => usize::MAX,
+    };
+
+    normalize_tags(tags)
+        .iter()
+        .take(max)
+        .map(|t| format!("#{t}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Теги новости без `#`, пробелов и пунктуации, без повторов.
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut seen = Vec::new();
+    for tag in tags {
+        let tag: String = tag.chars().filter(|c| c.is_alphanumeric() || *c == '_').collect();
+        if !tag.is_empty() && !seen.contains(&tag) {
+            seen.push(tag);
+        }
+    }
+    seen
+}
+
+fn fill(template: &str, values: &[(&str, String)], provider: SocialProvider) -> String {
+    let html = provider == SocialProvider::Telegram;
+    // разметка из шаблона у не-HTML провайдеров пришла бы буквальными <b>
+    let mut text = if html { template.to_string() } else { strip_tags(template) };
+
+    for (name, value) in values {
+        let value = if html { escape_html(value) } else { strip_tags(value) };
+        text = text.replace(&format!("{{{name}}}"), &value);
+    }
+    collapse_blank_lines(&text)
+}
+
+/// Пустые плейсхолдеры оставляют дыры — схлопываем до одной пустой строки.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && out.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        out.push(line);
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+/// Обрезает до `keep` символов по границе слова, добавляя `…`.
+fn shorten(text: &str, keep: usize) -> String {
+    if text.chars().count() <= keep {
+        return text.to_string();
+    }
+    if keep == 0 {
+        return String::new();
+    }
+    let cut: String = text.chars().take(keep).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(i) if i > cut.len() / 2 => &cut[..i],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end())
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Убирает HTML-теги; одиночные `<` в тексте («a < b») не трогает.
+fn strip_tags(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let is_tag = tail[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/');
+        match tail.find('>') {
+            Some(end) if is_tag => rest = &tail[end + 1..],
+            _ => {
+                out.push('<');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
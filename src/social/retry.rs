@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::social::errors::ErrorKind;
+use crate::social::types::SocialProvider;
+
+/// Политика повторов job'ов одного провайдера.
+///
+/// Значения по умолчанию можно переопределить через env:
+/// `SOCIAL_RETRY_<PROVIDER>_MAX_ATTEMPTS`, `..._BASE_SECS`, `..._MAX_SECS`
+/// (например `SOCIAL_RETRY_INSTAGRAM_MAX_ATTEMPTS=6`).
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Всего попыток, включая первую.
+    pub max_attempts: i32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    RetryIn(Duration),
+    /// Терминальное состояние `dead`.
+    Dead,
+}
+
+fn env_u64(provider: SocialProvider, name: &str) -> Option<u64> {
+    let key = format!("SOCIAL_RETRY_{}_{name}", provider.as_str().to_uppercase());
+    std::env::var(key).ok()?.trim().parse().ok()
+}
+
+impl RetryPolicy {
+    pub fn for_provider(provider: SocialProvider) -> Self {
+        // у Meta лимиты почасовые, поэтому паузы длиннее
+        let (max_attempts, base, max) = match provider {
+            SocialProvider::Telegram => (5, 30, 60 * 60),
+            SocialProvider::Vk => (5, 60, 2 * 60 * 60),
+            SocialProvider::Instagram | SocialProvider::Threads => (4, 120, 6 * 60 * 60),
+            // в песочнице сеть не участвует — повтор даст ту же ошибку
+            SocialProvider::Sandbox => (1, 1, 1),
+        };
+
+        Self {
+            max_attempts: env_u64(provider, "MAX_ATTEMPTS").map_or(max_attempts, |v| v.max(1) as i32),
+            base_delay: Duration::from_secs(env_u64(provider, "BASE_SECS").unwrap_or(base)),
+            max_delay: Duration::from_secs(env_u64(provider, "MAX_SECS").unwrap_or(max)),
+        }
+    }
+
+    /// Экспоненциальная пауза после попытки `attempt_no` (с 1) с jitter в [d/2, d].
+    pub fn backoff(&self, attempt_no: i32) -> Duration {
+        let exp = attempt_no.clamp(1, 20) as u32 - 1;
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exp.min(16))
+            .min(self.max_delay);
+
+        let half = delay.as_millis() as u64 / 2;
+        let jitter = if half == 0 { 0 } else { (Uuid::new_v4().as_u128() % half as u128) as u64 };
+        Duration::from_millis(half + jitter)
+    }
+
+    /// Что делать после неудачной попытки `attempt_no`.
+    pub fn decide(&self, kind: ErrorKind, attempt_no: i32, retry_after: Option<Duration>) -> RetryDecision {
+        match kind {
+            ErrorKind::Auth | ErrorKind::Permanent => RetryDecision::Dead,
+            _ if attempt_no >= self.max_attempts => RetryDecision::Dead,
+            ErrorKind::RateLimited => {
+                RetryDecision::RetryIn(self.backoff(attempt_no).max(retry_after.unwrap_or_default()))
+            }
+            ErrorKind::Transient => RetryDecision::RetryIn(self.backoff(attempt_no)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// Пауза без jitter'а лежит в [d/2, d].
+    fn assert_jittered(actual: Duration, full: Duration) {
+        assert!(actual >= full / 2 && actual <= full, "{actual:?} is not within [{:?}, {full:?}]", full / 2);
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max_delay() {
+        let policy = policy();
+        for _ in 0..50 {
+            assert_jittered(policy.backoff(1), Duration::from_secs(10));
+            assert_jittered(policy.backoff(2), Duration::from_secs(20));
+            assert_jittered(policy.backoff(3), Duration::from_secs(40));
+            assert_jittered(policy.backoff(4), Duration::from_secs(60));
+            assert_jittered(policy.backoff(30), Duration::from_secs(60));
+        }
+    }
+
+    #[test]
+    fn retry_after_overrides_backoff_for_rate_limit() {
+        let policy = policy();
+        let retry_after = Some(Duration::from_secs(600));
+
+        assert_eq!(
+            policy.decide(ErrorKind::RateLimited, 1, retry_after),
+            RetryDecision::RetryIn(Duration::from_secs(600))
+        );
+        let RetryDecision::RetryIn(delay) = policy.decide(ErrorKind::RateLimited, 1, None) else {
+            panic!("rate limit must be retried");
+        };
+        assert_jittered(delay, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn auth_permanent_and_last_attempt_are_dead() {
+        let policy = policy();
+
+        assert_eq!(policy.decide(ErrorKind::Auth, 1, None), RetryDecision::Dead);
+        assert_eq!(policy.decide(ErrorKind::Permanent, 1, None), RetryDecision::Dead);
+        assert!(matches!(policy.decide(ErrorKind::Transient, 4, None), RetryDecision::RetryIn(_)));
+        assert_eq!(policy.decide(ErrorKind::Transient, 5, None), RetryDecision::Dead);
+        assert_eq!(policy.decide(ErrorKind::RateLimited, 6, Some(Duration::from_secs(1))), RetryDecision::Dead);
+    }
+}
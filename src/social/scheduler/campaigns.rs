@@ -0,0 +1,33 @@
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::db::DbPool;
+use crate::social::campaigns::run_due_campaigns;
+
+/// Раз в минуту ставит в очередь job'ы наступивших кампаний; публикует их обычный worker.
+pub fn spawn_campaign_scheduler(pool: DbPool) {
+    tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(60));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            let pool = pool.clone();
+            let ran = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<(i64, usize)>> {
+                let mut conn = pool.get()?;
+                Ok(run_due_campaigns(&mut conn)?)
+            })
+            .await;
+
+            match ran {
+                Ok(Ok(ran)) => {
+                    for (campaign_id, enqueued) in ran {
+                        println!("[campaigns] campaign #{campaign_id}: {enqueued} job(s) enqueued");
+                    }
+                }
+                Ok(Err(err)) => eprintln!("[campaigns] {err:#}"),
+                Err(err) => eprintln!("[campaigns] scheduler task failed: {err}"),
+            }
+        }
+    });
+}
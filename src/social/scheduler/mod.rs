@@ -1,4 +1,28 @@
-pub mod telegram_hourly;
-pub mod threads_hourly;
-pub mod instagram_hourly;
-pub mod vk_hourly;
\ No newline at end of file
+pub mod campaigns;
+
+use crate::db::DbPool;
+use crate::models::social::SocialAccount;
+use crate::social::accounts::load_active_accounts;
+use crate::social::types::SocialProvider;
+
+/// Активные аккаунты провайдера на момент тика (список можно менять через admin API без рестарта).
+pub async fn active_accounts(pool: &DbPool, provider: SocialProvider) -> Vec<SocialAccount> {
+    let pool = pool.clone();
+    let loaded = tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<SocialAccount>> {
+        let mut conn = pool.get()?;
+        load_active_accounts(&mut conn, Some(provider))
+    })
+    .await;
+
+    match loaded {
+        Ok(Ok(accounts)) => accounts,
+        Ok(Err(err)) => {
+            eprintln!("[scheduler] failed to load {} accounts: {err:#}", provider.as_str());
+            Vec::new()
+        }
+        Err(err) => {
+            eprintln!("[scheduler] failed to load {} accounts: {err}", provider.as_str());
+            Vec::new()
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use anyhow::{ Result};
 
 use crate::social::adapters::instagram::InstagramPublisher;
+use crate::social::adapters::sandbox::SandboxPublisher;
 use crate::social::adapters::telegram::TelegramPublisher;
 use crate::social::adapters::threads::ThreadsPublisher;
 use crate::social::adapters::vk::VkPublisher;
@@ -15,6 +16,7 @@ pub struct SocialPublishers {
     vk: Arc<VkPublisher>,
     instagram: Arc<InstagramPublisher>,
     threads: Arc<ThreadsPublisher>,
+    sandbox: Arc<SandboxPublisher>,
 }
 
 impl SocialPublishers {
@@ -24,6 +26,7 @@ impl SocialPublishers {
             vk: Arc::new(VkPublisher::new()),
             instagram: Arc::new(InstagramPublisher::new()),
             threads: Arc::new(ThreadsPublisher::new()),
+            sandbox: Arc::new(SandboxPublisher),
         }
     }
 
@@ -33,6 +36,7 @@ impl SocialPublishers {
             SocialProvider::Vk => Ok(self.vk.clone()),
             SocialProvider::Instagram => Ok(self.instagram.clone()),
             SocialProvider::Threads => Ok(self.threads.clone()),
+            SocialProvider::Sandbox => Ok(self.sandbox.clone()),
         }
     }
 }
\ No newline at end of file
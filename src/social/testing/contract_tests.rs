@@ -0,0 +1,242 @@
+//! Контракт адаптеров: какие запросы уходят провайдеру и как классифицируются его ответы.
+
+use std::time::Duration;
+
+use axum::http::Method;
+
+use crate::social::adapters::instagram::InstagramPublisher;
+use crate::social::adapters::telegram::TelegramPublisher;
+use crate::social::adapters::threads::ThreadsPublisher;
+use crate::social::adapters::vk::VkPublisher;
+use crate::social::adapters::{
+    MediaItem, PublishCheckpoint, PublishPayload, PublishResult, PublishedPost, Recovery, SocialPublisher,
+};
+use crate::social::errors::{classify, may_have_reached_provider, ErrorKind};
+use crate::social::testing::{fixture, MockServer};
+
+fn text_payload(text: &str) -> PublishPayload {
+    PublishPayload {
+        text: text.to_string(),
+        link: Some("https://example.com/news/1".to_string()),
+        ..Default::default()
+    }
+}
+
+fn image_payload(text: &str) -> PublishPayload {
+    PublishPayload {
+        text: text.to_string(),
+        media: vec![MediaItem::image("https://example.com/images/1.jpg")],
+        ..Default::default()
+    }
+}
+
+/// Класс ошибки; ответ провайдера с ошибкой не должен считаться «возможно опубликованным».
+fn failure(result: anyhow::Result<PublishResult>) -> (ErrorKind, Option<Duration>) {
+    let err = result.expect_err("publish must fail");
+    assert!(!may_have_reached_provider(&err), "provider answered, post is known not to exist: {err:#}");
+    classify(&err)
+}
+
+#[tokio::test]
+async fn telegram_publishes_text_message() {
+    let mock = MockServer::start().await;
+    mock.on(Method::POST, "/botmock-token/sendMessage", fixture("telegram", "send_message_ok"));
+    let account = mock.account("telegram", "@mock_channel");
+
+    let result = TelegramPublisher::new().publish(&account, text_payload("Hello")).await.unwrap();
+
+    assert_eq!(result.external_post_id.as_deref(), Some("4242"));
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].body.contains("\"chat_id\":\"@mock_channel\""));
+    assert!(requests[0].body.contains("\"link_preview_options\""));
+}
+
+#[tokio::test]
+async fn telegram_classifies_errors() {
+    let cases = [
+        ("rate_limited", ErrorKind::RateLimited, Some(Duration::from_secs(7))),
+        ("unauthorized", ErrorKind::Auth, None),
+        ("chat_not_found", ErrorKind::Permanent, None),
+        ("bad_gateway", ErrorKind::Transient, None),
+    ];
+    for (name, kind, retry_after) in cases {
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, "/botmock-token/sendMessage", fixture("telegram", name));
+        let account = mock.account("telegram", "@mock_channel");
+
+        let result = TelegramPublisher::new().publish(&account, text_payload("Hello")).await;
+        assert_eq!(failure(result), (kind, retry_after), "fixture telegram/{name}");
+    }
+}
+
+#[tokio::test]
+async fn telegram_succeeds_after_rate_limit() {
+    let mock = MockServer::start().await;
+    mock.on(Method::POST, "/botmock-token/sendMessage", fixture("telegram", "rate_limited"))
+        .on(Method::POST, "/botmock-token/sendMessage", fixture("telegram", "send_message_ok"));
+    let account = mock.account("telegram", "@mock_channel");
+    let publisher = TelegramPublisher::new();
+
+    assert!(publisher.publish(&account, text_payload("Hello")).await.is_err());
+    let result = publisher.publish(&account, text_payload("Hello")).await.unwrap();
+    assert_eq!(result.external_post_id.as_deref(), Some("4242"));
+    assert_eq!(mock.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn telegram_deletes_every_album_message_and_edits_its_caption() {
+    let mock = MockServer::start().await;
+    mock.on(Method::POST, "/botmock-token/sendMediaGroup", fixture("telegram", "send_media_group_ok"))
+        .on(Method::POST, "/botmock-token/editMessageCaption", fixture("telegram", "ok_true"))
+        .on(Method::POST, "/botmock-token/deleteMessages", fixture("telegram", "ok_true"));
+    let account = mock.account("telegram", "@mock_channel");
+    let publisher = TelegramPublisher::new();
+
+    let mut payload = image_payload("Hello");
+    payload.media.push(MediaItem::image("https://example.com/images/2.jpg"));
+    payload.media.push(MediaItem::image("https://example.com/images/3.jpg"));
+    let result = publisher.publish(&account, payload).await.unwrap();
+    assert_eq!(result.external_post_id.as_deref(), Some("4250"));
+
+    let post = PublishedPost {
+        external_post_id: "4250",
+        state: result.published_state.as_ref(),
+    };
+    // у новости картинку уже убрали, но у поста текст — всё равно подпись
+    publisher.edit(&account, post, &text_payload("Fixed")).await.unwrap();
+    publisher.delete(&account, post).await.unwrap();
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].path.ends_with("/editMessageCaption"));
+    assert!(requests[2].body.contains("\"message_ids\":[4250,4251,4252]"));
+}
+
+#[tokio::test]
+async fn vk_posts_to_wall() {
+    let mock = MockServer::start().await;
+    mock.on(Method::POST, "/method/wall.post", fixture("vk", "wall_post_ok"));
+    let account = mock.account("vk", "-123456");
+
+    let result = VkPublisher::new().publish(&account, text_payload("Привет")).await.unwrap();
+
+    assert_eq!(result.external_post_id.as_deref(), Some("1017"));
+    let body = &mock.requests()[0].body;
+    assert!(body.contains("owner_id=-123456"));
+    // без фото вложением идёт ссылка
+    assert!(body.contains("attachments=https%3A%2F%2Fexample.com%2Fnews%2F1"));
+}
+
+#[tokio::test]
+async fn vk_classifies_errors() {
+    let cases = [
+        ("rate_limited", ErrorKind::RateLimited),
+        ("auth_failed", ErrorKind::Auth),
+        ("server_error", ErrorKind::Transient),
+    ];
+    for (name, kind) in cases {
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, "/method/wall.post", fixture("vk", name));
+        let account = mock.account("vk", "-123456");
+
+        let result = VkPublisher::new().publish(&account, text_payload("Привет")).await;
+        assert_eq!(failure(result).0, kind, "fixture vk/{name}");
+    }
+}
+
+fn instagram_mock(mock: &MockServer) {
+    mock.on(Method::POST, "/v24.0/1784000/media", fixture("instagram", "container_created"))
+        .on(Method::GET, "/17889455560051444", fixture("instagram", "container_finished"));
+}
+
+#[tokio::test]
+async fn instagram_publishes_container() {
+    let mock = MockServer::start().await;
+    instagram_mock(&mock);
+    mock.on(Method::POST, "/v24.0/1784000/media_publish", fixture("instagram", "media_published"));
+    let account = mock.account("instagram", "1784000");
+
+    let result = InstagramPublisher::new().publish(&account, image_payload("Caption")).await.unwrap();
+
+    assert_eq!(result.external_post_id.as_deref(), Some("17920238422030506"));
+    let requests = mock.requests();
+    let calls: Vec<(Method, &str)> = requests.iter().map(|r| (r.method.clone(), r.path.as_str())).collect();
+    assert_eq!(
+        calls,
+        [
+            (Method::POST, "/v24.0/1784000/media"),
+            (Method::GET, "/17889455560051444"),
+            (Method::POST, "/v24.0/1784000/media_publish"),
+        ]
+    );
+    // статус контейнера ждём до публикации
+    assert!(requests[1].query.as_deref().unwrap_or("").contains("fields=status_code"));
+}
+
+#[tokio::test]
+async fn instagram_classifies_errors() {
+    let mock = MockServer::start().await;
+    instagram_mock(&mock);
+    mock.on(Method::POST, "/v24.0/1784000/media_publish", fixture("instagram", "rate_limited"));
+    let account = mock.account("instagram", "1784000");
+    let result = InstagramPublisher::new().publish(&account, image_payload("Caption")).await;
+    assert_eq!(failure(result).0, ErrorKind::RateLimited);
+
+    let mock = MockServer::start().await;
+    mock.on(Method::POST, "/v24.0/1784000/media", fixture("instagram", "token_expired"));
+    let account = mock.account("instagram", "1784000");
+    let result = InstagramPublisher::new().publish(&account, image_payload("Caption")).await;
+    assert_eq!(failure(result).0, ErrorKind::Auth);
+}
+
+#[tokio::test]
+async fn threads_publishes_text_post() {
+    let mock = MockServer::start().await;
+    mock.on(Method::POST, "/v1.0/7700/threads", fixture("threads", "container_created"))
+        .on(Method::POST, "/v1.0/7700/threads_publish", fixture("threads", "published"));
+    let account = mock.account("threads", "7700");
+
+    let result = ThreadsPublisher::new().publish(&account, text_payload("Hello")).await.unwrap();
+
+    assert_eq!(result.external_post_id.as_deref(), Some("18031391083302437"));
+    let requests = mock.requests();
+    assert!(requests[1].body.contains("creation_id=18050206876707110"));
+}
+
+#[tokio::test]
+async fn threads_recovers_id_of_interrupted_post() {
+    let mock = MockServer::start().await;
+    mock.on(Method::GET, "/v1.0/18050206876707110", fixture("threads", "container_published"))
+        .on(Method::GET, "/v1.0/7700/threads", fixture("threads", "user_threads"));
+    let account = mock.account("threads", "7700");
+
+    let mut checkpoint = PublishCheckpoint::default();
+    checkpoint.state = Some(serde_json::json!({ "creation_id": "18050206876707110", "text": "Hello" }));
+    checkpoint.started_at = Some(chrono::Utc::now());
+
+    let recovery = ThreadsPublisher::new().recover(&account, &checkpoint).await.unwrap();
+    let Recovery::Published(result) = recovery else {
+        panic!("container is published: {recovery:?}");
+    };
+    assert_eq!(result.external_post_id.as_deref(), Some("18031391083302437"));
+    assert!(mock.requests()[1].query.as_deref().unwrap_or_default().contains("since="));
+}
+
+#[tokio::test]
+async fn threads_classifies_errors() {
+    let cases = [
+        ("rate_limited", ErrorKind::RateLimited),
+        ("token_expired", ErrorKind::Auth),
+        ("invalid_parameter", ErrorKind::Permanent),
+    ];
+    for (name, kind) in cases {
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, "/v1.0/7700/threads", fixture("threads", "container_created"))
+            .on(Method::POST, "/v1.0/7700/threads_publish", fixture("threads", name));
+        let account = mock.account("threads", "7700");
+
+        let result = ThreadsPublisher::new().publish(&account, text_payload("Hello")).await;
+        assert_eq!(failure(result).0, kind, "fixture threads/{name}");
+    }
+}
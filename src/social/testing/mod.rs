@@ -0,0 +1,165 @@
+//! Mock API провайдеров для тестов: отдаёт записанные ответы из `fixtures/`.
+//! Аккаунт направляется на mock через `settings_json.api_base_url`.
+
+mod contract_tests;
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::State,
+    http::{header, Method, StatusCode, Uri},
+    response::IntoResponse,
+    Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+
+use crate::models::social::SocialAccount;
+
+/// Записанный ответ: `{ "status": 429, "body": {...} }`. Строковое `body` уходит как есть.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    pub status: u16,
+    pub body: Value,
+    /// Задержка ответа — медленный провайдер.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// `fixtures/{provider}/{name}.json`.
+pub fn fixture(provider: &str, name: &str) -> Fixture {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "src/social/testing/fixtures",
+        provider,
+        &format!("{name}.json"),
+    ]
+    .iter()
+    .collect();
+    let raw = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("fixture {}: {e}", path.display()));
+    serde_json::from_str(&raw).unwrap_or_else(|e| panic!("fixture {}: {e}", path.display()))
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub body: String,
+}
+
+#[derive(Default)]
+struct Routes {
+    /// Ответы по очереди; последний повторяется.
+    responses: HashMap<(Method, String), VecDeque<Fixture>>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// HTTP-сервер на 127.0.0.1 со случайным портом; останавливается при drop.
+pub struct MockServer {
+    addr: SocketAddr,
+    routes: Arc<Mutex<Routes>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let routes = Arc::new(Mutex::new(Routes::default()));
+        let app = Router::new().fallback(replay).with_state(routes.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let addr = listener.local_addr().expect("mock server addr");
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, app).await.expect("mock server");
+        });
+
+        Self { addr, routes, handle }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Добавляет ответ на `method path` (путь без query).
+    pub fn on(&self, method: Method, path: &str, fixture: Fixture) -> &Self {
+        self.routes
+            .lock()
+            .unwrap()
+            .responses
+            .entry((method, path.to_string()))
+            .or_default()
+            .push_back(fixture);
+        self
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.routes.lock().unwrap().requests.clone()
+    }
+
+    /// Аккаунт провайдера, который ходит в этот mock.
+    pub fn account(&self, provider: &str, external_account_id: &str) -> SocialAccount {
+        SocialAccount {
+            id: 0,
+            provider: provider.to_string(),
+            account_name: format!("mock {provider}"),
+            external_account_id: Some(external_account_id.to_string()),
+            access_token: Some("mock-token".to_string()),
+            refresh_token: None,
+            token_expires_at: None,
+            settings_json: json!({ "api_base_url": self.base_url() }),
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            token_refreshed_at: None,
+            auth_error: None,
+            auth_error_at: None,
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn replay(
+    State(routes): State<Arc<Mutex<Routes>>>,
+    method: Method,
+    uri: Uri,
+    body: String,
+) -> impl IntoResponse {
+    let fixture = {
+        let mut routes = routes.lock().unwrap();
+        routes.requests.push(RecordedRequest {
+            method: method.clone(),
+            path: uri.path().to_string(),
+            query: uri.query().map(str::to_string),
+            body,
+        });
+        routes
+            .responses
+            .get_mut(&(method.clone(), uri.path().to_string()))
+            .and_then(|queue| if queue.len() > 1 { queue.pop_front() } else { queue.front().cloned() })
+    };
+
+    let Some(fixture) = fixture else {
+        let body = json!({ "mock_error": format!("no fixture for {method} {}", uri.path()) });
+        return (StatusCode::NOT_IMPLEMENTED, [(header::CONTENT_TYPE, "application/json")], body.to_string());
+    };
+
+    if fixture.delay_ms > 0 {
+        tokio::time::sleep(std::time::Duration::from_millis(fixture.delay_ms)).await;
+    }
+    let status = StatusCode::from_u16(fixture.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let (content_type, body) = match fixture.body {
+        Value::String(raw) => ("text/html", raw),
+        json => ("application/json", json.to_string()),
+    };
+    (status, [(header::CONTENT_TYPE, content_type)], body)
+}
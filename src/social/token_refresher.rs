@@ -0,0 +1,144 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use diesel::prelude::*;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::db::DbPool;
+use crate::models::social::SocialAccount;
+use crate::schema::social_accounts;
+use crate::social::accounts::{load_active_accounts, redact_account_secrets};
+use crate::social::alerts::report_account_auth_error;
+use crate::social::adapters::RefreshedToken;
+use crate::social::crypto::seal_token;
+use crate::social::service::SocialPublishers;
+use crate::social::types::SocialProvider;
+
+/// Продлеваем, когда до истечения осталось меньше этого.
+const REFRESH_BEFORE_DAYS: i64 = 10;
+/// Instagram не продлевает токены младше суток.
+const MIN_TOKEN_AGE_HOURS: i64 = 24;
+/// После неудачи повторяем не чаще.
+const RETRY_AFTER_FAILURE_HOURS: i64 = 6;
+
+/// Фоновое продление долгоживущих токенов Instagram/Threads (живут ~60 дней).
+/// При ошибке аккаунт помечается `auth_error`, админам уходит уведомление.
+pub fn spawn_token_refresher(pool: DbPool) {
+    tokio::spawn(async move {
+        let publishers = SocialPublishers::new();
+
+        let mut interval = time::interval(Duration::from_secs(60 * 60));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            // первый tick сразу: после рестарта проверяем токены без ожидания
+            interval.tick().await;
+
+            if let Err(err) = refresh_due_tokens(&pool, &publishers).await {
+                eprintln!("[token_refresher] {err:#}");
+            }
+        }
+    });
+}
+
+fn is_refresh_due(account: &SocialAccount, now: DateTime<Utc>) -> bool {
+    if account.access_token.is_none() {
+        return false;
+    }
+    if account
+        .auth_error_at
+        .is_some_and(|at| at > now - ChronoDuration::hours(RETRY_AFTER_FAILURE_HOURS))
+    {
+        return false;
+    }
+    if account
+        .token_refreshed_at
+        .is_some_and(|at| at > now - ChronoDuration::hours(MIN_TOKEN_AGE_HOURS))
+    {
+        return false;
+    }
+
+    match account.token_expires_at {
+        Some(expires) => expires < now + ChronoDuration::days(REFRESH_BEFORE_DAYS),
+        // срок неизвестен (токен внесён вручную) — продлеваем один раз, чтобы его узнать
+        None => account.token_refreshed_at.is_none(),
+    }
+}
+
+async fn refresh_due_tokens(pool: &DbPool, publishers: &SocialPublishers) -> Result<()> {
+    let now = Utc::now();
+    let accounts = {
+        let pool = pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<Vec<SocialAccount>> {
+            let mut conn = pool.get()?;
+            let mut accounts = load_active_accounts(&mut conn, Some(SocialProvider::Instagram))?;
+            accounts.extend(load_active_accounts(&mut conn, Some(SocialProvider::Threads))?);
+            Ok(accounts)
+        })
+        .await??
+    };
+
+    for account in accounts.into_iter().filter(|a| is_refresh_due(a, now)) {
+        let Some(provider) = SocialProvider::from_db(&account.provider) else {
+            continue;
+        };
+
+        match publishers.get(provider)?.refresh_token(&account).await {
+            Ok(Some(token)) => {
+                save_refreshed_token(pool, &account, token).await?;
+                println!("[token_refresher] {} #{} token refreshed", account.provider, account.id);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let msg = redact_account_secrets(&format!("{err:#}"), &account);
+                eprintln!("[token_refresher] {} #{} refresh failed: {msg}", account.provider, account.id);
+                flag_auth_error(pool, publishers, &account, msg).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn save_refreshed_token(pool: &DbPool, account: &SocialAccount, token: RefreshedToken) -> Result<()> {
+    let pool = pool.clone();
+    let account_id = account.id;
+    let expires_at = token.expires_at.or(account.token_expires_at);
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut conn = pool.get()?;
+        let access_token = seal_token(Some(token.access_token))?;
+        let now = Utc::now();
+
+        diesel::update(social_accounts::table.find(account_id))
+            .set((
+                social_accounts::access_token.eq(access_token),
+                social_accounts::token_expires_at.eq(expires_at),
+                social_accounts::token_refreshed_at.eq(now),
+                social_accounts::auth_error.eq(None::<String>),
+                social_accounts::auth_error_at.eq(None::<DateTime<Utc>>),
+                social_accounts::updated_at.eq(now),
+            ))
+            .execute(&mut conn)?;
+        Ok(())
+    })
+    .await??;
+
+    Ok(())
+}
+
+async fn flag_auth_error(
+    pool: &DbPool,
+    publishers: &SocialPublishers,
+    account: &SocialAccount,
+    error: String,
+) -> Result<()> {
+    let title = format!(
+        "Не удалось продлить токен {} «{}»",
+        account.provider, account.account_name
+    );
+    let body = match account.token_expires_at {
+        Some(expires) => format!("{error}\nТокен истекает {}", expires.format("%Y-%m-%d %H:%M UTC")),
+        None => error.clone(),
+    };
+
+    report_account_auth_error(pool, publishers, account.id, "social.token_refresh_failed", title, body, error).await
+}
@@ -6,6 +6,8 @@ pub enum SocialProvider {
     Vk,
     Instagram,
     Threads,
+    /// Песочница: пост рендерится и пишется в попытку, в сеть ничего не уходит.
+    Sandbox,
 }
 
 impl SocialProvider {
@@ -15,6 +17,7 @@ impl SocialProvider {
             Self::Vk => "vk",
             Self::Instagram => "instagram",
             Self::Threads => "threads",
+            Self::Sandbox => "sandbox",
         }
     }
 
@@ -24,6 +27,7 @@ impl SocialProvider {
             "vk" => Some(Self::Vk),
             "instagram" => Some(Self::Instagram),
             "threads" => Some(Self::Threads),
+            "sandbox" => Some(Self::Sandbox),
             _ => None,
         }
     }
@@ -37,6 +41,12 @@ pub enum SocialJobStatus {
     Posted,
     Failed,
     Cancelled,
+    /// Попытки исчерпаны или ошибка неисправима; вернуть в план можно только вручную.
+    Dead,
+    /// Пост удалён у провайдера из админки.
+    Deleted,
+    /// Dry-run: payload записан в попытку, провайдеру ничего не отправлено.
+    DryRun,
 }
 
 impl SocialJobStatus {
@@ -48,6 +58,9 @@ impl SocialJobStatus {
             Self::Posted => "posted",
             Self::Failed => "failed",
             Self::Cancelled => "cancelled",
+            Self::Dead => "dead",
+            Self::Deleted => "deleted",
+            Self::DryRun => "dry_run",
         }
     }
 }
\ No newline at end of file
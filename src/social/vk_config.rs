@@ -22,5 +22,8 @@ pub fn vk_account_from_env() -> Result<SocialAccount> {
         is_active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        token_refreshed_at: None,
+        auth_error: None,
+        auth_error_at: None,
     })
 }
\ No newline at end of file
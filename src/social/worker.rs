@@ -1,146 +1,485 @@
+use std::sync::Arc;
 use std::time::Duration;
-use crate::models::news::NewsPost;
 
 use anyhow::Result;
+use async_trait::async_trait;
+use diesel::Connection;
+use serde_json::{json, Value};
+use tokio::sync::watch;
+use tokio::task::{JoinError, JoinHandle, JoinSet};
 use tokio::time;
-use tracing::{error};
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 use crate::social_jobs::{
-    claim_one_due_job, insert_attempt, mark_job_failed, mark_job_posted,
+    claim_one_due_job, extend_lease, insert_attempt, mark_job_dead, mark_job_failed, mark_job_posted,
+    mark_job_dry_run, mark_publish_started, reap_expired_leases, release_job, release_leases, save_publish_state,
+    DueJobRow, JobPayload,
 };
 use crate::db::DbPool;
 use crate::models::social::SocialAccount;
-use crate::social::adapters::PublishPayload;
+use crate::social::accounts::{load_account, redact_account_secrets, redact_secrets};
+use crate::social::adapters::{
+    is_dry_run, Capabilities, CheckpointStore, PublishCheckpoint, PublishPayload, Recovery,
+};
+use crate::social::alerts::{report_account_auth_error, report_job_needs_attention};
+use crate::social::errors::{classify, may_have_reached_provider, ErrorKind};
+use crate::social::render::{build_payload, PostContext};
+use crate::social::retry::{RetryDecision, RetryPolicy};
 use crate::social::service::SocialPublishers;
 use crate::social::types::SocialProvider;
+use crate::social::worker_pool::{WorkerConfig, WorkerState, WorkerStatus};
+
+/// Сколько job принадлежит воркеру без heartbeat'а.
+const LEASE: Duration = Duration::from_secs(120);
+const HEARTBEAT_EVERY: Duration = Duration::from_secs(30);
 
 #[derive(Clone)]
 pub struct SocialWorker {
     pool: DbPool,
     publishers: SocialPublishers,
+    /// Пишется в `locked_by`: чужие результаты не перетирают job, который уже забрал reaper.
+    worker_id: String,
+    config: Arc<WorkerConfig>,
+    status: WorkerStatus,
 }
 
 impl SocialWorker {
     pub fn new(pool: DbPool) -> Self {
+        let instance = Uuid::new_v4().simple().to_string();
+        let worker_id = format!("{}-{}", std::process::id(), &instance[..8]);
+        let config = WorkerConfig::from_env();
         Self {
             pool,
             publishers: SocialPublishers::new(),
+            status: WorkerStatus::new(&worker_id, &config),
+            config: Arc::new(config),
+            worker_id,
         }
     }
 
-    pub async fn run_forever(self) {
+    /// Состояние для админки; обновляется, пока worker работает.
+    pub fn status(&self) -> WorkerStatus {
+        self.status.clone()
+    }
+
+    /// Берёт job'ы, пока есть свободные слоты, до сигнала в `shutdown`. После сигнала новые job'ы
+    /// не берутся: публикации в работе дописываются (не дольше `shutdown_grace`), оставшиеся
+    /// аренды сразу возвращаются в очередь.
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
         let mut ticker = time::interval(Duration::from_secs(15));
+        let mut tasks = JoinSet::new();
 
         loop {
-            ticker.tick().await;
+            tokio::select! {
+                // остановка важнее тика и завершившихся job'ов
+                biased;
+                // сигнал или закрытый канал
+                _ = shutdown.changed() => break,
+                _ = ticker.tick() => {
+                    if let Err(err) = self.reap_expired_leases().await {
+                        error!("social worker reaper error: {err:#}");
+                    }
+                }
+                // освободился слот — сразу берём следующую job
+                Some(joined) = tasks.join_next(), if !tasks.is_empty() => log_task_exit(joined),
+            }
 
-            if let Err(err) = self.process_until_empty().await {
+            // сигнал мог прийти, пока обрабатывалась другая ветка
+            if *shutdown.borrow() {
+                break;
+            }
+            let result = self.fill_slots(&mut tasks).await;
+            if let Err(err) = &result {
                 error!("social worker loop error: {err:#}");
             }
+            self.status.polled(result.err().map(|e| format!("{e:#}")));
         }
+
+        self.shutdown(tasks).await;
     }
 
+    async fn shutdown(&self, mut tasks: JoinSet<()>) {
+        self.status.set_state(WorkerState::Draining);
+        info!("social worker {}: stopping, {} job(s) in flight", self.worker_id, tasks.len());
+
+        let drain = async {
+            while let Some(joined) = tasks.join_next().await {
+                log_task_exit(joined);
+            }
+        };
+        if time::timeout(self.config.shutdown_grace, drain).await.is_err() {
+            warn!("social worker {}: in-flight jobs did not finish in time, aborting", self.worker_id);
+            tasks.shutdown().await;
+        }
+
+        let pool = self.pool.clone();
+        let worker_id = self.worker_id.clone();
+        let released = tokio::task::spawn_blocking(move || -> Result<Vec<i64>> {
+            let mut conn = pool.get()?;
+            release_leases(&mut conn, &worker_id)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|r| r);
+        match released {
+            Ok(ids) if !ids.is_empty() => warn!("social jobs returned to queue on shutdown: {ids:?}"),
+            Ok(_) => {}
+            Err(err) => error!("social worker {}: releasing leases failed: {err:#}", self.worker_id),
+        }
+
+        self.status.set_state(WorkerState::Stopped);
+        info!("social worker {} stopped", self.worker_id);
+    }
+
+    async fn reap_expired_leases(&self) -> Result<()> {
+        let pool = self.pool.clone();
+        let reaped = tokio::task::spawn_blocking(move || -> Result<_> {
+            let mut conn = pool.get()?;
+            reap_expired_leases(&mut conn)
+        })
+        .await??;
+
+        for (job_id, decision) in reaped {
+            match decision {
+                RetryDecision::RetryIn(delay) => warn!(
+                    "social job #{job_id}: lease expired, retry in {}s",
+                    delay.as_secs()
+                ),
+                RetryDecision::Dead => warn!("social job #{job_id}: lease expired, attempts exhausted, giving up"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Обрабатывает все наступившие job'ы и ждёт, пока они допишутся.
+    #[cfg(test)]
     async fn process_until_empty(&self) -> Result<()> {
+        let mut tasks = JoinSet::new();
         loop {
+            self.fill_slots(&mut tasks).await?;
+            let Some(joined) = tasks.join_next().await else {
+                return Ok(());
+            };
+            log_task_exit(joined);
+        }
+    }
+
+    /// Забирает job'ы в свободные слоты: не больше `concurrency` всего и лимита на провайдера.
+    async fn fill_slots(&self, tasks: &mut JoinSet<()>) -> Result<()> {
+        while self.status.in_flight_len() < self.config.concurrency {
+            let busy = self.status.busy_providers(&self.config);
             let claimed = {
                 let pool = self.pool.clone();
+                let worker_id = self.worker_id.clone();
                 tokio::task::spawn_blocking(move || -> Result<_> {
                     let mut conn = pool.get()?;
-                    claim_one_due_job(&mut conn)
+                    let Some(job) = claim_one_due_job(&mut conn, &worker_id, LEASE, &busy)? else {
+                        return Ok(None);
+                    };
+                    // креды берём из social_accounts; ошибка расшифровки валит только эту job
+                    let account = load_account(&mut conn, job.social_account_id);
+                    Ok(Some((job, account)))
                 })
                 .await??
             };
 
-            let Some(job) = claimed else {
+            let Some((job, account)) = claimed else {
                 break;
             };
 
-            let provider = match SocialProvider::from_db(&job.provider) {
-                Some(p) => p,
-                None => {
-                    let msg = format!("unknown provider: {}", job.provider);
-                    self.fail_job(job.job_id, msg, None).await?;
-                    continue;
+            let in_flight = self.status.start(job.job_id, &job.provider, job.social_account_id);
+            let worker = self.clone();
+            tasks.spawn(async move {
+                let _in_flight = in_flight;
+                let job_id = job.job_id;
+                if let Err(err) = worker.process_job(job, account).await {
+                    error!("social job #{job_id}: {err:#}");
                 }
-            };
+            });
+        }
 
-            let publisher = self.publishers.get(provider)?;
-
-            let account = SocialAccount {
-                id: 0,
-                provider: job.provider.clone(),
-                account_name: job.account_name.clone(),
-                external_account_id: job.external_account_id.clone(),
-                access_token: job.access_token.clone(),
-                refresh_token: job.refresh_token.clone(),
-                token_expires_at: None,
-                settings_json: serde_json::json!({}),
-                is_active: true,
-                created_at: chrono::Utc::now(),
-                updated_at: chrono::Utc::now(),
-            };
+        Ok(())
+    }
 
-            let payload = PublishPayload {
-                text: build_social_text_from_due_job(&job),
-                image_url: job.image_url.clone(),
-            };
+    async fn process_job(&self, job: DueJobRow, account: Result<Option<SocialAccount>>) -> Result<()> {
+        let account = match account {
+            Ok(account) => account,
+            Err(err) => {
+                let msg = redact_secrets(&format!("social account {}: {err:#}", job.social_account_id));
+                return self.fail_job(&job, msg, ErrorKind::Auth, RetryDecision::Dead, false).await;
+            }
+        };
+
+        let Some(account) = account else {
+            let msg = format!("social account {} is missing", job.social_account_id);
+            return self.fail_job(&job, msg, ErrorKind::Permanent, RetryDecision::Dead, false).await;
+        };
+        if !account.is_active {
+            // аккаунт выключили между захватом и публикацией: job ждёт его включения
+            let pool = self.pool.clone();
+            let worker_id = self.worker_id.clone();
+            return tokio::task::spawn_blocking(move || -> Result<()> {
+                let mut conn = pool.get()?;
+                release_job(&mut conn, job.job_id, &worker_id)
+            })
+            .await?;
+        }
 
-            match publisher.publish(&account, payload).await {
-                Ok(result) => {
-                    self.complete_job(
+        let Some(provider) = SocialProvider::from_db(&job.provider) else {
+            let msg = format!("unknown provider: {}", job.provider);
+            return self.fail_job(&job, msg, ErrorKind::Permanent, RetryDecision::Dead, false).await;
+        };
+
+        // heartbeat останавливается и при прерванной задаче
+        let _heartbeat = AbortOnDrop(self.spawn_heartbeat(job.job_id));
+        self.publish_job(&job, &account, provider).await
+    }
+
+    /// Продлевает аренду, пока идёт публикация (Instagram может ждать контейнер минуту).
+    fn spawn_heartbeat(&self, job_id: i64) -> JoinHandle<()> {
+        let pool = self.pool.clone();
+        let worker_id = self.worker_id.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(HEARTBEAT_EVERY);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let (pool, worker_id) = (pool.clone(), worker_id.clone());
+                let extended = tokio::task::spawn_blocking(move || -> Result<bool> {
+                    let mut conn = pool.get()?;
+                    extend_lease(&mut conn, job_id, &worker_id, LEASE)
+                })
+                .await;
+                match extended {
+                    Ok(Ok(true)) => {}
+                    Ok(Ok(false)) => {
+                        warn!("social job #{job_id}: lease lost, job was reclaimed");
+                        break;
+                    }
+                    Ok(Err(err)) => warn!("social job #{job_id}: heartbeat failed: {err:#}"),
+                    Err(err) => warn!("social job #{job_id}: heartbeat task failed: {err}"),
+                }
+            }
+        })
+    }
+
+    async fn publish_job(&self, job: &DueJobRow, account: &SocialAccount, provider: SocialProvider) -> Result<()> {
+        let publisher = self.publishers.get(provider)?;
+        let attempt_no = job.retry_count + 1;
+
+        let checkpoint = PublishCheckpoint::new(
+            job.idempotency_key.to_string(),
+            job.publish_state.clone(),
+            job.publish_started_at,
+            Arc::new(JobCheckpointStore {
+                pool: self.pool.clone(),
+                job_id: job.job_id,
+            }),
+        );
+
+        // прошлая попытка оборвалась после отправки запроса: сначала выясняем, не вышел ли пост
+        if job.publish_started_at.is_some() {
+            match publisher.recover(account, &checkpoint).await {
+                Ok(Recovery::Published(result)) => {
+                    info!("social job #{} was already published before the interruption", job.job_id);
+                    let response = result.raw_response.map(|r| redact_account_secrets(&r, account));
+                    return self.complete_job(job, result.external_post_id, result.published_state, response).await;
+                }
+                Ok(Recovery::NotPublished) => {}
+                Ok(Recovery::Unknown) => {
+                    let msg = "previous attempt was interrupted after the request was sent and the provider \
+                               cannot confirm whether the post went out; check the channel and reschedule \
+                               the job if it is missing"
+                        .to_string();
+                    report_job_needs_attention(
+                        &self.pool,
+                        &self.publishers,
                         job.job_id,
-                        result.external_post_id,
-                        result.raw_response,
+                        "social.publish_interrupted",
+                        format!(
+                            "Публикация в {} «{}» прервана, результат неизвестен",
+                            account.provider, account.account_name
+                        ),
+                        format!("job #{}: проверьте канал и при необходимости перепланируйте job", job.job_id),
                     )
                     .await?;
+                    return self.fail_job(job, msg, ErrorKind::Permanent, RetryDecision::Dead, true).await;
                 }
                 Err(err) => {
-                    self.fail_job(job.job_id, err.to_string(), Some(15)).await?;
+                    let msg = redact_account_secrets(&format!("recovery check failed: {err:#}"), account);
+                    let (kind, retry_after) = classify(&err);
+                    let decision = RetryPolicy::for_provider(provider).decide(kind, attempt_no, retry_after);
+                    return self.fail_job(job, msg, kind, decision, true).await;
+                }
+            }
+        }
+
+        // переопределения для сети, заданные при планировании
+        let overrides: JobPayload = serde_json::from_value(job.payload_json.clone()).unwrap_or_default();
+        let payload = build_payload(
+            account,
+            provider,
+            publisher.capabilities(),
+            &PostContext::from(job),
+            job.image_url.clone(),
+            overrides,
+            checkpoint,
+        );
+
+        if is_dry_run(account) {
+            return self.complete_dry_run(job, provider, publisher.capabilities(), payload).await;
+        }
+
+        {
+            let pool = self.pool.clone();
+            let job_id = job.job_id;
+            tokio::task::spawn_blocking(move || -> Result<()> {
+                let mut conn = pool.get()?;
+                mark_publish_started(&mut conn, job_id)
+            })
+            .await??;
+        }
+
+        match publisher.publish(account, payload).await {
+            Ok(result) => {
+                self.complete_job(
+                    job,
+                    result.external_post_id,
+                    result.published_state,
+                    // ответы провайдеров иногда эхом возвращают токены
+                    result.raw_response.map(|r| redact_account_secrets(&r, account)),
+                )
+                .await?;
+            }
+            Err(err) => {
+                let msg = redact_account_secrets(&format!("{err:#}"), account);
+                let (kind, retry_after) = classify(&err);
+                let decision = RetryPolicy::for_provider(provider).decide(kind, attempt_no, retry_after);
+
+                if kind == ErrorKind::Auth {
+                    let title = format!(
+                        "Публикация в {} «{}» не прошла авторизацию",
+                        account.provider, account.account_name
+                    );
+                    let body = format!("job #{}: {msg}", job.job_id);
+                    report_account_auth_error(
+                        &self.pool,
+                        &self.publishers,
+                        account.id,
+                        "social.publish_auth_failed",
+                        title,
+                        body,
+                        msg.clone(),
+                    )
+                    .await?;
                 }
-            }        }
+
+                self.fail_job(job, msg, kind, decision, may_have_reached_provider(&err)).await?;
+            }
+        }
 
         Ok(())
     }
 
     async fn complete_job(
         &self,
-        job_id: i64,
+        job: &DueJobRow,
         external_post_id: Option<String>,
+        published_state: Option<serde_json::Value>,
         response_body: Option<String>,
     ) -> Result<()> {
         let pool = self.pool.clone();
+        let worker_id = self.worker_id.clone();
+        let (job_id, attempt_no) = (job.job_id, job.retry_count + 1);
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut conn = pool.get()?;
+            conn.transaction(|conn| {
+                insert_attempt(conn, job_id, attempt_no, "success", response_body, None, None)?;
+                mark_job_posted(conn, job_id, &worker_id, external_post_id, published_state)
+            })
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Dry-run: payload проходит те же проверки, что перед публикацией, и вместо запроса
+    /// к провайдеру целиком пишется в `response_body` попытки.
+    async fn complete_dry_run(
+        &self,
+        job: &DueJobRow,
+        provider: SocialProvider,
+        caps: Capabilities,
+        payload: PublishPayload,
+    ) -> Result<()> {
+        if let Err(err) = caps.check(provider, &payload) {
+            return self.fail_job(job, err.to_string(), err.kind, RetryDecision::Dead, false).await;
+        }
+
+        let rendered = json!({ "provider": provider.as_str(), "payload": payload.to_json() }).to_string();
+        let pool = self.pool.clone();
+        let worker_id = self.worker_id.clone();
+        let (job_id, attempt_no) = (job.job_id, job.retry_count + 1);
+        info!("social job #{job_id}: dry run, payload saved to attempt {attempt_no}");
+
         tokio::task::spawn_blocking(move || -> Result<()> {
             let mut conn = pool.get()?;
-            insert_attempt(&mut conn, job_id, 1, "success", response_body, None)?;
-            mark_job_posted(&mut conn, job_id, external_post_id)?;
-            Ok(())
+            conn.transaction(|conn| {
+                insert_attempt(conn, job_id, attempt_no, "dry_run", Some(rendered), None, None)?;
+                mark_job_dry_run(conn, job_id, &worker_id)
+            })
         })
         .await??;
 
         Ok(())
     }
 
+    /// Пишет попытку и по решению политики либо планирует повтор, либо переводит job в `dead`.
+    /// `in_doubt` — запрос мог дойти до провайдера, следующая попытка начнётся с проверки.
     async fn fail_job(
         &self,
-        job_id: i64,
+        job: &DueJobRow,
         error_message: String,
-        retry_after_minutes: Option<i32>,
+        kind: ErrorKind,
+        decision: RetryDecision,
+        in_doubt: bool,
     ) -> Result<()> {
         let pool = self.pool.clone();
-        let error_for_attempt = error_message.clone();
+        let worker_id = self.worker_id.clone();
+        let (job_id, attempt_no) = (job.job_id, job.retry_count + 1);
+
+        match decision {
+            RetryDecision::RetryIn(delay) => warn!(
+                "social job #{job_id} attempt {attempt_no} failed ({}), retry in {}s",
+                kind.as_str(),
+                delay.as_secs()
+            ),
+            RetryDecision::Dead => warn!(
+                "social job #{job_id} attempt {attempt_no} failed ({}), giving up",
+                kind.as_str()
+            ),
+        }
 
         tokio::task::spawn_blocking(move || -> Result<()> {
             let mut conn = pool.get()?;
-            insert_attempt(
-                &mut conn,
-                job_id,
-                1,
-                "failed",
-                None,
-                Some(error_for_attempt),
-            )?;
-            mark_job_failed(&mut conn, job_id, error_message, retry_after_minutes)?;
-            Ok(())
+            conn.transaction(|conn| {
+                insert_attempt(
+                    conn,
+                    job_id,
+                    attempt_no,
+                    "failed",
+                    None,
+                    Some(error_message.clone()),
+                    Some(kind.as_str()),
+                )?;
+                match decision {
+                    RetryDecision::RetryIn(delay) => {
+                        mark_job_failed(conn, job_id, &worker_id, error_message, delay, in_doubt)
+                    }
+                    RetryDecision::Dead => mark_job_dead(conn, job_id, &worker_id, error_message, in_doubt),
+                }
+            })
         })
         .await??;
 
@@ -148,48 +487,484 @@ impl SocialWorker {
     }
 }
 
-fn build_social_text(post: &NewsPost) -> String {
-    let mut parts = Vec::new();
-
-    parts.push(format!("<b>{}</b>", post.title));
+struct AbortOnDrop(JoinHandle<()>);
 
-    if let Some(excerpt) = &post.excerpt {
-        if !excerpt.trim().is_empty() {
-            parts.push(excerpt.clone());
-        }
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
     }
+}
 
-    if let Some(body) = &post.body {
-        if !body.trim().is_empty() {
-            parts.push(body.clone());
-        }
+fn log_task_exit(joined: Result<(), JoinError>) {
+    match joined {
+        Ok(()) => {}
+        Err(err) if err.is_cancelled() => {}
+        Err(err) => error!("social worker task panicked: {err}"),
     }
+}
+
+/// Сохраняет промежуточное состояние адаптера в `social_post_jobs.publish_state`.
+struct JobCheckpointStore {
+    pool: DbPool,
+    job_id: i64,
+}
 
-    parts.join("\n\n")
+#[async_trait]
+impl CheckpointStore for JobCheckpointStore {
+    async fn save(&self, state: Value) -> Result<()> {
+        let pool = self.pool.clone();
+        let job_id = self.job_id;
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut conn = pool.get()?;
+            save_publish_state(&mut conn, job_id, state)
+        })
+        .await?
+    }
 }
 
-fn build_social_text_from_due_job(job: &crate::social_jobs::DueJobRow) -> String {
-    let mut parts = Vec::new();
+/// Цикл worker'а против mock-провайдера. Нужна отдельная БД в `TEST_DATABASE_URL`:
+/// worker забирает любые due job'ы, поэтому на общей БД их не гоняют.
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use chrono::Utc;
+    use diesel::prelude::*;
+    use diesel::r2d2::{self, ConnectionManager};
+    use serde_json::json;
+    use tokio::sync::Mutex;
 
-    parts.push(format!("<b>{}</b>", job.title.as_deref().unwrap_or("Untitled")));
+    use super::*;
+    use crate::models::social::{NewSocialAccount, NewSocialPostJob};
+    use crate::schema::{news_posts, social_accounts, social_post_attempts, social_post_jobs};
+    use crate::social::testing::{fixture, Fixture, MockServer};
+    use crate::social_jobs::{create_social_post_job, OPEN_JOB_STATUSES};
 
-    if let Some(excerpt) = &job.excerpt {
-        if !excerpt.trim().is_empty() {
-            parts.push(excerpt.clone());
-        }
+    /// Тесты делят одну очередь.
+    static QUEUE: Mutex<()> = Mutex::const_new(());
+
+    /// Схема в тестовой БД уже должна быть накатана. Тесты с БД помечены `#[ignore]`:
+    /// запускаются через `cargo test -- --include-ignored` и без `TEST_DATABASE_URL` падают.
+    fn test_pool() -> DbPool {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set for worker tests");
+        let manager = ConnectionManager::<PgConnection>::new(url);
+        r2d2::Pool::builder().max_size(4).build(manager).expect("test database pool")
+    }
+
+    /// Новость, telegram-аккаунт на mock'е и job к публикации; удаляются при drop.
+    struct QueuedJob {
+        pool: DbPool,
+        news_id: Uuid,
+        account_id: i64,
+        job_id: i64,
     }
 
-    if let Some(explanation_en) = &job.explanation_en {
-        if !explanation_en.trim().is_empty() {
-            parts.push(explanation_en.clone());
+    impl QueuedJob {
+        fn create(pool: &DbPool, mock: &MockServer, is_active: bool) -> Self {
+            let mut conn = pool.get().unwrap();
+
+            let queued: i64 = social_post_jobs::table
+                .filter(social_post_jobs::status.eq_any(OPEN_JOB_STATUSES))
+                .count()
+                .get_result(&mut conn)
+                .unwrap();
+            assert_eq!(queued, 0, "TEST_DATABASE_URL must point to a database without queued social jobs");
+
+            let news_id = diesel::insert_into(news_posts::table)
+                .values((
+                    news_posts::title.eq("Worker test"),
+                    news_posts::excerpt.eq("Mock provider run"),
+                    news_posts::url.eq("https://example.com/news/worker-test"),
+                ))
+                .returning(news_posts::id)
+                .get_result(&mut conn)
+                .unwrap();
+
+            let template = mock.account("telegram", "@mock_channel");
+            let account_id = diesel::insert_into(social_accounts::table)
+                .values(&NewSocialAccount {
+                    provider: template.provider,
+                    account_name: template.account_name,
+                    external_account_id: template.external_account_id,
+                    access_token: template.access_token,
+                    refresh_token: None,
+                    token_expires_at: None,
+                    settings_json: template.settings_json,
+                    is_active,
+                })
+                .returning(social_accounts::id)
+                .get_result(&mut conn)
+                .unwrap();
+
+            let job = create_social_post_job(
+                &mut conn,
+                &NewSocialPostJob {
+                    news_post_id: news_id,
+                    social_account_id: account_id,
+                    status: "pending".to_string(),
+                    scheduled_for: Utc::now() - chrono::Duration::minutes(1),
+                    published_at: None,
+                    retry_count: 0,
+                    next_retry_at: None,
+                    external_post_id: None,
+                    error_message: None,
+                    payload_json: json!({}),
+                    campaign_id: None,
+                    priority: 0,
+                },
+            )
+            .unwrap();
+
+            Self { pool: pool.clone(), news_id, account_id, job_id: job.id }
+        }
+
+        /// (status, retry_count, external_post_id, error_message)
+        fn state(&self) -> (String, i32, Option<String>, Option<String>) {
+            social_post_jobs::table
+                .find(self.job_id)
+                .select((
+                    social_post_jobs::status,
+                    social_post_jobs::retry_count,
+                    social_post_jobs::external_post_id,
+                    social_post_jobs::error_message,
+                ))
+                .first(&mut self.pool.get().unwrap())
+                .unwrap()
+        }
+
+        /// (attempt_no, status, error_kind) по порядку.
+        fn attempts(&self) -> Vec<(i32, String, Option<String>)> {
+            social_post_attempts::table
+                .filter(social_post_attempts::social_post_job_id.eq(self.job_id))
+                .order(social_post_attempts::id.asc())
+                .select((
+                    social_post_attempts::attempt_no,
+                    social_post_attempts::status,
+                    social_post_attempts::error_kind,
+                ))
+                .load(&mut self.pool.get().unwrap())
+                .unwrap()
+        }
+
+        fn attempt_body(&self) -> Option<String> {
+            social_post_attempts::table
+                .filter(social_post_attempts::social_post_job_id.eq(self.job_id))
+                .select(social_post_attempts::response_body)
+                .first(&mut self.pool.get().unwrap())
+                .unwrap()
+        }
+
+        fn set_account_settings(&self, settings: serde_json::Value) {
+            diesel::update(social_accounts::table.find(self.account_id))
+                .set(social_accounts::settings_json.eq(settings))
+                .execute(&mut self.pool.get().unwrap())
+                .unwrap();
+        }
+
+        fn make_due(&self) {
+            diesel::update(social_post_jobs::table.find(self.job_id))
+                .set(social_post_jobs::next_retry_at.eq(Utc::now() - chrono::Duration::seconds(1)))
+                .execute(&mut self.pool.get().unwrap())
+                .unwrap();
+        }
+
+        /// Ещё одна наступившая job той же новости в тот же аккаунт.
+        fn add_job(&self, priority: i32) -> i64 {
+            create_social_post_job(
+                &mut self.pool.get().unwrap(),
+                &NewSocialPostJob {
+                    news_post_id: self.news_id,
+                    social_account_id: self.account_id,
+                    status: "scheduled".to_string(),
+                    scheduled_for: Utc::now() - chrono::Duration::minutes(1),
+                    published_at: None,
+                    retry_count: 0,
+                    next_retry_at: None,
+                    external_post_id: None,
+                    error_message: None,
+                    payload_json: json!({}),
+                    campaign_id: None,
+                    priority,
+                },
+            )
+            .unwrap()
+            .id
+        }
+
+        fn status_of(&self, job_id: i64) -> String {
+            social_post_jobs::table
+                .find(job_id)
+                .select(social_post_jobs::status)
+                .first(&mut self.pool.get().unwrap())
+                .unwrap()
         }
     }
 
-    if let Some(body) = &job.body {
-        if !body.trim().is_empty() {
-            parts.push(body.clone());
+    impl Drop for QueuedJob {
+        fn drop(&mut self) {
+            let Ok(mut conn) = self.pool.get() else { return };
+            let _ = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let jobs = social_post_jobs::table
+                    .filter(social_post_jobs::social_account_id.eq(self.account_id))
+                    .select(social_post_jobs::id);
+                diesel::delete(social_post_attempts::table.filter(social_post_attempts::social_post_job_id.eq_any(jobs)))
+                    .execute(conn)?;
+                diesel::delete(social_post_jobs::table.filter(social_post_jobs::social_account_id.eq(self.account_id)))
+                    .execute(conn)?;
+                diesel::delete(social_accounts::table.find(self.account_id)).execute(conn)?;
+                diesel::delete(news_posts::table.find(self.news_id)).execute(conn)?;
+                Ok(())
+            });
         }
     }
 
-    parts.join("\n\n")
-}
\ No newline at end of file
+    const SEND_MESSAGE: &str = "/botmock-token/sendMessage";
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn publishes_job_and_records_attempt() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, SEND_MESSAGE, fixture("telegram", "send_message_ok"));
+        let job = QueuedJob::create(&pool, &mock, true);
+
+        SocialWorker::new(pool.clone()).process_until_empty().await.unwrap();
+
+        assert_eq!(job.state(), ("posted".to_string(), 0, Some("4242".to_string()), None));
+        assert_eq!(job.attempts(), [(1, "success".to_string(), None)]);
+        assert!(mock.requests()[0].body.contains("Worker test"));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn rate_limited_job_is_retried_later() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, SEND_MESSAGE, fixture("telegram", "rate_limited"))
+            .on(Method::POST, SEND_MESSAGE, fixture("telegram", "send_message_ok"));
+        let job = QueuedJob::create(&pool, &mock, true);
+        let worker = SocialWorker::new(pool.clone());
+
+        worker.process_until_empty().await.unwrap();
+        let (status, retry_count, _, error) = job.state();
+        assert_eq!((status.as_str(), retry_count), ("failed", 1));
+        assert!(error.unwrap().contains("Too Many Requests"));
+        let next_retry_at: Option<chrono::DateTime<Utc>> = social_post_jobs::table
+            .find(job.job_id)
+            .select(social_post_jobs::next_retry_at)
+            .first(&mut pool.get().unwrap())
+            .unwrap();
+        // не раньше retry_after из ответа
+        assert!(next_retry_at.unwrap() >= Utc::now() + chrono::Duration::seconds(6));
+
+        // до срока повтора job не забирается
+        worker.process_until_empty().await.unwrap();
+        assert_eq!(mock.requests().len(), 1);
+
+        job.make_due();
+        worker.process_until_empty().await.unwrap();
+        assert_eq!(job.state().0, "posted");
+        assert_eq!(
+            job.attempts(),
+            [
+                (1, "failed".to_string(), Some("rate_limited".to_string())),
+                (2, "success".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn permanent_error_marks_job_dead() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, SEND_MESSAGE, fixture("telegram", "chat_not_found"));
+        let job = QueuedJob::create(&pool, &mock, true);
+
+        SocialWorker::new(pool.clone()).process_until_empty().await.unwrap();
+
+        let (status, _, external_post_id, error) = job.state();
+        assert_eq!((status.as_str(), external_post_id), ("dead", None));
+        assert!(error.unwrap().contains("chat not found"));
+        assert_eq!(job.attempts(), [(1, "failed".to_string(), Some("permanent".to_string()))]);
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn inactive_account_job_waits_in_queue() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        let job = QueuedJob::create(&pool, &mock, false);
+
+        SocialWorker::new(pool.clone()).process_until_empty().await.unwrap();
+
+        assert_eq!(job.state(), ("pending".to_string(), 0, None, None));
+        assert!(job.attempts().is_empty());
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn dry_run_account_saves_payload_without_network() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        let job = QueuedJob::create(&pool, &mock, true);
+        job.set_account_settings(json!({ "api_base_url": mock.base_url(), "dry_run": true }));
+
+        SocialWorker::new(pool.clone()).process_until_empty().await.unwrap();
+
+        assert_eq!(job.state(), ("dry_run".to_string(), 0, None, None));
+        assert_eq!(job.attempts(), [(1, "dry_run".to_string(), None)]);
+        let body: serde_json::Value = serde_json::from_str(&job.attempt_body().unwrap()).unwrap();
+        assert_eq!(body["provider"], "telegram");
+        assert!(body["payload"]["text"].as_str().unwrap().contains("<b>Worker test</b>"));
+        assert_eq!(body["payload"]["link"], "https://example.com/news/worker-test");
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn higher_priority_job_goes_first() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, SEND_MESSAGE, fixture("telegram", "send_message_ok"));
+        let job = QueuedJob::create(&pool, &mock, true);
+        let urgent = job.add_job(10);
+        // один пост в час: уходит только первый захваченный
+        job.set_account_settings(json!({ "api_base_url": mock.base_url(), "posting": { "max_per_hour": 1 } }));
+
+        SocialWorker::new(pool.clone()).process_until_empty().await.unwrap();
+
+        assert_eq!(job.status_of(urgent), "posted");
+        assert_eq!(job.state().0, "pending");
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn quiet_hours_keep_jobs_queued() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        mock.on(Method::POST, SEND_MESSAGE, fixture("telegram", "send_message_ok"));
+        let job = QueuedJob::create(&pool, &mock, true);
+        let now = Utc::now();
+        let quiet = json!({
+            "from": (now - chrono::Duration::hours(1)).format("%H:%M").to_string(),
+            "to": (now + chrono::Duration::hours(1)).format("%H:%M").to_string(),
+        });
+        job.set_account_settings(json!({ "api_base_url": mock.base_url(), "posting": { "quiet_hours": quiet } }));
+        let worker = SocialWorker::new(pool.clone());
+
+        worker.process_until_empty().await.unwrap();
+        assert_eq!(job.state().0, "pending");
+        assert!(mock.requests().is_empty());
+
+        job.set_account_settings(json!({ "api_base_url": mock.base_url() }));
+        worker.process_until_empty().await.unwrap();
+        assert_eq!(job.state().0, "posted");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn shutdown_returns_unfinished_jobs_to_queue() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        let job = QueuedJob::create(&pool, &mock, true);
+        let worker = SocialWorker::new(pool.clone());
+        let status = worker.status();
+
+        // job взята этим worker'ом, но к остановке так и не дописана
+        let claimed = claim_one_due_job(&mut pool.get().unwrap(), &worker.worker_id, LEASE, &[]).unwrap();
+        assert_eq!(claimed.map(|j| j.job_id), Some(job.job_id));
+
+        let (stop, shutdown) = watch::channel(false);
+        let running = tokio::spawn(worker.run(shutdown));
+        stop.send(true).unwrap();
+        running.await.unwrap();
+
+        assert_eq!(job.state().0, "pending");
+        assert_eq!(job.state().1, 0, "shutdown is not a failed attempt");
+        assert_eq!(status.report().state, WorkerState::Stopped);
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn expired_lease_backs_off_and_dies_after_max_attempts() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        let job = QueuedJob::create(&pool, &mock, true);
+        let expire = |retry_count: i32| {
+            diesel::update(social_post_jobs::table.find(job.job_id))
+                .set((
+                    social_post_jobs::status.eq("processing"),
+                    social_post_jobs::retry_count.eq(retry_count),
+                    social_post_jobs::locked_by.eq("crashed-worker"),
+                    social_post_jobs::locked_until.eq(Utc::now() - chrono::Duration::seconds(1)),
+                ))
+                .execute(&mut pool.get().unwrap())
+                .unwrap();
+        };
+        let worker = SocialWorker::new(pool.clone());
+
+        expire(0);
+        worker.reap_expired_leases().await.unwrap();
+        assert_eq!(job.state().0, "failed");
+        let next_retry_at: Option<chrono::DateTime<Utc>> = social_post_jobs::table
+            .find(job.job_id)
+            .select(social_post_jobs::next_retry_at)
+            .first(&mut pool.get().unwrap())
+            .unwrap();
+        assert!(next_retry_at.unwrap() > Utc::now(), "reaped job must back off");
+
+        let max_attempts = RetryPolicy::for_provider(SocialProvider::Telegram).max_attempts;
+        expire(max_attempts - 1);
+        worker.reap_expired_leases().await.unwrap();
+        assert_eq!(job.state().0, "dead");
+        assert_eq!(job.state().1, max_attempts);
+        assert_eq!(job.attempts().len(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn publish_confirmed_after_lease_expired_is_recorded() {
+        let _queue = QUEUE.lock().await;
+        let pool = test_pool();
+        let mock = MockServer::start().await;
+        let slow = Fixture {
+            delay_ms: 1500,
+            ..fixture("telegram", "send_message_ok")
+        };
+        mock.on(Method::POST, SEND_MESSAGE, slow);
+        let job = QueuedJob::create(&pool, &mock, true);
+
+        let worker = SocialWorker::new(pool.clone());
+        let run = tokio::spawn(async move { worker.process_until_empty().await });
+
+        // пока провайдер отвечает, reaper забирает аренду
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        let mut conn = pool.get().unwrap();
+        diesel::update(social_post_jobs::table.find(job.job_id))
+            .set(social_post_jobs::locked_until.eq(Utc::now() - chrono::Duration::seconds(1)))
+            .execute(&mut conn)
+            .unwrap();
+        assert_eq!(reap_expired_leases(&mut conn).unwrap().len(), 1);
+        assert_eq!(job.state().0, "failed");
+
+        run.await.unwrap().unwrap();
+
+        let (status, _, external_post_id, _) = job.state();
+        assert_eq!((status.as_str(), external_post_id.as_deref()), ("posted", Some("4242")));
+        let attempts: Vec<String> = job.attempts().into_iter().map(|(_, status, _)| status).collect();
+        assert_eq!(attempts, ["failed", "success"]);
+    }
+}
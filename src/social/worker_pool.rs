@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::social::types::SocialProvider;
+
+const PROVIDERS: [SocialProvider; 5] = [
+    SocialProvider::Telegram,
+    SocialProvider::Vk,
+    SocialProvider::Instagram,
+    SocialProvider::Threads,
+    SocialProvider::Sandbox,
+];
+
+/// Сколько job'ов worker публикует одновременно.
+///
+/// Переопределяется через env: `SOCIAL_WORKER_CONCURRENCY` (всего),
+/// `SOCIAL_WORKER_<PROVIDER>_CONCURRENCY` (например `SOCIAL_WORKER_INSTAGRAM_CONCURRENCY=2`),
+/// `SOCIAL_WORKER_SHUTDOWN_GRACE_SECS` — сколько ждать публикаций в работе при остановке.
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    pub concurrency: usize,
+    pub provider_limits: BTreeMap<&'static str, usize>,
+    pub shutdown_grace: Duration,
+}
+
+fn env_usize(key: &str) -> Option<usize> {
+    std::env::var(key).ok()?.trim().parse().ok()
+}
+
+impl WorkerConfig {
+    pub fn from_env() -> Self {
+        let concurrency = env_usize("SOCIAL_WORKER_CONCURRENCY").unwrap_or(4).max(1);
+
+        let provider_limits = PROVIDERS
+            .into_iter()
+            .map(|provider| {
+                // Instagram/Threads подолгу ждут контейнер и упираются в почасовые лимиты Meta
+                let default = match provider {
+                    SocialProvider::Telegram | SocialProvider::Vk => 2,
+                    SocialProvider::Instagram | SocialProvider::Threads => 1,
+                    SocialProvider::Sandbox => concurrency,
+                };
+                let key = format!("SOCIAL_WORKER_{}_CONCURRENCY", provider.as_str().to_uppercase());
+                let limit = env_usize(&key).unwrap_or(default).clamp(1, concurrency);
+                (provider.as_str(), limit)
+            })
+            .collect();
+
+        Self {
+            concurrency,
+            provider_limits,
+            shutdown_grace: Duration::from_secs(env_usize("SOCIAL_WORKER_SHUTDOWN_GRACE_SECS").unwrap_or(30) as u64),
+        }
+    }
+
+    /// Неизвестный провайдер ограничен одним слотом: такая job всё равно сразу уйдёт в `dead`.
+    pub fn limit_for(&self, provider: &str) -> usize {
+        self.provider_limits.get(provider).copied().unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Running,
+    /// Получен сигнал остановки: новые job'ы не берутся, публикации в работе дописываются.
+    Draining,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InFlightJob {
+    pub job_id: i64,
+    pub provider: String,
+    pub social_account_id: i64,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Снимок состояния worker'а этого процесса для админки.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerReport {
+    pub worker_id: String,
+    pub state: WorkerState,
+    pub started_at: DateTime<Utc>,
+    pub concurrency: usize,
+    pub provider_limits: BTreeMap<&'static str, usize>,
+    pub in_flight: Vec<InFlightJob>,
+    /// Job'ов отработано с запуска, включая неудачные попытки.
+    pub processed: u64,
+    pub last_poll_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Общее состояние worker'а; клон держит `AppState`, чтобы админка видела его без обращения к БД.
+#[derive(Clone)]
+pub struct WorkerStatus(Arc<Mutex<WorkerReport>>);
+
+impl WorkerStatus {
+    pub fn new(worker_id: &str, config: &WorkerConfig) -> Self {
+        Self(Arc::new(Mutex::new(WorkerReport {
+            worker_id: worker_id.to_string(),
+            state: WorkerState::Running,
+            started_at: Utc::now(),
+            concurrency: config.concurrency,
+            provider_limits: config.provider_limits.clone(),
+            in_flight: Vec::new(),
+            processed: 0,
+            last_poll_at: None,
+            last_error: None,
+        })))
+    }
+
+    pub fn report(&self) -> WorkerReport {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WorkerReport> {
+        // паника в чужой задаче не должна ронять админку
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_state(&self, state: WorkerState) {
+        self.lock().state = state;
+    }
+
+    pub fn polled(&self, error: Option<String>) {
+        let mut report = self.lock();
+        report.last_poll_at = Some(Utc::now());
+        if error.is_some() {
+            report.last_error = error;
+        }
+    }
+
+    pub fn in_flight_len(&self) -> usize {
+        self.lock().in_flight.len()
+    }
+
+    /// Провайдеры, у которых заняты все слоты.
+    pub fn busy_providers(&self, config: &WorkerConfig) -> Vec<String> {
+        let report = self.lock();
+        let mut busy: Vec<String> = Vec::new();
+        for job in &report.in_flight {
+            if busy.contains(&job.provider) {
+                continue;
+            }
+            let used = report.in_flight.iter().filter(|j| j.provider == job.provider).count();
+            if used >= config.limit_for(&job.provider) {
+                busy.push(job.provider.clone());
+            }
+        }
+        busy
+    }
+
+    /// Job снимается с учёта при drop guard'а — в том числе если задача упала или её прервали.
+    pub fn start(&self, job_id: i64, provider: &str, social_account_id: i64) -> InFlightGuard {
+        self.lock().in_flight.push(InFlightJob {
+            job_id,
+            provider: provider.to_string(),
+            social_account_id,
+            started_at: Utc::now(),
+        });
+        InFlightGuard {
+            status: self.clone(),
+            job_id,
+        }
+    }
+}
+
+pub struct InFlightGuard {
+    status: WorkerStatus,
+    job_id: i64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut report = self.status.lock();
+        report.in_flight.retain(|j| j.job_id != self.job_id);
+        report.processed += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_is_busy_when_its_slots_are_taken() {
+        let config = WorkerConfig {
+            concurrency: 4,
+            provider_limits: BTreeMap::from([("telegram", 2), ("instagram", 1)]),
+            shutdown_grace: Duration::from_secs(1),
+        };
+        let status = WorkerStatus::new("test", &config);
+
+        let first = status.start(1, "telegram", 10);
+        let _instagram = status.start(2, "instagram", 20);
+        assert_eq!(status.busy_providers(&config), ["instagram"]);
+
+        let _second = status.start(3, "telegram", 10);
+        let mut busy = status.busy_providers(&config);
+        busy.sort();
+        assert_eq!(busy, ["instagram", "telegram"]);
+
+        drop(first);
+        assert_eq!(status.busy_providers(&config), ["instagram"]);
+        assert_eq!(status.report().processed, 1);
+        assert_eq!(status.in_flight_len(), 2);
+    }
+}
@@ -1,8 +1,30 @@
 use diesel::prelude::*;
 use diesel::result::QueryResult;
 
+use serde::{Deserialize, Serialize};
+
 use crate::models::social::{NewSocialPostJob, SocialPostJob };
 use crate::schema::social_post_jobs;
+use crate::social::adapters::MediaItem;
+use crate::social::errors::ErrorKind;
+use crate::social::limits::blocked_accounts;
+use crate::social::retry::{RetryDecision, RetryPolicy};
+use crate::social::types::SocialProvider;
+
+/// `payload_json` job'а: переопределения для конкретной сети поверх полей новости.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// Медиа в порядке показа; важнее `image_url`.
+    #[serde(default, alias = "images", skip_serializing_if = "Option::is_none")]
+    pub media: Option<Vec<MediaItem>>,
+}
+
+/// Статусы, из которых job ещё может уйти в публикацию.
+pub const OPEN_JOB_STATUSES: [&str; 3] = ["pending", "scheduled", "failed"];
 
 pub fn create_social_post_job(
     conn: &mut PgConnection,
@@ -14,8 +36,9 @@ pub fn create_social_post_job(
         .get_result(conn)
 }
 
-use anyhow::{Result};
-use diesel::sql_types::{BigInt, Nullable, Text, Uuid as SqlUuid};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{Array, BigInt, Bool, Integer, Jsonb, Nullable, Text, Timestamptz, Uuid as SqlUuid};
 use uuid::Uuid;
 
 #[derive(Debug, QueryableByName)]
@@ -26,20 +49,11 @@ pub struct DueJobRow {
     #[diesel(sql_type = SqlUuid)]
     pub news_post_id: Uuid,
 
-    #[diesel(sql_type = Text)]
-    pub provider: String,
+    #[diesel(sql_type = BigInt)]
+    pub social_account_id: i64,
 
     #[diesel(sql_type = Text)]
-    pub account_name: String,
-
-    #[diesel(sql_type = Nullable<Text>)]
-    pub external_account_id: Option<String>,
-
-    #[diesel(sql_type = Nullable<Text>)]
-    pub access_token: Option<String>,
-
-    #[diesel(sql_type = Nullable<Text>)]
-    pub refresh_token: Option<String>,
+    pub provider: String,
 
     #[diesel(sql_type = Nullable<Text>)]
     pub title: Option<String>,
@@ -55,114 +69,431 @@ pub struct DueJobRow {
 
     #[diesel(sql_type = Nullable<Text>)]
     pub explanation_en: Option<String>,
+
+    #[diesel(sql_type = Text)]
+    pub kind: String,
+
+    #[diesel(sql_type = Text)]
+    pub level: String,
+
+    #[diesel(sql_type = Nullable<Text>)]
+    pub phonetic: Option<String>,
+
+    #[diesel(sql_type = Array<Nullable<Text>>)]
+    pub tags: Vec<Option<String>>,
+
+    #[diesel(sql_type = Nullable<Text>)]
+    pub url: Option<String>,
+
+    #[diesel(sql_type = Jsonb)]
+    pub payload_json: serde_json::Value,
+
+    /// Сколько неудачных попыток уже было; текущая — `retry_count + 1`.
+    #[diesel(sql_type = Integer)]
+    pub retry_count: i32,
+
+    #[diesel(sql_type = SqlUuid)]
+    pub idempotency_key: Uuid,
+
+    /// Не `NULL` — прошлая попытка оборвалась после отправки запроса провайдеру.
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub publish_started_at: Option<DateTime<Utc>>,
+
+    #[diesel(sql_type = Nullable<Jsonb>)]
+    pub publish_state: Option<serde_json::Value>,
 }
 
-pub fn claim_one_due_job(conn: &mut PgConnection) -> Result<Option<DueJobRow>> {
+#[derive(QueryableByName)]
+struct ReapedJob {
+    #[diesel(sql_type = BigInt)]
+    social_post_job_id: i64,
+}
+
+/// Ключ advisory lock'а, которым сериализуются захваты job'ов.
+const CLAIM_LOCK_KEY: i64 = 0x736f_6369_616c; // "social"
+
+/// Пока воркер держит job, `locked_until` продлевается heartbeat'ом.
+/// Аккаунты, упёршиеся в `settings_json.posting`, пропускаются до следующего прохода,
+/// job'ы провайдеров из `busy_providers` — пока у воркера не освободится слот.
+pub fn claim_one_due_job(
+    conn: &mut PgConnection,
+    worker_id: &str,
+    lease: std::time::Duration,
+    busy_providers: &[String],
+) -> Result<Option<DueJobRow>> {
+    conn.transaction(|conn| {
+        // захваты по очереди: иначе два воркера одновременно уложатся в один и тот же лимит аккаунта
+        diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+            .bind::<BigInt, _>(CLAIM_LOCK_KEY)
+            .execute(conn)?;
+        let blocked = blocked_accounts(conn, Utc::now())?;
+        claim_next(conn, worker_id, lease, &blocked, busy_providers)
+    })
+}
+
+/// Наступившая job с наибольшим приоритетом среди аккаунтов не из `blocked`.
+fn claim_next(
+    conn: &mut PgConnection,
+    worker_id: &str,
+    lease: std::time::Duration,
+    blocked: &[i64],
+    busy_providers: &[String],
+) -> Result<Option<DueJobRow>> {
     let sql = r#"
     WITH picked AS (
         SELECT spj.id
         FROM social_post_jobs spj
-        WHERE spj.status IN ('pending', 'scheduled', 'failed')
-          AND spj.scheduled_for <= now()
-          AND (spj.next_retry_at IS NULL OR spj.next_retry_at <= now())
-        ORDER BY spj.scheduled_for ASC, spj.id ASC
+        WHERE spj.scheduled_for <= now()
+          AND (
+                spj.status IN ('pending', 'scheduled')
+             OR (spj.status = 'failed' AND spj.next_retry_at <= now())
+          )
+          AND spj.social_account_id <> ALL($3)
+          -- выключенный аккаунт — пауза канала: его job'ы ждут включения
+          AND spj.social_account_id NOT IN (
+                SELECT id FROM social_accounts WHERE provider = ANY($4) OR NOT is_active
+          )
+        ORDER BY spj.priority DESC, spj.scheduled_for ASC, spj.id ASC
         FOR UPDATE SKIP LOCKED
         LIMIT 1
     ),
     updated AS (
         UPDATE social_post_jobs spj
         SET status = 'processing',
+            locked_by = $1,
+            locked_until = now() + make_interval(secs => $2),
             updated_at = now()
         WHERE spj.id IN (SELECT id FROM picked)
-        RETURNING spj.id, spj.news_post_id, spj.social_account_id
+        RETURNING spj.id, spj.news_post_id, spj.social_account_id, spj.payload_json, spj.retry_count,
+                  spj.idempotency_key, spj.publish_started_at, spj.publish_state
     )
     SELECT
         u.id AS job_id,
         u.news_post_id,
+        u.social_account_id,
         sa.provider,
-        sa.account_name,
-        sa.external_account_id,
-        sa.access_token,
-        sa.refresh_token,
         np.title,
         np.excerpt,
         np.body,
-        np.image_url
+        np.image_url,
+        np.explanation_en,
+        np.kind,
+        np.level,
+        np.phonetic,
+        np.tags,
+        np.url,
+        u.payload_json,
+        u.retry_count,
+        u.idempotency_key,
+        u.publish_started_at,
+        u.publish_state
     FROM updated u
     JOIN social_accounts sa ON sa.id = u.social_account_id
     JOIN news_posts np ON np.id = u.news_post_id
     "#;
 
-    let rows = diesel::sql_query(sql).load::<DueJobRow>(conn)?;
+    let rows = diesel::sql_query(sql)
+        .bind::<Text, _>(worker_id)
+        .bind::<BigInt, _>(lease.as_secs() as i64)
+        .bind::<Array<BigInt>, _>(blocked)
+        .bind::<Array<Text>, _>(busy_providers)
+        .load::<DueJobRow>(conn)?;
     Ok(rows.into_iter().next())
 }
 
+/// Heartbeat. `false` — аренду уже забрал reaper.
+pub fn extend_lease(
+    conn: &mut PgConnection,
+    job_id: i64,
+    worker_id: &str,
+    lease: std::time::Duration,
+) -> Result<bool> {
+    let updated = diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET locked_until = now() + make_interval(secs => $1)
+        WHERE id = $2 AND status = 'processing' AND locked_by = $3
+        "#,
+    )
+    .bind::<BigInt, _>(lease.as_secs() as i64)
+    .bind::<BigInt, _>(job_id)
+    .bind::<Text, _>(worker_id)
+    .execute(conn)?;
+
+    Ok(updated > 0)
+}
+
+/// Остановка воркера: job'ы, которые он так и не дописал, сразу возвращаются в очередь,
+/// не дожидаясь истечения аренды. Попыткой это не считается; если запрос к провайдеру
+/// уже уходил, `publish_started_at` остаётся и следующая попытка начнётся с проверки.
+pub fn release_leases(conn: &mut PgConnection, worker_id: &str) -> Result<Vec<i64>> {
+    let rows = diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET status = CASE WHEN retry_count > 0 THEN 'failed' ELSE 'pending' END,
+            next_retry_at = now(),
+            locked_by = NULL,
+            locked_until = NULL,
+            updated_at = now()
+        WHERE status = 'processing' AND locked_by = $1
+        RETURNING id AS social_post_job_id
+        "#,
+    )
+    .bind::<Text, _>(worker_id)
+    .load::<ReapedJob>(conn)?;
+
+    Ok(rows.into_iter().map(|r| r.social_post_job_id).collect())
+}
+
+/// Возвращает одну job в очередь без попытки: её аккаунт выключили уже после захвата.
+pub fn release_job(conn: &mut PgConnection, job_id: i64, worker_id: &str) -> Result<()> {
+    let updated = diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET status = CASE WHEN retry_count > 0 THEN 'failed' ELSE 'pending' END,
+            next_retry_at = now(),
+            locked_by = NULL,
+            locked_until = NULL,
+            updated_at = now()
+        WHERE id = $1 AND status = 'processing' AND locked_by = $2
+        "#,
+    )
+    .bind::<BigInt, _>(job_id)
+    .bind::<Text, _>(worker_id)
+    .execute(conn)?;
+
+    ensure_updated(updated, job_id, worker_id)
+}
+
+/// Фиксируется до запроса к провайдеру: если процесс упадёт, следующая попытка
+/// сначала выяснит, вышел ли пост.
+pub fn mark_publish_started(conn: &mut PgConnection, job_id: i64) -> Result<()> {
+    diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET publish_started_at = COALESCE(publish_started_at, now())
+        WHERE id = $1
+        "#,
+    )
+    .bind::<BigInt, _>(job_id)
+    .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn save_publish_state(conn: &mut PgConnection, job_id: i64, state: serde_json::Value) -> Result<()> {
+    diesel::sql_query("UPDATE social_post_jobs SET publish_state = $1 WHERE id = $2")
+        .bind::<Jsonb, _>(state)
+        .bind::<BigInt, _>(job_id)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[derive(QueryableByName)]
+struct ExpiredLease {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Integer)]
+    retry_count: i32,
+    #[diesel(sql_type = Text)]
+    provider: String,
+}
+
+/// Возвращает в очередь job'ы, чей воркер перестал продлевать аренду (упал или завис).
+/// Это считается неудачной попыткой по политике провайдера: повтор с backoff, а после
+/// `max_attempts` — `dead`, чтобы job, роняющий воркер, не крутился вечно.
+pub fn reap_expired_leases(conn: &mut PgConnection) -> Result<Vec<(i64, RetryDecision)>> {
+    conn.transaction(|conn| {
+        let expired = diesel::sql_query(
+            r#"
+            SELECT spj.id, spj.retry_count, sa.provider
+            FROM social_post_jobs spj
+            JOIN social_accounts sa ON sa.id = spj.social_account_id
+            WHERE spj.status = 'processing'
+              AND spj.locked_until < now()
+            FOR UPDATE OF spj SKIP LOCKED
+            "#,
+        )
+        .load::<ExpiredLease>(conn)?;
+
+        let mut reaped = Vec::with_capacity(expired.len());
+        for lease in expired {
+            let attempt_no = lease.retry_count + 1;
+            let decision = match SocialProvider::from_db(&lease.provider) {
+                Some(provider) => RetryPolicy::for_provider(provider).decide(ErrorKind::Transient, attempt_no, None),
+                None => RetryDecision::Dead,
+            };
+            let (status, retry_in) = match decision {
+                RetryDecision::RetryIn(delay) => ("failed", Some(delay.as_secs() as i64)),
+                RetryDecision::Dead => ("dead", None),
+            };
+
+            // publish_started_at остаётся: следующая попытка начнётся с проверки у провайдера
+            diesel::sql_query(
+                r#"
+                UPDATE social_post_jobs
+                SET status = $1,
+                    retry_count = $2,
+                    next_retry_at = now() + make_interval(secs => $3),
+                    error_message = 'lease expired: worker stopped while processing the job',
+                    locked_by = NULL,
+                    locked_until = NULL,
+                    updated_at = now()
+                WHERE id = $4
+                "#,
+            )
+            .bind::<Text, _>(status)
+            .bind::<Integer, _>(attempt_no)
+            .bind::<Nullable<BigInt>, _>(retry_in)
+            .bind::<BigInt, _>(lease.id)
+            .execute(conn)?;
+
+            insert_attempt(
+                conn,
+                lease.id,
+                attempt_no,
+                "failed",
+                None,
+                Some("lease expired".to_string()),
+                Some("lease_expired"),
+            )?;
+            reaped.push((lease.id, decision));
+        }
+        Ok(reaped)
+    })
+}
+
+/// Job уже не за этим worker'ом (аренду забрал reaper или админ): ошибка откатывает
+/// транзакцию вместе с попыткой, чтобы не осталось попытки без смены статуса.
+fn ensure_updated(updated: usize, job_id: i64, worker_id: &str) -> Result<()> {
+    if updated == 0 {
+        bail!("social job #{job_id} is no longer leased by {worker_id}");
+    }
+    Ok(())
+}
+
+/// Провайдер подтвердил публикацию — это записывается, даже если аренду успел забрать
+/// reaper: пока `publish_started_at` не сброшен, job ждёт именно этой проверки.
 pub fn mark_job_posted(
     conn: &mut PgConnection,
     job_id: i64,
+    worker_id: &str,
     external_post_id: Option<String>,
+    published_state: Option<serde_json::Value>,
 ) -> Result<()> {
-    diesel::sql_query(
+    let updated = diesel::sql_query(
         r#"
         UPDATE social_post_jobs
         SET status = 'posted',
             published_at = now(),
             external_post_id = $1,
             error_message = NULL,
+            locked_by = NULL,
+            locked_until = NULL,
+            publish_started_at = NULL,
+            publish_state = NULL,
+            published_state = $4,
             updated_at = now()
-        WHERE id = $2
+        WHERE id = $2 AND (locked_by = $3 OR publish_started_at IS NOT NULL)
         "#,
     )
     .bind::<Nullable<Text>, _>(external_post_id)
     .bind::<BigInt, _>(job_id)
+    .bind::<Text, _>(worker_id)
+    .bind::<Nullable<Jsonb>, _>(published_state)
     .execute(conn)?;
 
-    Ok(())
+    ensure_updated(updated, job_id, worker_id)
+}
+
+/// Dry-run завершён: payload уже в попытке, провайдеру ничего не ушло.
+pub fn mark_job_dry_run(conn: &mut PgConnection, job_id: i64, worker_id: &str) -> Result<()> {
+    let updated = diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET status = 'dry_run',
+            error_message = NULL,
+            next_retry_at = NULL,
+            locked_by = NULL,
+            locked_until = NULL,
+            publish_started_at = NULL,
+            publish_state = NULL,
+            updated_at = now()
+        WHERE id = $1 AND locked_by = $2
+        "#,
+    )
+    .bind::<BigInt, _>(job_id)
+    .bind::<Text, _>(worker_id)
+    .execute(conn)?;
+
+    ensure_updated(updated, job_id, worker_id)
 }
 
+/// Неудачная попытка с повтором через `retry_in`.
+/// `in_doubt` — запрос мог дойти до провайдера (обрыв сети после отправки),
+/// тогда следующая попытка начнётся с проверки, не вышел ли пост.
 pub fn mark_job_failed(
     conn: &mut PgConnection,
     job_id: i64,
+    worker_id: &str,
     error_message: String,
-    next_retry_minutes: Option<i32>,
+    retry_in: std::time::Duration,
+    in_doubt: bool,
 ) -> Result<()> {
-    match next_retry_minutes {
-        Some(minutes) => {
-            diesel::sql_query(
-                r#"
-                UPDATE social_post_jobs
-                SET status = 'failed',
-                    retry_count = retry_count + 1,
-                    error_message = $1,
-                    next_retry_at = now() + ($2 || ' minutes')::interval,
-                    updated_at = now()
-                WHERE id = $3
-                "#,
-            )
-            .bind::<Text, _>(error_message)
-            .bind::<diesel::sql_types::Integer, _>(minutes)
-            .bind::<BigInt, _>(job_id)
-            .execute(conn)?;
-        }
-        None => {
-            diesel::sql_query(
-                r#"
-                UPDATE social_post_jobs
-                SET status = 'failed',
-                    retry_count = retry_count + 1,
-                    error_message = $1,
-                    next_retry_at = NULL,
-                    updated_at = now()
-                WHERE id = $2
-                "#,
-            )
-            .bind::<Text, _>(error_message)
-            .bind::<BigInt, _>(job_id)
-            .execute(conn)?;
-        }
-    }
+    let updated = diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET status = 'failed',
+            retry_count = retry_count + 1,
+            error_message = $1,
+            next_retry_at = now() + make_interval(secs => $2),
+            publish_started_at = CASE WHEN $3 THEN publish_started_at END,
+            locked_by = NULL,
+            locked_until = NULL,
+            updated_at = now()
+        WHERE id = $4 AND locked_by = $5
+        "#,
+    )
+    .bind::<Text, _>(error_message)
+    .bind::<BigInt, _>(retry_in.as_secs() as i64)
+    .bind::<Bool, _>(in_doubt)
+    .bind::<BigInt, _>(job_id)
+    .bind::<Text, _>(worker_id)
+    .execute(conn)?;
 
-    Ok(())
+    ensure_updated(updated, job_id, worker_id)
+}
+
+/// Терминальная ошибка: попытки исчерпаны или повтор бессмыслен.
+pub fn mark_job_dead(
+    conn: &mut PgConnection,
+    job_id: i64,
+    worker_id: &str,
+    error_message: String,
+    in_doubt: bool,
+) -> Result<()> {
+    let updated = diesel::sql_query(
+        r#"
+        UPDATE social_post_jobs
+        SET status = 'dead',
+            retry_count = retry_count + 1,
+            error_message = $1,
+            next_retry_at = NULL,
+            publish_started_at = CASE WHEN $2 THEN publish_started_at END,
+            locked_by = NULL,
+            locked_until = NULL,
+            updated_at = now()
+        WHERE id = $3 AND locked_by = $4
+        "#,
+    )
+    .bind::<Text, _>(error_message)
+    .bind::<Bool, _>(in_doubt)
+    .bind::<BigInt, _>(job_id)
+    .bind::<Text, _>(worker_id)
+    .execute(conn)?;
+
+    ensure_updated(updated, job_id, worker_id)
 }
 
 pub fn insert_attempt(
@@ -172,19 +503,21 @@ pub fn insert_attempt(
     status: &str,
     response_body: Option<String>,
     error_message: Option<String>,
+    error_kind: Option<&str>,
 ) -> Result<()> {
     diesel::sql_query(
         r#"
         INSERT INTO social_post_attempts
-            (social_post_job_id, attempt_no, status, response_body, error_message)
-        VALUES ($1, $2, $3, $4, $5)
+            (social_post_job_id, attempt_no, status, response_body, error_message, error_kind)
+        VALUES ($1, $2, $3, $4, $5, $6)
         "#,
     )
     .bind::<BigInt, _>(job_id)
-    .bind::<diesel::sql_types::Integer, _>(attempt_no)
+    .bind::<Integer, _>(attempt_no)
     .bind::<Text, _>(status)
     .bind::<Nullable<Text>, _>(response_body)
     .bind::<Nullable<Text>, _>(error_message)
+    .bind::<Nullable<Text>, _>(error_kind)
     .execute(conn)?;
 
     Ok(())